@@ -0,0 +1,427 @@
+use std::{
+  collections::HashMap,
+  fs::{self, create_dir_all},
+  io::Write,
+  path::PathBuf,
+};
+
+use anyhow::Result;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+  builders::{Chapter, HtmlGen},
+  components::{ComponentArg, ComponentRegistry},
+  config::{Config, CoverMode},
+  toc::{Toc, TocEntry},
+  utils::{escape_html, get_files, get_hash, get_media_type},
+};
+
+/// Folder, inside the EPUB package, containing the publication files
+const PACKAGE_DIR: &str = "OEBPS";
+
+/// A file listed in the package manifest
+#[derive(Debug)]
+struct ManifestItem {
+  id: String,
+  href: String,
+  media_type: &'static str,
+  properties: Option<&'static str>,
+}
+
+impl ManifestItem {
+  /// Item of a file, `index` keeps the ids of similar hrefs apart,
+  /// e.g.: `a-b.xhtml` and `a_b.xhtml`
+  fn new(index: usize, href: &str, media_type: &'static str) -> Self {
+    let id: String = href
+      .chars()
+      .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+      .collect();
+
+    Self {
+      id: format!("item-{}-{}", index, id),
+      href: href.to_string(),
+      media_type,
+      properties: None,
+    }
+  }
+
+  fn with_properties(mut self, properties: &'static str) -> Self {
+    self.properties = Some(properties);
+    self
+  }
+}
+
+#[derive(Debug)]
+pub struct EpubGen {
+  pub config: Config,
+  pub path: PathBuf,
+  html_gen: HtmlGen,
+}
+
+impl EpubGen {
+  /// Creates an [EpubGen]. Assets and themes are referenced with
+  /// urls relative to the chapters, since they are packaged with the book.
+  pub fn new(config: Config, path: PathBuf, theme: Option<String>) -> Self {
+    let html_gen = HtmlGen::new(config.clone(), path.clone(), theme)
+      .with_component_arg("assets_path", ComponentArg::String("../assets".to_string()))
+      .with_component_arg("themes_path", ComponentArg::String("../themes".to_string()));
+
+    Self {
+      config,
+      path,
+      html_gen,
+    }
+  }
+
+  /// Add custom components, see [HtmlGen::with_components]
+  pub fn with_components(mut self, registry: ComponentRegistry) -> Self {
+    self.html_gen = self.html_gen.with_components(registry);
+    self
+  }
+
+  /// Book identifier, a hash of the title and authors, stable across builds of the same book
+  fn get_identifier(&self) -> String {
+    let mut source = self.config.title.clone();
+    for author in self.config.authors.iter().flatten() {
+      source.push('\n');
+      source.push_str(author);
+    }
+    format!("urn:wordsmith:{}", get_hash(source.as_bytes()))
+  }
+
+  /// Wrap content into an XHTML document
+  fn get_xhtml(&self, title: &str, head: &str, body: &str) -> String {
+    format!(
+      r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{lang}" lang="{lang}">
+<head><meta charset="utf-8" /><title>{title}</title>{head}</head>
+<body>{body}</body>
+</html>"#,
+      title = escape_html(title),
+      lang = escape_html(self.config.get_language())
+    )
+  }
+
+  /// Cover page: cover image if it exists, otherwise the title page of the template.
+  /// The none mode has no cover page
+  fn get_cover_xhtml(&self, head: &str) -> Result<Option<String>> {
+    let body = match (self.config.cover.mode, self.html_gen.get_cover_image()) {
+      (CoverMode::None, _) => return Ok(None),
+      (CoverMode::Image, Some(image)) => format!(
+        r#"<div class="cover"><img src="../assets/images/{}" alt="{}" /></div>"#,
+        escape_html(image),
+        escape_html(&self.config.title)
+      ),
+      _ => format!(
+        r#"<div class="cover">{}</div>"#,
+        self.html_gen.get_cover_title_html()?
+      ),
+    };
+
+    Ok(Some(self.get_xhtml(&self.config.title, head, &body)))
+  }
+
+  /// Back cover page, if configured
+  fn get_back_cover_xhtml(&self, head: &str) -> Result<Option<String>> {
+    Ok(self.html_gen.get_back_cover_image()?.map(|image| {
+      let body = format!(
+        r#"<div class="back-cover"><img src="../assets/images/{}" alt="" /></div>"#,
+        escape_html(image)
+      );
+      self.get_xhtml(&self.config.title, head, &body)
+    }))
+  }
+
+  /// Navigation document, built from the headings of all chapters
+  fn get_nav_xhtml(&self, toc: &Toc, links: &HashMap<String, String>) -> String {
+    let list = match toc.is_empty() {
+      // The navigation must contain a list, e.g.: for a book without chapters
+      true => format!(
+        r##"<ol><li><a href="#toc">{}</a></li></ol>"##,
+        escape_html(&self.config.toc.title)
+      ),
+      false => toc.to_list_html(self.config.toc.depth, |entry| {
+        links.get(&entry.id).cloned().unwrap_or_default()
+      }),
+    };
+
+    let body = format!(
+      r#"<nav epub:type="toc" id="toc"><h1>{}</h1>{}</nav>"#,
+      escape_html(&self.config.toc.title),
+      list
+    );
+
+    self.get_xhtml(&self.config.toc.title, "", &body)
+  }
+
+  /// Package document: metadata, manifest and reading order (spine)
+  fn get_package_opf(&self, items: &[ManifestItem], spine: &[String]) -> String {
+    let mut metadata = String::new();
+    metadata.push_str(&format!(
+      r#"<dc:identifier id="book-id">{}</dc:identifier>"#,
+      self.get_identifier()
+    ));
+    metadata.push_str(&format!(
+      "<dc:title>{}</dc:title>",
+      escape_html(&self.config.title)
+    ));
+    for author in self.config.get_author_names() {
+      metadata.push_str(&format!(
+        "<dc:creator>{}</dc:creator>",
+        escape_html(&author)
+      ));
+    }
+    metadata.push_str(&format!(
+      "<dc:language>{}</dc:language>",
+      escape_html(self.config.get_language())
+    ));
+    if let Some(subject) = &self.config.subject {
+      metadata.push_str(&format!(
+        "<dc:description>{}</dc:description>",
+        escape_html(subject)
+      ));
+    }
+    for keyword in self.config.keywords.iter().flatten() {
+      metadata.push_str(&format!(
+        "<dc:subject>{}</dc:subject>",
+        escape_html(keyword)
+      ));
+    }
+    if let Some(publisher) = &self.config.publisher {
+      metadata.push_str(&format!(
+        "<dc:publisher>{}</dc:publisher>",
+        escape_html(publisher)
+      ));
+    }
+    metadata.push_str(&format!(
+      r#"<meta property="dcterms:modified">{}</meta>"#,
+      chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
+    ));
+
+    let manifest: String = items
+      .iter()
+      .map(|item| {
+        let properties = match item.properties {
+          Some(properties) => format!(r#" properties="{}""#, properties),
+          None => String::new(),
+        };
+        format!(
+          r#"<item id="{}" href="{}" media-type="{}"{} />"#,
+          item.id,
+          escape_html(&item.href),
+          item.media_type,
+          properties
+        )
+      })
+      .collect();
+
+    let spine: String = spine
+      .iter()
+      .map(|id| format!(r#"<itemref idref="{}" />"#, id))
+      .collect();
+
+    format!(
+      r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id" xml:lang="{lang}">
+<metadata xmlns:dc="http://purl.org/dc/elements/1.1/">{metadata}</metadata>
+<manifest>{manifest}</manifest>
+<spine>{spine}</spine>
+</package>"#,
+      lang = escape_html(self.config.get_language())
+    )
+  }
+
+  fn get_container_xml(&self) -> String {
+    format!(
+      r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+<rootfiles><rootfile full-path="{}/content.opf" media-type="application/oebps-package+xml" /></rootfiles>
+</container>"#,
+      PACKAGE_DIR
+    )
+  }
+
+  /// Return the chapter file name, e.g.: `001-introduction.xhtml`
+  fn get_chapter_file(&self, chapter: &Chapter) -> String {
+    format!("{}.xhtml", chapter.get_file_stem())
+  }
+
+  /// Return the chapter file name inside the package, e.g.: `text/001-introduction.xhtml`
+  fn get_chapter_href(&self, chapter: &Chapter) -> String {
+    format!("text/{}", self.get_chapter_file(chapter))
+  }
+
+  /// Collect project files (assets and theme stylesheets) to be packaged.
+  /// Files that are not supported by EPUB readers are ignored.
+  fn get_resources(&self) -> Result<Vec<(ManifestItem, PathBuf)>> {
+    let mut resources = Vec::new();
+
+    let cover = self
+      .html_gen
+      .get_cover_image()
+      .map(|image| format!("assets/images/{}", image));
+
+    let files = [
+      get_files(&self.path.join("assets"))?,
+      get_files(&self.path.join("themes"))?,
+    ];
+    for (index, file) in files.into_iter().flatten().enumerate() {
+      // The ebook uses the cached diagrams, scripts are not packaged
+      let media_type = match get_media_type(&file) {
+        Some("text/javascript") | None => continue,
+        Some(media_type) => media_type,
+      };
+
+      let href = file
+        .strip_prefix(&self.path)?
+        .to_string_lossy()
+        .replace('\\', "/");
+
+      let mut item = ManifestItem::new(index, &href, media_type);
+      if cover.as_ref() == Some(&href) {
+        item = item.with_properties("cover-image");
+      }
+
+      resources.push((item, file));
+    }
+
+    Ok(resources)
+  }
+
+  /// Package all chapters, resources, navigation and metadata into an EPUB file
+  pub fn generate(&self, epub_file: PathBuf) -> Result<()> {
+    log::debug!("Generating EPUB");
+
+    let head = self
+      .html_gen
+      .compile_components(&self.html_gen.get_theme_head_html()?)?;
+
+    let mut items: Vec<ManifestItem> = Vec::new();
+    let mut spine: Vec<String> = Vec::new();
+    let mut documents: Vec<(String, String)> = Vec::new();
+
+    if let Some(xhtml) = self.get_cover_xhtml(&head)? {
+      let cover = ManifestItem::new(0, "text/cover.xhtml", "application/xhtml+xml");
+      spine.push(cover.id.clone());
+      documents.push((cover.href.clone(), xhtml));
+      items.push(cover);
+    }
+
+    let mut toc = Toc::default();
+    // Link of each navigation entry, by heading id
+    let mut links: HashMap<String, String> = HashMap::new();
+
+    let chapters = self.html_gen.get_chapters()?;
+    // Chapters are in the same folder
+    let references = self
+      .html_gen
+      .get_references(&chapters, |chapter| self.get_chapter_file(chapter))?;
+
+    for (index, chapter) in chapters.iter().enumerate() {
+      let href = self.get_chapter_href(chapter);
+      let chapter_toc = chapter.get_toc();
+
+      // Links in the navigation document are relative to the package folder
+      for entry in chapter_toc.entries() {
+        links.insert(entry.id.clone(), format!("{}#{}", href, entry.id));
+      }
+
+      let title = chapter
+        .get_title()
+        .unwrap_or_else(|| self.config.title.clone());
+      let depth = self.config.toc.depth;
+      match chapter.front_matter.toc && chapter_toc.entries().iter().any(|e| e.level <= depth) {
+        true => toc.extend(chapter_toc),
+        // Every chapter is listed in the navigation, even without headings
+        false => {
+          links.insert(href.clone(), href.clone());
+          toc.push(TocEntry {
+            level: 1,
+            id: href.clone(),
+            title: escape_html(&title),
+            number: None,
+          });
+        }
+      }
+
+      let body = self.html_gen.compile_chapter(chapter, &references)?;
+      let mut item = ManifestItem::new(index, &href, "application/xhtml+xml");
+      // Reading systems need to know which documents contain MathML or SVG
+      match (body.contains("<math "), body.contains("<svg")) {
+        (true, true) => item = item.with_properties("mathml svg"),
+        (true, false) => item = item.with_properties("mathml"),
+        (false, true) => item = item.with_properties("svg"),
+        (false, false) => {}
+      }
+      spine.push(item.id.clone());
+      documents.push((href, self.get_xhtml(&title, &head, &body)));
+      items.push(item);
+    }
+
+    if let Some(xhtml) = self.get_back_cover_xhtml(&head)? {
+      let back_cover = ManifestItem::new(0, "text/back-cover.xhtml", "application/xhtml+xml");
+      spine.push(back_cover.id.clone());
+      documents.push((back_cover.href.clone(), xhtml));
+      items.push(back_cover);
+    }
+
+    let nav = ManifestItem::new(0, "nav.xhtml", "application/xhtml+xml").with_properties("nav");
+    documents.push((nav.href.clone(), self.get_nav_xhtml(&toc, &links)));
+    items.push(nav);
+
+    let resources = self.get_resources()?;
+
+    log::debug!("Writing {}", epub_file.display());
+    if let Some(parent) = epub_file.parent() {
+      create_dir_all(parent)?;
+    }
+
+    let mut zip = ZipWriter::new(fs::File::create(&epub_file)?);
+    let options = SimpleFileOptions::default();
+
+    // The mimetype file must be the first entry and must not be compressed
+    zip.start_file(
+      "mimetype",
+      options.compression_method(CompressionMethod::Stored),
+    )?;
+    zip.write_all(b"application/epub+zip")?;
+
+    zip.start_file("META-INF/container.xml", options)?;
+    zip.write_all(self.get_container_xml().as_bytes())?;
+
+    for (href, content) in &documents {
+      zip.start_file(format!("{}/{}", PACKAGE_DIR, href), options)?;
+      zip.write_all(content.as_bytes())?;
+    }
+
+    for (item, file) in &resources {
+      zip.start_file(format!("{}/{}", PACKAGE_DIR, item.href), options)?;
+      zip.write_all(&fs::read(file)?)?;
+    }
+
+    items.extend(resources.into_iter().map(|(item, _)| item));
+
+    zip.start_file(format!("{}/content.opf", PACKAGE_DIR), options)?;
+    zip.write_all(self.get_package_opf(&items, &spine).as_bytes())?;
+
+    zip.finish()?;
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_manifest_item_id() {
+    let item = ManifestItem::new(0, "text/001-introduction.xhtml", "application/xhtml+xml");
+    let dash = ManifestItem::new(1, "text/a-b.xhtml", "application/xhtml+xml");
+    let underscore = ManifestItem::new(2, "text/a_b.xhtml", "application/xhtml+xml");
+
+    assert_eq!(item.id, "item-0-text-001-introduction-xhtml");
+    assert_ne!(dash.id, underscore.id);
+  }
+}
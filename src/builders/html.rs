@@ -1,16 +1,29 @@
 use crate::{
-  components::{ComponentArg, Components, BREAK_PAGE_HTML},
-  config::Config,
+  captions::{self, Caption, CaptionNumbers},
+  components::{ComponentArg, ComponentRegistry, Components, RefTag, BREAK_PAGE_HTML},
+  config::{Config, CoverMode},
+  diagrams::{self, DiagramCache, DiagramKind},
   error::WordsmithError,
+  front_matter::FrontMatter,
+  highlight::Highlighter,
+  manifest::{self, ContentFile},
+  math,
+  references::{Label, LabelKind, References},
+  toc::{HeadingNumbers, Toc},
+  utils::{escape_html, unescape_html},
 };
 use anyhow::Result;
-use comrak::{markdown_to_html, ComrakExtensionOptions, ComrakOptions, ComrakRenderOptions};
+use comrak::{
+  markdown_to_html_with_plugins, ComrakExtensionOptions, ComrakOptions, ComrakPlugins,
+  ComrakRenderOptions,
+};
 use lazy_static::lazy_static;
 use std::{
   collections::HashMap,
-  fs::{self, create_dir_all, read_dir, read_to_string, remove_dir_all},
-  path::PathBuf,
+  fs::{self, create_dir_all, read_to_string, remove_file},
+  path::{Path, PathBuf},
 };
+use url::Url;
 
 // TODO: Extract html/css stuff from here(?)
 
@@ -19,20 +32,72 @@ lazy_static! {
     static ref DEFAULT_THEME: &'static str = "light";
 }
 
+/// Title page of the template cover mode, when no template is configured
+const DEFAULT_COVER_TEMPLATE: &str = r#"<div class="cover-title-page"><div class="cover-title">{title}</div><div class="cover-subtitle">{subtitle}</div><div class="cover-authors">{authors}</div></div>"#;
+
+/// A content file rendered to HTML
+#[derive(Debug, Clone)]
+pub struct Chapter {
+  /// Markdown source file
+  pub path: PathBuf,
+  /// Source file relative to the content folder, e.g.: `basics/setup.md`
+  pub file: String,
+  /// Title of the part containing the chapter
+  pub part: Option<String>,
+  /// Metadata of the source file
+  pub front_matter: FrontMatter,
+  /// Generated HTML, components are rendered by [HtmlGen::compile_chapter]
+  pub html: String,
+  /// Labels of the headings, figures and tables, e.g.: `{#setup}`
+  pub labels: Vec<Label>,
+  /// Numbered captions of the figures and tables
+  pub captions: Vec<Caption>,
+}
+
+impl Chapter {
+  /// Plain text title of the front matter, or of the first heading
+  pub fn get_title(&self) -> Option<String> {
+    self.front_matter.title.clone().or_else(|| {
+      Toc::from_html(&self.html)
+        .entries()
+        .first()
+        .map(|entry| unescape_html(&entry.title))
+    })
+  }
+
+  /// Headings of the chapter, the front matter title replaces the first h1 title
+  pub fn get_toc(&self) -> Toc {
+    let mut toc = Toc::from_html(&self.html);
+    if let Some(title) = &self.front_matter.title {
+      toc.set_chapter_title(title);
+    }
+    toc
+  }
+
+  /// Source file without extension, folders are joined with `-`,
+  /// e.g.: `001-introduction`, `basics-setup`
+  pub fn get_file_stem(&self) -> String {
+    manifest::get_file_stem(&self.file)
+  }
+}
+
 #[derive(Debug, Clone)]
-pub struct HtmlGen<'a> {
+pub struct HtmlGen {
   pub theme: Option<String>,
   pub config: Config,
   pub path: PathBuf,
-  components: Components<'a>,
+  components: Components,
+  diagram_cache: DiagramCache,
+  /// Load diagram scripts from urls relative to the project folder instead of `file://` urls
+  relative_script_urls: bool,
 }
 
-impl<'a> HtmlGen<'a> {
+impl HtmlGen {
   /// Creates a [Builder] with provided theme, document configurations,
   /// and the target path to build the project
   pub fn new(config: Config, path: PathBuf, theme: Option<String>) -> Self {
     // Register list of components
-    let components = Components::new(HashMap::from([
+    let args = HashMap::from([
       (
         "assets_path".to_string(),
         ComponentArg::String(path.join("assets").display().to_string()),
@@ -41,21 +106,56 @@ impl<'a> HtmlGen<'a> {
         "themes_path".to_string(),
         ComponentArg::String(path.join("themes").display().to_string()),
       ),
-    ]));
+    ]);
+    let components = Components::new(args, &path, &config.components);
+    let diagram_cache = DiagramCache::new(&path, &config.diagrams);
 
     Self {
       theme,
       config,
       path,
       components,
+      diagram_cache,
+      relative_script_urls: false,
     }
   }
 
+  /// Override a component argument, e.g.: to use relative `assets_path` urls
+  pub fn with_component_arg(mut self, key: &str, value: ComponentArg) -> Self {
+    self.components = self.components.with_arg(key, value);
+    self
+  }
+
+  /// Load diagram scripts from urls relative to the project folder, e.g.: when the page
+  /// is served over HTTP, where browsers block `file://` scripts
+  pub fn with_relative_script_urls(mut self) -> Self {
+    self.relative_script_urls = true;
+    self
+  }
+
+  /// Add custom components, registered after the built-in and project components.
+  ///
+  /// With the same name, the component with the highest priority is used, e.g.:
+  /// register with [PROJECT_PRIORITY](crate::PROJECT_PRIORITY) to replace project templates
+  pub fn with_components(mut self, registry: ComponentRegistry) -> Self {
+    self.components = self.components.with_registry(registry);
+    self
+  }
+
   /// Return the full path relative to the current working directory.
   fn get_path(&self, path: &str) -> PathBuf {
     self.path.join(path)
   }
 
+  /// Path relative to the project folder, used in error messages
+  fn get_relative_path(&self, path: &Path) -> String {
+    path
+      .strip_prefix(&self.path)
+      .unwrap_or(path)
+      .display()
+      .to_string()
+  }
+
   /// Return the folder that should contain all project builds/generated files.
   fn get_output_path(&self) -> PathBuf {
     self.get_path("output")
@@ -73,17 +173,23 @@ impl<'a> HtmlGen<'a> {
     DEFAULT_THEME.to_string()
   }
 
-  /// Check if output path exists and remove its contents
-  fn clean_output_folder(&self) -> Result<()> {
-    log::debug!("Cleaning output folder");
-    if self.get_output_path().exists() {
-      remove_dir_all(self.get_output_path())?;
+  /// Remove the HTML file of a previous build.
+  /// Other files of the output folder belong to other builds (EPUB, site) and are kept
+  fn clean_output_file(&self) -> Result<()> {
+    log::debug!("Cleaning output file");
+    let html_file = self.get_output_file("html.html");
+    if html_file.exists() {
+      remove_file(html_file)?;
     }
     Ok(())
   }
 
-  /// Transform markdown content into HTML
-  fn transform_md_to_html(&self, markdown: &str) -> String {
+  /// Transform markdown content into HTML.
+  ///
+  /// Fenced code blocks are highlighted unless disabled in the configuration,
+  /// `$...$` and `$$...$$` formulas are typeset as MathML and diagram blocks
+  /// use their cached SVG. `file` is the source named by formula errors
+  fn transform_md_to_html(&self, markdown: &str, file: &str) -> Result<String> {
     let options = &ComrakOptions {
       extension: ComrakExtensionOptions {
         strikethrough: true,
@@ -104,7 +210,25 @@ impl<'a> HtmlGen<'a> {
       },
       ..ComrakOptions::default()
     };
-    markdown_to_html(markdown, options)
+
+    let highlighter = Highlighter::new(self.config.highlight.line_numbers);
+    let mut plugins = ComrakPlugins::default();
+    if self.config.highlight.enabled {
+      plugins.render.codefence_syntax_highlighter = Some(&highlighter);
+    }
+
+    let (markdown, diagrams) = diagrams::extract_diagrams(markdown, &self.config.diagrams);
+    let (markdown, formulas) = match self.config.math {
+      true => math::extract_math(&markdown),
+      false => (markdown, Vec::new()),
+    };
+    let html = markdown_to_html_with_plugins(&markdown, options, &plugins);
+    let html = math::render_math(&html, &formulas, file)?;
+    Ok(diagrams::render_diagrams(
+      &html,
+      &diagrams,
+      &self.diagram_cache,
+    ))
   }
 
   /// Load theme HTML from themes folder
@@ -118,70 +242,335 @@ impl<'a> HtmlGen<'a> {
     Ok(read_to_string(theme_path)?)
   }
 
-  /// Build cover image HTML
+  /// Cover image of the image mode, if the file exists
+  pub fn get_cover_image(&self) -> Option<&str> {
+    let cover = &self.config.cover;
+    let image = self.get_path("assets/images").join(&cover.filename);
+    match cover.mode == CoverMode::Image && !cover.filename.is_empty() && image.exists() {
+      true => Some(&cover.filename),
+      false => None,
+    }
+  }
+
+  /// Back cover image, if configured.
   ///
-  /// If there's no cover image the document title will be used
-  /// as a fallback.
-  pub fn get_cover_html(&self) -> String {
-    log::debug!("Building cover");
+  /// Return [WordsmithError::CoverNotFound] error if the file does not exist
+  pub fn get_back_cover_image(&self) -> Result<Option<&str>> {
+    let back = match &self.config.cover.back {
+      Some(back) => back,
+      None => return Ok(None),
+    };
+    let image = self.get_path("assets/images").join(back);
+    if !image.exists() {
+      return Err(WordsmithError::CoverNotFound(format!("{}", image.display())).into());
+    }
+    Ok(Some(back))
+  }
+
+  /// Title page of the template mode, also used when the cover image does not exist.
+  /// `{title}`, `{subtitle}` and `{authors}` are replaced in the template.
+  ///
+  /// Return [WordsmithError::CoverNotFound] error if the template does not exist
+  pub fn get_cover_title_html(&self) -> Result<String> {
+    let template = match &self.config.cover.template {
+      Some(template) => {
+        let template_path = self.get_path(template);
+        if !template_path.exists() {
+          return Err(WordsmithError::CoverNotFound(format!("{}", template_path.display())).into());
+        }
+        read_to_string(template_path)?
+      }
+      None => DEFAULT_COVER_TEMPLATE.to_string(),
+    };
 
-    let image_src = self
-      .get_path("assets/images/")
-      .join(&self.config.cover.filename);
+    let cover = &self.config.cover;
+    Ok(
+      template
+        .replace("{title}", &escape_html(&self.config.title))
+        .replace(
+          "{subtitle}",
+          &escape_html(cover.subtitle.as_deref().unwrap_or_default()),
+        )
+        .replace(
+          "{authors}",
+          &escape_html(&self.config.get_author_names().join(", ")),
+        ),
+    )
+  }
 
-    if !image_src.exists() {
-      return format!(r#"<h1>{}</1>"#, &self.config.title);
+  /// Area of the cover, placed with the cover position offsets and dimensions
+  fn get_cover_box_html(&self, content: &str) -> String {
+    let cover = &self.config.cover;
+    let (left, top, right, bottom) = cover.position.get_values();
+    let mut style = format!(
+      "left:{}mm;top:{}mm;right:{}mm;bottom:{}mm;",
+      left, top, right, bottom
+    );
+    let (width, height) = cover.dimension.get_values();
+    if width > 0.0 && height > 0.0 {
+      style.push_str(&format!("width:{}mm;height:{}mm;", width, height));
     }
+    format!(
+      r#"<div class="cover-box" style="{}">{}</div>"#,
+      style, content
+    )
+  }
 
-    // Get cover dimensions
-    let (width, height) = &self.config.cover.dimension.get_values();
-
+  fn get_cover_image_html(&self, image: &str) -> String {
     format!(
-      r#"
-      <div style="width:{w}mm;height:{h}mm;" class="cover">
-        <img src="{src}" style="width:{w}mm;height:{h}mm;" alt="{alt}" />
-      </div>
-    "#,
-      src = &image_src.display(),
-      alt = &self.config.title,
-      w = &width,
-      h = &height
+      r#"<img src="{}/images/{}" alt="{}" />"#,
+      self.components.get_string_arg("assets_path"),
+      escape_html(image),
+      escape_html(&self.config.title)
     )
   }
 
-  /// Turn all your markdown files into HTML and concatenate
-  /// them into one HTML response.
-  pub fn get_content_html(&self) -> Result<String> {
-    let mut content: Vec<String> = Vec::new();
+  /// Build the cover page HTML, followed by a page break.
+  ///
+  /// The image mode falls back to the title page when the image does not exist,
+  /// the none mode has no cover page
+  pub fn get_cover_html(&self) -> Result<String> {
+    log::debug!("Building cover");
 
-    let mut paths: Vec<_> = read_dir(self.get_path("content"))?
-      .map(|d| d.unwrap())
-      .collect();
+    let content = match (self.config.cover.mode, self.get_cover_image()) {
+      (CoverMode::None, _) => return Ok(String::new()),
+      (CoverMode::Image, Some(image)) => self.get_cover_image_html(image),
+      _ => self.get_cover_title_html()?,
+    };
 
-    paths.sort_by_key(|d| d.path());
+    Ok(format!(
+      r#"<div class="cover">{}</div>{}"#,
+      self.get_cover_box_html(&content),
+      *BREAK_PAGE_HTML
+    ))
+  }
 
-    for entry in paths {
-      let metadata = &entry.metadata()?;
-      let path = &entry.path();
-      let file_extension = &path.extension();
+  /// Build the back cover page HTML, empty without back cover.
+  ///
+  /// The page is as high as the paper: the `back-cover` page of the theme has no
+  /// margins in every print, and the document margins (body padding) are removed
+  pub fn get_back_cover_html(&self) -> Result<String> {
+    let image = match self.get_back_cover_image()? {
+      Some(image) => image,
+      None => return Ok(String::new()),
+    };
+
+    let (_, page_height) = self.config.document.get_page_dimensions().get_values();
+    let (left, _, right, bottom) = self.config.document.margins.get_values();
+    Ok(format!(
+      r#"<div class="back-cover" style="height:{}mm;margin:0 -{}mm -{}mm -{}mm;">{}</div>"#,
+      page_height,
+      right,
+      bottom,
+      left,
+      self.get_cover_box_html(&self.get_cover_image_html(image))
+    ))
+  }
+
+  /// Return the markdown files of the content folder in reading order,
+  /// see [manifest::get_content_files]
+  pub fn get_content_files(&self) -> Result<Vec<ContentFile>> {
+    manifest::get_content_files(&self.get_path("content"), self.config.chapters.as_deref())
+  }
 
-      // Skip directories and files that are not markdown
-      if file_extension.is_none() || !metadata.is_file() {
+  /// Components of a chapter, front matter values are added to the arguments
+  fn get_chapter_components(&self, components: &Components, chapter: &FrontMatter) -> Components {
+    components.clone().with_args(chapter.get_args())
+  }
+
+  /// Turn every markdown file into HTML, drafts are skipped.
+  ///
+  /// Heading ids are unique across all chapters, headings are numbered
+  /// when numbering is enabled, figures and tables when captions are enabled.
+  pub fn get_chapters(&self) -> Result<Vec<Chapter>> {
+    let mut chapters: Vec<Chapter> = Vec::new();
+    for file in self.get_content_files()? {
+      let path = self.get_path("content").join(&file.file);
+      let relative_path = self.get_relative_path(&path);
+      let (front_matter, raw_content) =
+        FrontMatter::parse(&read_to_string(&path)?, &relative_path)?;
+      if front_matter.draft {
+        log::debug!("Skipping draft {}", relative_path);
         continue;
       }
-      if !matches!(file_extension.unwrap().to_str(), Some("md")) {
-        continue;
+
+      let compiled_content = self
+        .get_chapter_components(&self.components, &front_matter)
+        .compile_source(&raw_content, &relative_path)?;
+
+      let (html, labels) =
+        Label::parse_html(&self.transform_md_to_html(&compiled_content, &relative_path)?);
+      chapters.push(Chapter {
+        path,
+        file: file.file,
+        part: file.part,
+        front_matter,
+        html,
+        labels,
+        captions: Vec::new(),
+      });
+    }
+
+    let content: Vec<String> = chapters.iter().map(|c| c.html.clone()).collect();
+    for (chapter, html) in chapters.iter_mut().zip(Toc::unique_heading_ids(&content)) {
+      chapter.html = html;
+    }
+
+    let numbering = &self.config.numbering;
+    if numbering.enabled {
+      let mut numbers = HeadingNumbers::new(numbering.depth, &numbering.chapter_label);
+      for chapter in chapters.iter_mut().filter(|c| self.is_numbered(c)) {
+        chapter.html = numbers.number_html(&chapter.html);
       }
+    }
 
-      let raw_content = read_to_string(path)?;
-      let compiled_content = self.components.compile_tag_assets_path(&raw_content)?;
+    if self.config.captions.enabled {
+      let mut numbers = CaptionNumbers::new(&self.config.captions);
+      for chapter in chapters.iter_mut() {
+        (chapter.html, chapter.captions) = numbers.caption_html(&chapter.html);
+      }
+    }
 
-      content.push(self.transform_md_to_html(&compiled_content))
+    for chapter in chapters.iter_mut() {
+      let toc = chapter.get_toc();
+      Label::set_headings(&mut chapter.labels, &toc);
+      Label::set_captions(&mut chapter.labels, &chapter.captions);
     }
 
+    Ok(chapters)
+  }
+
+  /// Check if the headings of a chapter are numbered, chapters can opt out
+  /// with the front matter or the `numbering.exclude` configuration
+  fn is_numbered(&self, chapter: &Chapter) -> bool {
+    chapter.front_matter.numbered && !self.config.numbering.exclude.contains(&chapter.file)
+  }
+
+  /// Headings of the chapters, `listed` keeps the chapters included in the table of contents
+  pub fn get_toc(&self, chapters: &[Chapter], listed: bool) -> Toc {
+    let mut toc = Toc::default();
+    for chapter in chapters {
+      if chapter.front_matter.toc || !listed {
+        toc.extend(chapter.get_toc());
+      }
+    }
+    toc
+  }
+
+  /// Labels of the chapters, linked to `get_page(chapter)#label`. The page is empty
+  /// when all chapters are in the same document.
+  ///
+  /// Return [WordsmithError::InvalidReferences] for labels defined twice
+  /// and `@ref` tags to unknown labels
+  pub fn get_references<F>(&self, chapters: &[Chapter], get_page: F) -> Result<References>
+  where
+    F: Fn(&Chapter) -> String,
+  {
+    let mut references = References::new();
+    let mut files: HashMap<&str, String> = HashMap::new();
+    let mut issues: Vec<String> = Vec::new();
+
+    for chapter in chapters {
+      let file = self.get_relative_path(&chapter.path);
+      for label in &chapter.labels {
+        if let Some(other) = files.get(label.id.as_str()) {
+          issues.push(format!(
+            "label {} is defined in {} and {}",
+            label.id, other, file
+          ));
+          continue;
+        }
+        files.insert(&label.id, file.clone());
+        references.insert(label, &format!("{}#{}", get_page(chapter), label.id));
+      }
+    }
+
+    for chapter in chapters {
+      for attributes in self.components.get_tags(&chapter.html, "ref")? {
+        let label = attributes.get_string("0").unwrap_or_default();
+        if references.get(&label).is_none() {
+          issues.push(format!(
+            "@ref({}) in {}: label not found",
+            label,
+            self.get_relative_path(&chapter.path)
+          ));
+        }
+      }
+    }
+
+    match issues.is_empty() {
+      true => Ok(references),
+      false => Err(WordsmithError::InvalidReferences(issues).into()),
+    }
+  }
+
+  /// Components rendering `@ref` tags with the given references
+  fn get_reference_components(
+    &self,
+    components: &Components,
+    references: &References,
+  ) -> Result<Components> {
+    let mut registry = ComponentRegistry::new();
+    registry.register("ref", RefTag::new(references.clone()))?;
+    Ok(components.clone().with_registry(registry))
+  }
+
+  fn render_chapter(&self, components: &Components, chapter: &Chapter) -> Result<String> {
+    let html = self
+      .get_chapter_components(components, &chapter.front_matter)
+      .compile_all(&chapter.html)?;
+
+    Ok(match &chapter.front_matter.class {
+      Some(class) => format!(
+        r#"<section class="chapter {}">{}</section>"#,
+        escape_html(class),
+        html
+      ),
+      None => html,
+    })
+  }
+
+  /// Render the components of a chapter, with the values of its front matter
+  /// and the `references` of the document, see [HtmlGen::get_references].
+  /// Chapters with a `class` are wrapped in a section
+  pub fn compile_chapter(&self, chapter: &Chapter, references: &References) -> Result<String> {
+    let components = self.get_reference_components(&self.components, references)?;
+    self.render_chapter(&components, chapter)
+  }
+
+  /// Render and concatenate the chapters.
+  /// A page with the part title is added before the first chapter of each part
+  fn render_content(&self, components: &Components, chapters: &[Chapter]) -> Result<String> {
+    let mut content: Vec<String> = Vec::new();
+    let mut part = None;
+    for chapter in chapters {
+      if chapter.part.is_some() && chapter.part != part {
+        content.push(format!(
+          r#"<div class="part-title">{}</div>{}"#,
+          escape_html(chapter.part.as_deref().unwrap_or_default()),
+          *BREAK_PAGE_HTML
+        ));
+      }
+      part = chapter.part.clone();
+      content.push(self.render_chapter(components, chapter)?);
+    }
     Ok(content.join(" "))
   }
 
+  /// Turn all your markdown files into HTML and concatenate
+  /// them into one HTML response.
+  pub fn get_content_html(&self) -> Result<String> {
+    let chapters = self.get_chapters()?;
+    let references = self.get_references(&chapters, |_| String::new())?;
+    let components = self.get_reference_components(&self.components, &references)?;
+    self.render_content(&components, &chapters)
+  }
+
+  /// Compile all registered components
+  pub fn compile_components(&self, html: &str) -> Result<String> {
+    self.components.compile_all(html)
+  }
+
   /// Remove generated files
   #[allow(dead_code)]
   pub fn clean_after_build(&self) {
@@ -194,8 +583,106 @@ impl<'a> HtmlGen<'a> {
     Ok(read_to_string(self.get_path("themes/").join(file))?)
   }
 
+  /// Base head and theme HTML, included in the document `<head>`
+  pub fn get_theme_head_html(&self) -> Result<String> {
+    let mut html = self.components.compile_theme_source(
+      &self.get_theme_partial_file_html("__base-head.html")?,
+      "themes/__base-head.html",
+    )?;
+    html.push_str(
+      self
+        .components
+        .compile_theme_source(
+          &self.get_theme_html()?,
+          &format!("themes/{}.html", self.get_theme()),
+        )?
+        .as_str(),
+    );
+    html.push_str(self.get_highlight_head_html()?.as_str());
+    Ok(html)
+  }
+
+  /// Scripts of the diagram languages not rendered yet, run by the browser before printing.
+  ///
+  /// Return [WordsmithError::DiagramScriptNotFound] error if a script does not exist
+  fn get_diagram_scripts_html(&self, chapters: &[Chapter]) -> Result<String> {
+    let mut html = String::new();
+    for kind in [DiagramKind::Mermaid, DiagramKind::Dot] {
+      let attribute = format!(r#"data-diagram="{}""#, kind.get_name());
+      let script = match self.config.diagrams.get_script(kind) {
+        Some(script) if chapters.iter().any(|c| c.html.contains(&attribute)) => script,
+        _ => continue,
+      };
+      let script_path = self.get_path(script);
+      let file_url = script_path
+        .canonicalize()
+        .ok()
+        .and_then(|path| Url::from_file_path(path).ok())
+        .ok_or_else(|| {
+          WordsmithError::DiagramScriptNotFound(format!("{}", script_path.display()))
+        })?;
+      let url = if self.relative_script_urls {
+        script.trim_start_matches("./").replace('\\', "/")
+      } else {
+        file_url.to_string()
+      };
+      html.push_str(&format!(r#"<script src="{}"></script>"#, escape_html(&url)));
+    }
+    Ok(html)
+  }
+
+  /// Color scheme of the current theme and line numbers style.
+  ///
+  /// Return [WordsmithError::HighlightSchemeNotFound] error if the scheme file does not exist
+  fn get_highlight_head_html(&self) -> Result<String> {
+    let highlight = &self.config.highlight;
+    if !highlight.enabled {
+      return Ok(String::new());
+    }
+
+    let mut html = String::new();
+    if let Some(scheme) = highlight.get_scheme(&self.get_theme()) {
+      let scheme_path = self.get_path(&format!("themes/css/{}.css", scheme));
+      if !scheme_path.exists() {
+        return Err(
+          WordsmithError::HighlightSchemeNotFound(format!("{}", scheme_path.display())).into(),
+        );
+      }
+      html.push_str(&format!(
+        r#"<link href="{}/css/{}.css" rel="stylesheet" />"#,
+        self.components.get_string_arg("themes_path"),
+        scheme
+      ));
+    }
+
+    if highlight.line_numbers {
+      html.push_str(
+        r#"
+      <style>
+        code.line-numbers {
+          counter-reset: line;
+        }
+
+        code.line-numbers .line::before {
+          counter-increment: line;
+          content: counter(line);
+          display: inline-block;
+          width: 2em;
+          margin-right: 1em;
+          text-align: right;
+          opacity: 0.5;
+          user-select: none;
+        }
+      </style>
+    "#,
+      );
+    }
+
+    Ok(html)
+  }
+
   pub fn get_document_margin_style(&self) -> String {
-    let (doc_w, doc_h) = self.config.document.dimensions.get_values();
+    let (doc_w, doc_h) = self.config.document.get_page_dimensions().get_values();
     let (ml, mt, mr, mb) = self.config.document.margins.get_values();
 
     format!(
@@ -220,41 +707,106 @@ impl<'a> HtmlGen<'a> {
     )
   }
 
+  /// List of figures and list of tables pages, when configured and not empty
+  fn get_caption_lists_html(&self, chapters: &[Chapter]) -> String {
+    let captions: Vec<Caption> = chapters
+      .iter()
+      .flat_map(|chapter| chapter.captions.clone())
+      .collect();
+    let lists = [
+      (
+        &self.config.captions.list_of_figures,
+        LabelKind::Figure,
+        "list-of-figures",
+      ),
+      (
+        &self.config.captions.list_of_tables,
+        LabelKind::Table,
+        "list-of-tables",
+      ),
+    ];
+
+    let mut html = String::new();
+    for (title, kind, class) in lists {
+      let entries: Vec<Caption> = captions
+        .iter()
+        .filter(|c| c.kind == kind)
+        .cloned()
+        .collect();
+      if let (Some(title), false) = (title, entries.is_empty()) {
+        html.push_str(&captions::to_list_html(
+          &escape_html(title),
+          class,
+          &entries,
+        ));
+        html.push_str(&BREAK_PAGE_HTML);
+      }
+    }
+    html
+  }
+
   pub fn generate_html_file_content(&self) -> Result<String> {
+    Ok(self.generate_document()?.0)
+  }
+
+  /// Generate the document and the headings of all chapters
+  fn generate_document(&self) -> Result<(String, Toc)> {
     log::debug!("Generating HTML file content");
     let mut html = String::new();
 
-    html.push_str(r#"<!DOCTYPE html><head><meta charset="utf-8">"#);
+    let chapters = self.get_chapters()?;
+    let toc = self.get_toc(&chapters, false);
+    let toc_html = self
+      .get_toc(&chapters, true)
+      .to_html(&self.config.toc.title, self.config.toc.depth);
+    let has_toc_tag = chapters
+      .iter()
+      .any(|chapter| self.components.has_toc_tag(&chapter.html));
+    let references = self
+      .get_references(&chapters, |_| String::new())?
+      .with_pages(true);
+    let components = self
+      .get_reference_components(&self.components, &references)?
+      .with_arg("toc", ComponentArg::String(toc_html.clone()));
+
+    html.push_str(&format!(
+      r#"<!DOCTYPE html><html lang="{}"><head><meta charset="utf-8">"#,
+      escape_html(self.config.get_language())
+    ));
     html.push_str(self.get_document_margin_style().as_str());
-    html.push_str(
-      self
-        .get_theme_partial_file_html("__base-head.html")?
-        .as_str(),
-    );
-    html.push_str(self.get_theme_html()?.as_str());
+    html.push_str(self.get_theme_head_html()?.as_str());
+    html.push_str(self.get_diagram_scripts_html(&chapters)?.as_str());
     html.push_str(r#"</head><body>"#);
-    html.push_str(self.get_cover_html().as_str());
-    html.push_str(BREAK_PAGE_HTML.to_string().as_str());
-    html.push_str(self.get_content_html()?.as_str());
+    html.push_str(self.get_cover_html()?.as_str());
+    if self.config.toc.enabled && !has_toc_tag {
+      html.push_str(toc_html.as_str());
+      html.push_str(BREAK_PAGE_HTML.to_string().as_str());
+    }
+    html.push_str(self.get_caption_lists_html(&chapters).as_str());
+    html.push_str(self.render_content(&components, &chapters)?.as_str());
+    html.push_str(toc.to_anchors_html().as_str());
+    html.push_str(self.get_back_cover_html()?.as_str());
     html.push_str(r#"</body></html>"#);
 
-    self.components.compile_all(&html)
+    Ok((components.compile_all(&html)?, toc))
   }
 
-  pub fn build(&self) -> Result<(PathBuf, String)> {
+  /// Write the document to `output/html.html`.
+  /// Return the file and the headings of all chapters, used by the PDF outline and headers
+  pub fn build(&self) -> Result<(PathBuf, Toc)> {
     log::debug!("Building doc");
     // Prepare output folder
-    self.clean_output_folder()?;
+    self.clean_output_file()?;
 
     log::debug!("Creating directories");
     create_dir_all(self.get_output_path())?;
 
     let html_file = self.get_output_file("html.html");
-    let html = self.generate_html_file_content()?;
+    let (html, toc) = self.generate_document()?;
 
     log::debug!("Generating {} file", &html_file.display());
     fs::write(&html_file, &html)?;
 
-    Ok((html_file, html))
+    Ok((html_file, toc))
   }
 }
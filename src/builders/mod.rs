@@ -1,5 +1,10 @@
+mod epub;
 mod html;
 mod pdf;
+mod pdf_document;
+mod site;
 
-pub use html::HtmlGen;
+pub use epub::EpubGen;
+pub use html::{Chapter, HtmlGen};
 pub use pdf::PdfGen;
+pub use site::SiteGen;
@@ -1,57 +1,516 @@
-use std::{fs, path::PathBuf};
+use std::{collections::HashMap, path::PathBuf, time::Duration};
 
-use anyhow::Result;
-use headless_chrome::{types::PrintToPdfOptions, Browser, LaunchOptions, LaunchOptionsBuilder};
+use anyhow::{anyhow, Result};
+use headless_chrome::{
+  types::PrintToPdfOptions, Browser, LaunchOptions, LaunchOptionsBuilder, Tab,
+};
 
-use crate::config::Config;
+use crate::{
+  builders::pdf_document::{PdfDocument, PdfMetadata},
+  config::{Config, CoverMode, HeaderFooterConfig, Orientation},
+  diagrams::{self, DiagramCache, RenderedDiagrams},
+  error::WordsmithError,
+  toc::{Toc, TocEntry},
+  utils::escape_html,
+};
+
+/// Convert millimeters to inches, used by chrome print options
+fn mm_to_inches(value: f64) -> f64 {
+  value / 25.4
+}
+
+/// Range of pages printed with the same header/footer.
+///
+/// `chapter` is `None` when header and footer must not be displayed (cover page)
+#[derive(Debug, PartialEq)]
+struct PageSegment {
+  first: u32,
+  last: u32,
+  chapter: Option<String>,
+}
 
 #[derive(Debug)]
 pub struct PdfGen<'a> {
   pub config: &'a Config,
+  toc: Toc,
+  diagram_cache: Option<DiagramCache>,
 }
 
 impl<'a> PdfGen<'a> {
+  /// Close the browser connection after this period without events
+  pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
   pub fn new(config: &'a Config) -> Self {
-    Self { config }
+    Self {
+      config,
+      toc: Toc::default(),
+      diagram_cache: None,
+    }
+  }
+
+  /// Headings of the document, used to find chapter titles
+  pub fn with_toc(mut self, toc: Toc) -> Self {
+    self.toc = toc;
+    self
+  }
+
+  /// Save the diagrams rendered while printing, reused by the next builds
+  pub fn with_diagram_cache(mut self, cache: DiagramCache) -> Self {
+    self.diagram_cache = Some(cache);
+    self
+  }
+
+  fn has_header_footer(&self) -> bool {
+    self.config.header.is_some() || self.config.footer.is_some()
+  }
+
+  /// Check if header or footer displays the current chapter title
+  fn uses_chapter(&self) -> bool {
+    [&self.config.header, &self.config.footer]
+      .iter()
+      .filter_map(|section| section.as_ref())
+      .any(|section| section.template.contains("{chapter}"))
   }
 
   fn get_print_options(&self) -> PrintToPdfOptions {
+    let height = |section: &Option<HeaderFooterConfig>| match section {
+      Some(section) => mm_to_inches(section.height),
+      None => 0.0,
+    };
+
+    // Chrome swaps the paper width and height in landscape
+    let (paper_w, paper_h) = self.config.document.get_paper_dimensions().get_values();
+
     PrintToPdfOptions {
-      landscape: Some(false),
+      landscape: Some(self.config.document.orientation == Orientation::Landscape),
+      paper_width: Some(mm_to_inches(paper_w)),
+      paper_height: Some(mm_to_inches(paper_h)),
       display_header_footer: Some(false),
       margin_left: Some(0.0),
-      margin_top: Some(0.0),
+      margin_top: Some(height(&self.config.header)),
       margin_right: Some(0.0),
-      margin_bottom: Some(0.0),
+      margin_bottom: Some(height(&self.config.footer)),
       print_background: Some(true),
       prefer_css_page_size: Some(true),
       ..PrintToPdfOptions::default()
     }
   }
 
-  fn get_browser_options(&self) -> Result<LaunchOptions> {
+  /// Print options displaying header and footer for the given chapter
+  fn get_header_footer_print_options(&self, chapter: &str) -> PrintToPdfOptions {
+    PrintToPdfOptions {
+      display_header_footer: Some(true),
+      header_template: Some(self.get_template_html(&self.config.header, chapter)),
+      footer_template: Some(self.get_template_html(&self.config.footer, chapter)),
+      ..self.get_print_options()
+    }
+  }
+
+  /// Print options for a range of pages.
+  /// Header and footer are displayed only if the segment has a chapter.
+  ///
+  /// All segments keep the margins of the draft print, so its page numbers stay valid,
+  /// the theme `@page` rules remove the margins of the cover pages
+  fn get_segment_print_options(&self, segment: &PageSegment) -> PrintToPdfOptions {
+    let options = match &segment.chapter {
+      Some(chapter) => self.get_header_footer_print_options(chapter),
+      None => self.get_print_options(),
+    };
+
+    PrintToPdfOptions {
+      page_ranges: Some(format!("{}-{}", segment.first, segment.last)),
+      ..options
+    }
+  }
+
+  /// Build chrome header/footer template.
+  ///
+  /// Chrome replaces the content of elements with the classes
+  /// `pageNumber`, `totalPages` and `date` while printing.
+  /// An empty template would make chrome display its default header/footer.
+  fn get_template_html(&self, section: &Option<HeaderFooterConfig>, chapter: &str) -> String {
+    let section = match section {
+      Some(section) => section,
+      None => return "<span></span>".to_string(),
+    };
+
+    let (ml, _, mr, _) = self.config.document.margins.get_values();
+
+    let content = section
+      .template
+      .replace("{title}", &escape_html(&self.config.title))
+      .replace("{chapter}", chapter)
+      .replace("{page}", r#"<span class="pageNumber"></span>"#)
+      .replace("{pages}", r#"<span class="totalPages"></span>"#)
+      .replace("{date}", r#"<span class="date"></span>"#);
+
+    format!(
+      r#"<div style="width:100%;font-size:{fs}px;padding:0 {mr}mm 0 {ml}mm;text-align:center;">{content}</div>"#,
+      fs = section.font_size,
+    )
+  }
+
+  /// Split the document into ranges of pages sharing the same header/footer.
+  ///
+  /// The cover and back cover pages never have header/footer. When `{chapter}` is used,
+  /// a new range starts at every chapter (h1) page.
+  fn get_page_segments(
+    &self,
+    page_count: u32,
+    destinations: &HashMap<String, u32>,
+  ) -> Vec<PageSegment> {
+    let has_cover = self.config.cover.mode != CoverMode::None;
+    let has_back_cover = self.config.cover.back.is_some();
+
+    let mut segments = Vec::new();
+    if has_cover {
+      segments.push(PageSegment {
+        first: 1,
+        last: 1,
+        chapter: None,
+      });
+    }
+
+    let first_page = has_cover as u32 + 1;
+    let last_page = page_count.saturating_sub(has_back_cover as u32);
+    if first_page <= last_page {
+      let mut chapters: Vec<(u32, String)> = Vec::new();
+      if self.uses_chapter() {
+        for entry in self.toc.entries().iter().filter(|e| e.level == 1) {
+          if let Some(page) = destinations
+            .get(&entry.id)
+            .filter(|p| (first_page..=last_page).contains(*p))
+          {
+            chapters.push((*page, entry.title.clone()));
+          }
+        }
+        chapters.sort_by_key(|(page, _)| *page);
+      }
+
+      let (mut first, mut chapter) = (first_page, String::new());
+      for (page, title) in chapters {
+        if page > first {
+          segments.push(PageSegment {
+            first,
+            last: page - 1,
+            chapter: Some(chapter),
+          });
+          first = page;
+        }
+        chapter = title;
+      }
+
+      segments.push(PageSegment {
+        first,
+        last: last_page,
+        chapter: Some(chapter),
+      });
+    }
+
+    if has_back_cover && page_count >= first_page {
+      segments.push(PageSegment {
+        first: page_count,
+        last: page_count,
+        chapter: None,
+      });
+    }
+
+    segments
+  }
+
+  /// Document properties, chrome headless ignores the html meta tags
+  fn get_metadata(&self) -> PdfMetadata {
+    PdfMetadata {
+      title: self.config.title.clone(),
+      authors: self.config.get_author_names(),
+      subject: self.config.subject.clone(),
+      keywords: self.config.keywords.clone().unwrap_or_default(),
+      language: self.config.get_language().to_string(),
+      publisher: self.config.publisher.clone(),
+    }
+  }
+
+  fn get_browser_options(idle_timeout: Duration) -> Result<LaunchOptions<'static>> {
     Ok(
       LaunchOptionsBuilder::default()
         .disable_default_args(true)
         .headless(true)
+        .idle_browser_timeout(idle_timeout)
         .build()?,
     )
   }
 
+  /// Launch chrome headless. The browser can be reused by many
+  /// [PdfGen::generate_with_browser] calls, e.g.: in watch mode
+  pub fn launch_browser(idle_timeout: Duration) -> Result<Browser> {
+    Browser::new(Self::get_browser_options(idle_timeout)?)
+  }
+
+  /// Print the document once per page segment and merge all segments.
+  ///
+  /// A first print (without chapter titles) is used to find the page count
+  /// and where each chapter starts.
+  fn print_with_header_footer(&self, tab: &Tab) -> Result<PdfDocument> {
+    let draft = tab.print_to_pdf(Some(self.get_header_footer_print_options("")))?;
+    let draft = PdfDocument::load(&draft)?;
+
+    let segments = self.get_page_segments(draft.page_count(), &draft.destination_pages());
+
+    let mut documents = Vec::new();
+    for segment in segments {
+      log::debug!("Printing pages {}-{}", segment.first, segment.last);
+      let pdf = tab.print_to_pdf(Some(self.get_segment_print_options(&segment)))?;
+      documents.push(PdfDocument::load(&pdf)?);
+    }
+
+    PdfDocument::merge(documents)
+  }
+
+  /// Fill the page numbers of cross-references (`data-ref-page` elements),
+  /// using the pages of a first print
+  fn set_reference_pages(&self, tab: &Tab) -> Result<()> {
+    let has_pages = tab
+      .evaluate("document.querySelector('[data-ref-page]') !== null", false)?
+      .value
+      .and_then(|value| value.as_bool())
+      .unwrap_or(false);
+    if !has_pages {
+      return Ok(());
+    }
+
+    let draft = tab.print_to_pdf(Some(self.get_print_options()))?;
+    let pages = PdfDocument::load(&draft)?.destination_pages();
+    tab.evaluate(&get_reference_pages_script(&pages), false)?;
+    Ok(())
+  }
+
+  /// Render diagrams without cached SVG (`data-diagram` elements), using the
+  /// scripts loaded by the page, and add them to the cache.
+  ///
+  /// Return [WordsmithError::InvalidDiagrams] error if a diagram can not be rendered
+  fn render_diagrams(&self, tab: &Tab) -> Result<()> {
+    let has_diagrams = tab
+      .evaluate("document.querySelector('[data-diagram]') !== null", false)?
+      .value
+      .and_then(|value| value.as_bool())
+      .unwrap_or(false);
+    if !has_diagrams {
+      return Ok(());
+    }
+
+    log::debug!("Rendering diagrams");
+    let json = tab
+      .evaluate(diagrams::get_render_script(), true)?
+      .value
+      .and_then(|value| value.as_str().map(|json| json.to_string()))
+      .ok_or_else(|| anyhow!("Diagrams could not be rendered"))?;
+    let rendered = RenderedDiagrams::from_json(&json)?;
+
+    if let Some(cache) = &self.diagram_cache {
+      for (key, svg) in &rendered.svgs {
+        cache.insert(key, svg)?;
+      }
+    }
+    if !rendered.errors.is_empty() {
+      return Err(WordsmithError::InvalidDiagrams(rendered.errors).into());
+    }
+    Ok(())
+  }
+
+  /// Headings included in the PDF outline
+  fn get_outline_entries(&self) -> Vec<TocEntry> {
+    self
+      .toc
+      .entries()
+      .iter()
+      .filter(|entry| entry.level <= self.config.outline.depth)
+      .cloned()
+      .collect()
+  }
+
   /// Using chrome headless open an HTML file and generate a PDF file
   pub fn generate(&self, html_file: PathBuf, pdf_file: PathBuf) -> Result<()> {
-    let browser_opts = self.get_browser_options()?;
-    let browser = Browser::new(browser_opts)?;
+    let browser = Self::launch_browser(Self::DEFAULT_IDLE_TIMEOUT)?;
+    self.generate_with_browser(&browser, html_file, pdf_file)
+  }
 
-    let tab = browser.wait_for_initial_tab()?;
+  /// Generate a PDF file using an already running browser.
+  /// A new tab is opened and closed for every call, even when the generation fails
+  pub fn generate_with_browser(
+    &self,
+    browser: &Browser,
+    html_file: PathBuf,
+    pdf_file: PathBuf,
+  ) -> Result<()> {
+    let tab = browser.new_tab()?;
+    let result = self.generate_in_tab(&tab, html_file, pdf_file);
+    let closed = tab.close(false);
+    result?;
+    closed?;
+    Ok(())
+  }
+
+  /// Open the HTML file in the tab, print it and save the PDF file
+  fn generate_in_tab(&self, tab: &Tab, html_file: PathBuf, pdf_file: PathBuf) -> Result<()> {
     tab.navigate_to(&format!("file://{}", html_file.display()))?;
+    tab.wait_until_navigated()?;
+    self.render_diagrams(tab)?;
+    self.set_reference_pages(tab)?;
 
-    let pdf = tab
-      .wait_until_navigated()?
-      .print_to_pdf(Some(self.get_print_options()))?;
+    let mut document = if self.has_header_footer() {
+      self.print_with_header_footer(tab)?
+    } else {
+      PdfDocument::load(&tab.print_to_pdf(Some(self.get_print_options()))?)?
+    };
 
-    fs::write(pdf_file, pdf)?;
+    if self.config.outline.enabled {
+      document.set_outline(&self.get_outline_entries())?;
+    }
+    document.set_metadata(&self.get_metadata())?;
+    document.save(&pdf_file)
+  }
+}
 
-    Ok(())
+/// Script setting the text of `data-ref-page` elements to the page of their label.
+/// Quotes and backslashes are removed from the destination names, labels never contain them
+fn get_reference_pages_script(pages: &HashMap<String, u32>) -> String {
+  let mut entries: Vec<String> = pages
+    .iter()
+    .map(|(id, page)| format!(r#""{}":{}"#, id.replace(['\\', '"'], ""), page))
+    .collect();
+  entries.sort();
+  format!(
+    "(() => {{ const pages = {{{}}}; document.querySelectorAll('[data-ref-page]').forEach((e) => {{ if (pages[e.dataset.refPage]) e.textContent = pages[e.dataset.refPage]; }}); }})()",
+    entries.join(",")
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::CoverConfig;
+
+  fn heading(id: &str, title: &str) -> String {
+    format!(r##"<h1><a href="#{id}" aria-hidden="true" class="anchor" id="{id}"></a>{title}</h1>"##)
+  }
+
+  #[test]
+  fn test_get_page_segments_by_chapter() {
+    let config = Config {
+      header: Some(HeaderFooterConfig {
+        template: "{chapter}".to_string(),
+        ..HeaderFooterConfig::default()
+      }),
+      ..Config::default()
+    };
+    let toc = Toc::from_html(&format!("{}{}", heading("a", "A"), heading("b", "B")));
+    let destinations = HashMap::from([("a".to_string(), 3), ("b".to_string(), 5)]);
+
+    let segments = PdfGen::new(&config)
+      .with_toc(toc)
+      .get_page_segments(8, &destinations);
+
+    let ranges: Vec<(u32, u32, Option<&str>)> = segments
+      .iter()
+      .map(|s| (s.first, s.last, s.chapter.as_deref()))
+      .collect();
+
+    assert_eq!(
+      ranges,
+      vec![
+        (1, 1, None),
+        (2, 2, Some("")),
+        (3, 4, Some("A")),
+        (5, 8, Some("B"))
+      ]
+    );
+  }
+
+  #[test]
+  fn test_get_page_segments_without_cover() {
+    let config = Config {
+      header: Some(HeaderFooterConfig::default()),
+      cover: CoverConfig {
+        mode: CoverMode::None,
+        back: Some("back.jpg".to_string()),
+        ..CoverConfig::default()
+      },
+      ..Config::default()
+    };
+
+    let segments = PdfGen::new(&config).get_page_segments(4, &HashMap::new());
+
+    let ranges: Vec<(u32, u32, bool)> = segments
+      .iter()
+      .map(|s| (s.first, s.last, s.chapter.is_some()))
+      .collect();
+
+    assert_eq!(ranges, vec![(1, 3, true), (4, 4, false)]);
+  }
+
+  #[test]
+  fn test_get_segment_print_options() {
+    let config = Config {
+      header: Some(HeaderFooterConfig::default()),
+      footer: Some(HeaderFooterConfig::default()),
+      cover: CoverConfig {
+        back: Some("back.jpg".to_string()),
+        ..CoverConfig::default()
+      },
+      ..Config::default()
+    };
+    let pdf_gen = PdfGen::new(&config);
+    let draft = pdf_gen.get_print_options();
+
+    let segments = pdf_gen.get_page_segments(6, &HashMap::new());
+    let back_cover = segments.last().unwrap();
+    assert_eq!((back_cover.first, back_cover.last), (6, 6));
+    assert_eq!(back_cover.chapter, None);
+
+    // Same layout as the draft print, so the back cover is still its last page
+    let options = pdf_gen.get_segment_print_options(back_cover);
+    assert_eq!(options.page_ranges.as_deref(), Some("6-6"));
+    assert_eq!(options.display_header_footer, Some(false));
+    assert_eq!(
+      (options.margin_top, options.margin_bottom),
+      (draft.margin_top, draft.margin_bottom)
+    );
+    assert_eq!(
+      (options.paper_width, options.paper_height),
+      (draft.paper_width, draft.paper_height)
+    );
+
+    let content = pdf_gen.get_segment_print_options(&segments[1]);
+    assert_eq!(
+      (content.margin_top, content.margin_bottom),
+      (draft.margin_top, draft.margin_bottom)
+    );
+  }
+
+  #[test]
+  fn test_get_reference_pages_script() {
+    let pages = HashMap::from([("setup".to_string(), 4), ("fig-a".to_string(), 7)]);
+
+    let script = get_reference_pages_script(&pages);
+
+    assert!(script.contains(r#"const pages = {"fig-a":7,"setup":4};"#));
+  }
+
+  #[test]
+  fn test_get_template_html() {
+    let config = Config {
+      title: "Tom & Jerry".to_string(),
+      ..Config::default()
+    };
+    let section = Some(HeaderFooterConfig {
+      template: "{title} - {chapter} {page}/{pages}".to_string(),
+      ..HeaderFooterConfig::default()
+    });
+
+    let html = PdfGen::new(&config).get_template_html(&section, "Intro");
+
+    assert!(html.contains(
+      r#"Tom &amp; Jerry - Intro <span class="pageNumber"></span>/<span class="totalPages"></span>"#
+    ));
   }
 }
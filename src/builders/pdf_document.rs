@@ -0,0 +1,557 @@
+use std::{collections::HashMap, fs::File, path::Path};
+
+use anyhow::Result;
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+
+use crate::{
+  toc::TocEntry,
+  utils::{escape_html, unescape_html},
+};
+
+/// Encode a PDF text string.
+/// Non ASCII text is encoded as UTF-16BE with a byte order mark.
+fn text_string(value: &str) -> Object {
+  if value.is_ascii() {
+    return Object::string_literal(value);
+  }
+
+  let mut bytes = vec![0xFE, 0xFF];
+  for unit in value.encode_utf16() {
+    bytes.extend(unit.to_be_bytes());
+  }
+  Object::String(bytes, StringFormat::Hexadecimal)
+}
+
+/// Document properties, written to the Info dictionary and XMP metadata
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PdfMetadata {
+  pub title: String,
+  pub authors: Vec<String>,
+  pub subject: Option<String>,
+  pub keywords: Vec<String>,
+  pub language: String,
+  pub publisher: Option<String>,
+}
+
+impl PdfMetadata {
+  /// Build the XMP metadata packet
+  fn to_xmp(&self) -> String {
+    let rdf_list = |container: &str, values: &[String]| {
+      let items: String = values
+        .iter()
+        .map(|value| format!("<rdf:li>{}</rdf:li>", escape_html(value)))
+        .collect();
+      format!("<rdf:{c}>{}</rdf:{c}>", items, c = container)
+    };
+
+    let mut dc = String::new();
+    dc.push_str(&format!(
+      r#"<dc:title><rdf:Alt><rdf:li xml:lang="x-default">{}</rdf:li></rdf:Alt></dc:title>"#,
+      escape_html(&self.title)
+    ));
+    if !self.authors.is_empty() {
+      dc.push_str(&format!(
+        "<dc:creator>{}</dc:creator>",
+        rdf_list("Seq", &self.authors)
+      ));
+    }
+    if let Some(subject) = &self.subject {
+      dc.push_str(&format!(
+        r#"<dc:description><rdf:Alt><rdf:li xml:lang="x-default">{}</rdf:li></rdf:Alt></dc:description>"#,
+        escape_html(subject)
+      ));
+    }
+    if !self.keywords.is_empty() {
+      dc.push_str(&format!(
+        "<dc:subject>{}</dc:subject>",
+        rdf_list("Bag", &self.keywords)
+      ));
+    }
+    dc.push_str(&format!(
+      "<dc:language>{}</dc:language>",
+      rdf_list("Bag", std::slice::from_ref(&self.language))
+    ));
+    if let Some(publisher) = &self.publisher {
+      dc.push_str(&format!(
+        "<dc:publisher>{}</dc:publisher>",
+        rdf_list("Bag", std::slice::from_ref(publisher))
+      ));
+    }
+
+    format!(
+      r#"<?xpacket begin="{bom}" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+<rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:pdf="http://ns.adobe.com/pdf/1.3/" xmlns:xmp="http://ns.adobe.com/xap/1.0/">
+{dc}
+<pdf:Keywords>{keywords}</pdf:Keywords>
+<xmp:CreatorTool>Wordsmith</xmp:CreatorTool>
+<xmp:MetadataDate>{date}</xmp:MetadataDate>
+</rdf:Description>
+</rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#,
+      bom = '\u{feff}',
+      keywords = escape_html(&self.keywords.join(", ")),
+      date = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+    )
+  }
+}
+
+/// A bookmark of the document outline
+#[derive(Debug)]
+struct OutlineItem {
+  title: String,
+  dest: Object,
+  children: Vec<usize>,
+}
+
+/// Thin wrapper around a PDF printed by chrome headless,
+/// used to post-process the generated file.
+#[derive(Debug, Clone)]
+pub struct PdfDocument {
+  doc: Document,
+}
+
+impl PdfDocument {
+  /// Load a PDF from memory
+  pub fn load(bytes: &[u8]) -> Result<Self> {
+    Ok(Self {
+      doc: Document::load_mem(bytes)?,
+    })
+  }
+
+  /// Number of pages of the document
+  pub fn page_count(&self) -> u32 {
+    self.doc.get_pages().len() as u32
+  }
+
+  /// Return named destinations and the page number (starting at 1) they point to.
+  ///
+  /// Chrome writes a named destination for every element that is the target
+  /// of an internal link, e.g.: `<a href="#header-id-introduction">`.
+  pub fn destination_pages(&self) -> HashMap<String, u32> {
+    let page_numbers: HashMap<ObjectId, u32> = self
+      .doc
+      .get_pages()
+      .into_iter()
+      .map(|(number, id)| (id, number))
+      .collect();
+
+    let mut destinations = HashMap::new();
+    for (name, dest) in self.get_dests().iter() {
+      let page = self
+        .get_destination_page(dest)
+        .and_then(|id| page_numbers.get(&id));
+
+      if let Some(page) = page {
+        destinations.insert(String::from_utf8_lossy(name).to_string(), *page);
+      }
+    }
+
+    destinations
+  }
+
+  /// Resolve the page referenced by a destination.
+  /// It can be an array (`[page /XYZ x y z]`) or a dictionary with a `D` entry.
+  fn get_destination_page(&self, dest: &Object) -> Option<ObjectId> {
+    let (_, dest) = self.doc.dereference(dest).ok()?;
+    let dest = match dest {
+      Object::Dictionary(dict) => self.doc.dereference(dict.get(b"D").ok()?).ok()?.1,
+      dest => dest,
+    };
+    dest.as_array().ok()?.first()?.as_reference().ok()
+  }
+
+  /// Get the `Dests` dictionary from the document catalog
+  fn get_dests(&self) -> Dictionary {
+    self
+      .doc
+      .catalog()
+      .and_then(|catalog| catalog.get(b"Dests"))
+      .and_then(|dests| self.doc.dereference(dests))
+      .and_then(|(_, dests)| dests.as_dict())
+      .cloned()
+      .unwrap_or_default()
+  }
+
+  /// Merge documents into one, keeping the pages order.
+  ///
+  /// Named destinations of all documents are kept, so internal links
+  /// keep working across merged documents.
+  pub fn merge(documents: Vec<PdfDocument>) -> Result<Self> {
+    let mut documents = documents.into_iter();
+    let mut base = match documents.next() {
+      Some(document) => document,
+      None => {
+        return Ok(Self {
+          doc: Document::with_version("1.4"),
+        })
+      }
+    };
+
+    let pages_id = base.doc.catalog()?.get(b"Pages")?.as_reference()?;
+    let mut page_ids: Vec<ObjectId> = base.doc.get_pages().into_values().collect();
+    let mut dests = base.get_dests();
+
+    for mut document in documents {
+      document.doc.renumber_objects_with(base.doc.max_id + 1);
+      dests.extend(&document.get_dests());
+      page_ids.extend(document.doc.get_pages().into_values());
+
+      base.doc.max_id = document.doc.max_id;
+      for (id, object) in document.doc.objects {
+        // Skip objects replaced by the base document catalog and page tree
+        if matches!(object.type_name(), Ok(b"Catalog") | Ok(b"Pages")) {
+          continue;
+        }
+        base.doc.objects.insert(id, object);
+      }
+    }
+
+    for page_id in &page_ids {
+      base
+        .doc
+        .get_dictionary_mut(*page_id)?
+        .set("Parent", pages_id);
+    }
+
+    let pages = base.doc.get_dictionary_mut(pages_id)?;
+    pages.set("Count", page_ids.len() as i64);
+    pages.set(
+      "Kids",
+      page_ids
+        .into_iter()
+        .map(Object::Reference)
+        .collect::<Vec<Object>>(),
+    );
+
+    if !dests.is_empty() {
+      base.doc.catalog_mut()?.set("Dests", dests);
+    }
+
+    base.doc.prune_objects();
+
+    Ok(base)
+  }
+
+  /// Write document properties to the Info dictionary and the XMP metadata stream
+  pub fn set_metadata(&mut self, metadata: &PdfMetadata) -> Result<()> {
+    let mut info = Dictionary::new();
+    info.set("Title", text_string(&metadata.title));
+    if !metadata.authors.is_empty() {
+      info.set("Author", text_string(&metadata.authors.join(", ")));
+    }
+    if let Some(subject) = &metadata.subject {
+      info.set("Subject", text_string(subject));
+    }
+    if !metadata.keywords.is_empty() {
+      info.set("Keywords", text_string(&metadata.keywords.join(", ")));
+    }
+    info.set("Creator", text_string("Wordsmith"));
+
+    // Keep entries written by chrome, e.g.: Producer and CreationDate
+    match self.doc.trailer.get(b"Info").and_then(Object::as_reference) {
+      Ok(info_id) => self.doc.get_dictionary_mut(info_id)?.extend(&info),
+      Err(_) => {
+        let info_id = self.doc.add_object(info);
+        self.doc.trailer.set("Info", info_id);
+      }
+    }
+
+    let xmp = Stream::new(
+      dictionary! {
+        "Type" => "Metadata",
+        "Subtype" => "XML",
+      },
+      metadata.to_xmp().into_bytes(),
+    )
+    .with_compression(false);
+    let xmp_id = self.doc.add_object(xmp);
+
+    let catalog = self.doc.catalog_mut()?;
+    catalog.set("Metadata", xmp_id);
+    catalog.set("Lang", text_string(&metadata.language));
+
+    Ok(())
+  }
+
+  /// Build the document outline (bookmarks) from the headings.
+  ///
+  /// Bookmarks are nested following the heading levels and jump to the
+  /// named destination of each heading. Headings without a destination are skipped.
+  pub fn set_outline(&mut self, entries: &[TocEntry]) -> Result<()> {
+    let dests = self.get_dests();
+
+    let mut items: Vec<OutlineItem> = Vec::new();
+    let mut roots: Vec<usize> = Vec::new();
+    // Level and index of the items that can receive children
+    let mut parents: Vec<(u8, usize)> = Vec::new();
+
+    for entry in entries {
+      let dest = match dests.get(entry.id.as_bytes()) {
+        Ok(dest) => match self.doc.dereference(dest) {
+          Ok((_, Object::Dictionary(dict))) => dict.get(b"D")?.clone(),
+          Ok((_, dest)) => dest.clone(),
+          Err(_) => continue,
+        },
+        Err(_) => continue,
+      };
+
+      while matches!(parents.last(), Some((level, _)) if *level >= entry.level) {
+        parents.pop();
+      }
+
+      let index = items.len();
+      items.push(OutlineItem {
+        title: unescape_html(&entry.get_numbered_title()),
+        dest,
+        children: Vec::new(),
+      });
+      match parents.last() {
+        Some((_, parent)) => items[*parent].children.push(index),
+        None => roots.push(index),
+      }
+      parents.push((entry.level, index));
+    }
+
+    if roots.is_empty() {
+      return Ok(());
+    }
+
+    let ids: Vec<ObjectId> = items.iter().map(|_| self.doc.new_object_id()).collect();
+    let outlines_id = self.doc.new_object_id();
+    let count = self.add_outline_items(&items, &ids, &roots, outlines_id);
+
+    self.doc.objects.insert(
+      outlines_id,
+      Object::Dictionary(dictionary! {
+        "Type" => "Outlines",
+        "First" => ids[roots[0]],
+        "Last" => ids[roots[roots.len() - 1]],
+        "Count" => count,
+      }),
+    );
+
+    let catalog = self.doc.catalog_mut()?;
+    catalog.set("Outlines", outlines_id);
+    catalog.set("PageMode", "UseOutlines");
+
+    Ok(())
+  }
+
+  /// Write a list of sibling outline items and their children.
+  ///
+  /// Return the number of items written, all items are open.
+  fn add_outline_items(
+    &mut self,
+    items: &[OutlineItem],
+    ids: &[ObjectId],
+    siblings: &[usize],
+    parent: ObjectId,
+  ) -> i64 {
+    let mut count = 0;
+
+    for (position, index) in siblings.iter().enumerate() {
+      let item = &items[*index];
+      let mut dict = dictionary! {
+        "Title" => text_string(&item.title),
+        "Parent" => parent,
+        "Dest" => item.dest.clone(),
+      };
+      if position > 0 {
+        dict.set("Prev", ids[siblings[position - 1]]);
+      }
+      if let Some(next) = siblings.get(position + 1) {
+        dict.set("Next", ids[*next]);
+      }
+      if !item.children.is_empty() {
+        let children = self.add_outline_items(items, ids, &item.children, ids[*index]);
+        dict.set("First", ids[item.children[0]]);
+        dict.set("Last", ids[item.children[item.children.len() - 1]]);
+        dict.set("Count", children);
+        count += children;
+      }
+
+      self
+        .doc
+        .objects
+        .insert(ids[*index], Object::Dictionary(dict));
+      count += 1;
+    }
+
+    count
+  }
+
+  /// Write the document to a file
+  pub fn save(&mut self, file: &Path) -> Result<()> {
+    self.doc.save_to(&mut File::create(file)?)?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Create a document with one empty page per destination name
+  fn fake_document(names: &[&str]) -> PdfDocument {
+    let mut doc = Document::with_version("1.4");
+    let pages_id = doc.new_object_id();
+
+    let mut kids = Vec::<Object>::new();
+    let mut dests = Dictionary::new();
+    for name in names {
+      let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+      });
+      kids.push(page_id.into());
+      dests.set(
+        name.as_bytes().to_vec(),
+        vec![
+          page_id.into(),
+          "XYZ".into(),
+          0.into(),
+          0.into(),
+          Object::Null,
+        ],
+      );
+    }
+
+    doc.objects.insert(
+      pages_id,
+      Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Count" => kids.len() as i64,
+        "Kids" => kids,
+      }),
+    );
+
+    let catalog_id = doc.add_object(dictionary! {
+      "Type" => "Catalog",
+      "Pages" => pages_id,
+      "Dests" => dests,
+    });
+    doc.trailer.set("Root", catalog_id);
+
+    let mut bytes = Vec::new();
+    doc.save_to(&mut bytes).unwrap();
+    PdfDocument::load(&bytes).unwrap()
+  }
+
+  #[test]
+  fn test_destination_pages() {
+    let document = fake_document(&["a", "b"]);
+
+    let destinations = document.destination_pages();
+
+    assert_eq!(document.page_count(), 2);
+    assert_eq!(destinations.get("a"), Some(&1));
+    assert_eq!(destinations.get("b"), Some(&2));
+  }
+
+  #[test]
+  fn test_merge() {
+    let merged = PdfDocument::merge(vec![
+      fake_document(&["a"]),
+      fake_document(&["b", "c"]),
+      fake_document(&["d"]),
+    ])
+    .unwrap();
+
+    let destinations = merged.destination_pages();
+
+    assert_eq!(merged.page_count(), 4);
+    assert_eq!(destinations.get("a"), Some(&1));
+    assert_eq!(destinations.get("c"), Some(&3));
+    assert_eq!(destinations.get("d"), Some(&4));
+  }
+
+  #[test]
+  fn test_set_metadata() {
+    let mut document = fake_document(&["a"]);
+    let metadata = PdfMetadata {
+      title: "Título".to_string(),
+      authors: vec!["Name".to_string()],
+      keywords: vec!["rust".to_string(), "pdf".to_string()],
+      language: "pt-BR".to_string(),
+      ..PdfMetadata::default()
+    };
+
+    document.set_metadata(&metadata).unwrap();
+
+    let info_id = document
+      .doc
+      .trailer
+      .get(b"Info")
+      .unwrap()
+      .as_reference()
+      .unwrap();
+    let info = document.doc.get_dictionary(info_id).unwrap();
+    assert_eq!(info.get(b"Author").unwrap(), &text_string("Name"));
+    assert_eq!(info.get(b"Keywords").unwrap(), &text_string("rust, pdf"));
+    assert_eq!(info.get(b"Title").unwrap(), &text_string("Título"));
+
+    let catalog = document.doc.catalog().unwrap();
+    assert_eq!(catalog.get(b"Lang").unwrap(), &text_string("pt-BR"));
+    assert!(catalog.get(b"Metadata").is_ok());
+  }
+
+  #[test]
+  fn test_set_outline() {
+    let mut document = fake_document(&["a", "b", "c"]);
+    let entry = |level: u8, id: &str, title: &str| TocEntry {
+      level,
+      id: id.to_string(),
+      title: title.to_string(),
+      number: None,
+    };
+
+    document
+      .set_outline(&[
+        entry(1, "a", "Tom &amp; Jerry"),
+        entry(2, "b", "Setup"),
+        entry(2, "missing", "Missing"),
+        entry(1, "c", "End"),
+      ])
+      .unwrap();
+
+    let doc = &document.doc;
+    let catalog = doc.catalog().unwrap();
+    assert_eq!(
+      catalog.get(b"PageMode").unwrap(),
+      &Object::from("UseOutlines")
+    );
+
+    let outlines_id = catalog.get(b"Outlines").unwrap().as_reference().unwrap();
+    let outlines = doc.get_dictionary(outlines_id).unwrap();
+    assert_eq!(outlines.get(b"Count").unwrap(), &Object::Integer(3));
+
+    let first_id = outlines.get(b"First").unwrap().as_reference().unwrap();
+    let first = doc.get_dictionary(first_id).unwrap();
+    assert_eq!(first.get(b"Title").unwrap(), &text_string("Tom & Jerry"));
+    assert_eq!(first.get(b"Count").unwrap(), &Object::Integer(1));
+
+    let child_id = first.get(b"First").unwrap().as_reference().unwrap();
+    let child = doc.get_dictionary(child_id).unwrap();
+    assert_eq!(child.get(b"Title").unwrap(), &text_string("Setup"));
+    assert!(child.get(b"Next").is_err());
+
+    let last_id = outlines.get(b"Last").unwrap().as_reference().unwrap();
+    assert_eq!(first.get(b"Next").unwrap().as_reference().unwrap(), last_id);
+    let last = doc.get_dictionary(last_id).unwrap();
+    assert_eq!(last.get(b"Title").unwrap(), &text_string("End"));
+    assert_eq!(last.get(b"Prev").unwrap().as_reference().unwrap(), first_id);
+  }
+
+  #[test]
+  fn test_text_string() {
+    assert_eq!(text_string("abc"), Object::string_literal("abc"));
+    assert_eq!(
+      text_string("é"),
+      Object::String(vec![0xFE, 0xFF, 0x00, 0xE9], StringFormat::Hexadecimal)
+    );
+  }
+}
@@ -0,0 +1,328 @@
+use std::{
+  collections::HashMap,
+  fs::{self, create_dir_all, remove_dir_all},
+  path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+
+use crate::{
+  builders::{Chapter, HtmlGen},
+  components::{ComponentArg, ComponentRegistry},
+  config::Config,
+  toc::Toc,
+  utils::{copy_files, escape_html},
+};
+
+/// Layout of the website pages: sidebar with the table of contents,
+/// content and navigation to the previous/next pages
+const SITE_STYLE: &str = r#"
+<style>
+  body.site {
+    margin: 0;
+    padding: 0 !important;
+    display: flex;
+    min-height: 100vh;
+  }
+
+  .site-sidebar {
+    position: sticky;
+    top: 0;
+    flex: 0 0 280px;
+    height: 100vh;
+    overflow-y: auto;
+    padding: 30px 20px;
+    box-sizing: border-box;
+    border-right: 1px solid #ddd;
+    font-size: 14px;
+  }
+
+  .site-sidebar ol {
+    list-style: none;
+    padding-left: 15px;
+    margin: 0;
+  }
+
+  .site-sidebar > nav > ol {
+    padding-left: 0;
+  }
+
+  .site-sidebar li {
+    margin: 6px 0;
+  }
+
+  .site-sidebar a {
+    text-decoration: none;
+  }
+
+  .site-title {
+    display: block;
+    font-weight: bold;
+    font-size: 18px;
+    margin-bottom: 20px;
+  }
+
+  .site-content {
+    flex: 1;
+    max-width: 800px;
+    padding: 40px;
+    margin: 0 auto;
+  }
+
+  .site-content h1 {
+    padding-top: 0 !important;
+  }
+
+  .site-content img {
+    max-width: 100%;
+  }
+
+  .site-cover {
+    display: block;
+    max-width: 400px;
+    margin: 0 auto 40px;
+  }
+
+  .site-pagination {
+    display: flex;
+    justify-content: space-between;
+    margin-top: 60px;
+    padding-top: 20px;
+    border-top: 1px solid #ddd;
+  }
+
+  @media (max-width: 800px) {
+    body.site {
+      display: block;
+    }
+
+    .site-sidebar {
+      position: static;
+      height: auto;
+      border-right: none;
+      border-bottom: 1px solid #ddd;
+    }
+  }
+</style>
+"#;
+
+/// A page of the website
+#[derive(Debug)]
+struct Page {
+  file: String,
+  /// Plain text, escaped when inserted into HTML
+  title: String,
+}
+
+#[derive(Debug)]
+pub struct SiteGen {
+  pub config: Config,
+  pub path: PathBuf,
+  html_gen: HtmlGen,
+}
+
+impl SiteGen {
+  /// Creates a [SiteGen]. Assets and themes are copied to the website
+  /// folder and referenced with relative urls.
+  pub fn new(config: Config, path: PathBuf, theme: Option<String>) -> Self {
+    let html_gen = HtmlGen::new(config.clone(), path.clone(), theme)
+      .with_component_arg("assets_path", ComponentArg::String("assets".to_string()))
+      .with_component_arg("themes_path", ComponentArg::String("themes".to_string()));
+
+    Self {
+      config,
+      path,
+      html_gen,
+    }
+  }
+
+  /// Add custom components, see [HtmlGen::with_components]
+  pub fn with_components(mut self, registry: ComponentRegistry) -> Self {
+    self.html_gen = self.html_gen.with_components(registry);
+    self
+  }
+
+  /// Return the page file name of a chapter, e.g.: `001-introduction.html`
+  fn get_page_file(&self, chapter: &Chapter) -> String {
+    format!("{}.html", chapter.get_file_stem())
+  }
+
+  fn get_page_html(&self, title: &str, head: &str, sidebar: &str, content: &str) -> String {
+    let page_title = if title == self.config.title {
+      escape_html(title)
+    } else {
+      format!(
+        "{} - {}",
+        escape_html(title),
+        escape_html(&self.config.title)
+      )
+    };
+
+    format!(
+      r#"<!DOCTYPE html><html lang="{lang}"><head><meta charset="utf-8"><meta name="viewport" content="width=device-width, initial-scale=1"><title>{page_title}</title>{head}{style}</head><body class="site"><aside class="site-sidebar"><a class="site-title" href="index.html">{title}</a>{sidebar}</aside><main class="site-content">{content}</main></body></html>"#,
+      style = SITE_STYLE,
+      title = escape_html(&self.config.title),
+      lang = escape_html(self.config.get_language()),
+    )
+  }
+
+  /// Links to the previous and next pages
+  fn get_pagination_html(&self, prev: Option<&Page>, next: Option<&Page>) -> String {
+    let prev = match prev {
+      Some(page) => format!(
+        r#"<a class="site-prev" href="{}">&larr; {}</a>"#,
+        escape_html(&page.file),
+        escape_html(&page.title)
+      ),
+      None => "<span></span>".to_string(),
+    };
+    let next = match next {
+      Some(page) => format!(
+        r#"<a class="site-next" href="{}">{} &rarr;</a>"#,
+        escape_html(&page.file),
+        escape_html(&page.title)
+      ),
+      None => "<span></span>".to_string(),
+    };
+
+    format!(r#"<nav class="site-pagination">{}{}</nav>"#, prev, next)
+  }
+
+  /// Home page: cover image, title, authors and table of contents
+  fn get_index_content_html(&self, toc_list: &str) -> String {
+    let mut html = String::new();
+
+    if let Some(image) = self.html_gen.get_cover_image() {
+      html.push_str(&format!(
+        r#"<img class="site-cover" src="assets/images/{}" alt="{}" />"#,
+        escape_html(image),
+        escape_html(&self.config.title)
+      ));
+    }
+
+    html.push_str(&format!("<h1>{}</h1>", escape_html(&self.config.title)));
+
+    let authors = self.config.get_author_names();
+    if !authors.is_empty() {
+      html.push_str(&format!(
+        r#"<p class="site-authors">{}</p>"#,
+        escape_html(&authors.join(", "))
+      ));
+    }
+
+    html.push_str(&format!(
+      r#"<div class="toc"><div id="toc-title">{}</div>{}</div>"#,
+      escape_html(&self.config.toc.title),
+      toc_list
+    ));
+
+    html
+  }
+
+  /// Copy assets and theme stylesheets next to the pages.
+  /// Theme templates (html files) are not needed by the website.
+  fn copy_resources(&self, site_path: &Path) -> Result<()> {
+    copy_files(&self.path.join("assets"), &site_path.join("assets"), |_| {
+      true
+    })?;
+    copy_files(&self.path.join("themes"), &site_path.join("themes"), |f| {
+      !matches!(f.extension().and_then(|e| e.to_str()), Some("html"))
+    })
+  }
+
+  /// Generate a multi-page website, one page per content file
+  pub fn generate(&self, site_path: PathBuf) -> Result<()> {
+    log::debug!("Generating website in {}", site_path.display());
+
+    if site_path.exists() {
+      remove_dir_all(&site_path)?;
+    }
+    create_dir_all(&site_path)?;
+
+    let head = self
+      .html_gen
+      .compile_components(&self.html_gen.get_theme_head_html()?)?;
+
+    let chapters = self.html_gen.get_chapters()?;
+
+    let mut toc = Toc::default();
+    let mut hrefs: HashMap<String, String> = HashMap::new();
+    let mut pages = vec![Page {
+      file: "index.html".to_string(),
+      title: self.config.title.clone(),
+    }];
+
+    for chapter in &chapters {
+      let file = self.get_page_file(chapter);
+      let chapter_toc = chapter.get_toc();
+      for entry in chapter_toc.entries() {
+        hrefs.insert(entry.id.clone(), file.clone());
+      }
+      if chapter.front_matter.toc {
+        toc.extend(chapter_toc);
+      }
+
+      pages.push(Page {
+        title: chapter
+          .get_title()
+          .unwrap_or_else(|| chapter.get_file_stem()),
+        file,
+      });
+    }
+
+    let toc_list = toc.to_list_html(self.config.toc.depth, |entry| {
+      let file = hrefs.get(&entry.id).map(|h| h.as_str()).unwrap_or("");
+      format!("{}#{}", file, entry.id)
+    });
+    let sidebar = format!("<nav>{}</nav>", toc_list);
+
+    let references = self
+      .html_gen
+      .get_references(&chapters, |chapter| self.get_page_file(chapter))?;
+    let mut contents = vec![self.get_index_content_html(&toc_list)];
+    for chapter in &chapters {
+      contents.push(self.html_gen.compile_chapter(chapter, &references)?);
+    }
+
+    for (index, (page, content)) in pages.iter().zip(contents).enumerate() {
+      let prev = index.checked_sub(1).and_then(|i| pages.get(i));
+      let next = pages.get(index + 1);
+
+      let content = format!("{}{}", content, self.get_pagination_html(prev, next));
+      let html = self.get_page_html(&page.title, &head, &sidebar, &content);
+
+      log::debug!("Generating page {}", page.file);
+      fs::write(site_path.join(&page.file), html)?;
+    }
+
+    self.copy_resources(&site_path)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_get_pagination_html() {
+    let config = Config::default();
+    let site = SiteGen::new(config, PathBuf::from("/tmp/book"), None);
+    let next = Page {
+      file: "001-intro.html".to_string(),
+      title: "Intro".to_string(),
+    };
+    let prev = Page {
+      file: "index.html".to_string(),
+      title: "Tom & <Jerry>".to_string(),
+    };
+
+    let html = site.get_pagination_html(None, Some(&next));
+    let prev_html = site.get_pagination_html(Some(&prev), None);
+
+    assert_eq!(
+      html,
+      r#"<nav class="site-pagination"><span></span><a class="site-next" href="001-intro.html">Intro &rarr;</a></nav>"#
+    );
+    assert!(prev_html.contains("&larr; Tom &amp; &lt;Jerry&gt;</a>"));
+  }
+}
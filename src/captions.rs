@@ -0,0 +1,210 @@
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+use crate::{config::CaptionsConfig, references::LabelKind};
+
+lazy_static! {
+    /// Match images alone in a paragraph, e.g.: `![Diagram](diagram.png "Caption")`
+    ///
+    /// Expect the capture naming groups:
+    /// - a: for the image attributes
+    static ref REG_FIGURE: Regex = Regex::new(r#"<p><img (?P<a>[^>]*?)\s*/></p>"#).unwrap();
+
+    /// Match the id and title of image attributes
+    ///
+    /// Expect the capture naming groups:
+    /// - n: for the attribute name
+    /// - v: for the attribute value
+    static ref REG_FIGURE_ATTRIBUTE: Regex = Regex::new(r#"(?:^|\s+)(?P<n>id|title)="(?P<v>[^"]*)""#).unwrap();
+
+    /// Match caption paragraphs written after a table, e.g.: `Table: Prices`
+    ///
+    /// Expect the capture naming groups:
+    /// - t: for the caption text
+    static ref REG_TABLE_CAPTION: Regex = Regex::new(r#"</table>\s*<p>Table:\s*(?P<t>[^\n]*?)</p>"#).unwrap();
+}
+
+/// Caption of a figure or table
+#[derive(Debug, Clone, PartialEq)]
+pub struct Caption {
+  pub kind: LabelKind,
+  /// Id of the figure or table, its label or a generated id, e.g.: `_figure-2`.
+  /// Generated ids start with `_`, so they never collide with labels (starting with a letter)
+  pub id: String,
+  /// Number with its label, e.g.: `Figure 2`
+  pub number: String,
+  /// Caption text, HTML escaped
+  pub title: String,
+}
+
+/// Numbers of figures and tables, counted across chapters
+#[derive(Debug, Clone)]
+pub struct CaptionNumbers {
+  figures: u32,
+  tables: u32,
+  /// Number displayed in figure captions, e.g.: `Figure {n}`
+  figure_label: String,
+  /// Number displayed in table captions, e.g.: `Table {n}`
+  table_label: String,
+}
+
+impl CaptionNumbers {
+  pub fn new(config: &CaptionsConfig) -> Self {
+    Self {
+      figures: 0,
+      tables: 0,
+      figure_label: config.figure_label.clone(),
+      table_label: config.table_label.clone(),
+    }
+  }
+
+  /// Wrap images with a title in figures, and add the `Table: ...` paragraphs
+  /// following tables as their caption. Captions are numbered.
+  ///
+  /// Return the HTML and the captions found
+  pub fn caption_html(&mut self, html: &str) -> (String, Vec<Caption>) {
+    let mut captions = Vec::new();
+
+    let html = REG_FIGURE.replace_all(html, |m: &Captures| {
+      let (mut id, mut title) = (None, None);
+      for attribute in REG_FIGURE_ATTRIBUTE.captures_iter(&m["a"]) {
+        match &attribute["n"] {
+          "id" => id = Some(attribute["v"].to_string()),
+          _ => title = Some(attribute["v"].to_string()),
+        }
+      }
+      let title = match title {
+        Some(title) => title,
+        None => return m[0].to_string(),
+      };
+
+      self.figures += 1;
+      let caption = Caption {
+        kind: LabelKind::Figure,
+        id: id.unwrap_or_else(|| format!("_figure-{}", self.figures)),
+        number: self.figure_label.replace("{n}", &self.figures.to_string()),
+        title,
+      };
+      let html = format!(
+        r#"<figure class="figure" id="{}"><img {} /><figcaption>{}</figcaption></figure>"#,
+        caption.id,
+        REG_FIGURE_ATTRIBUTE.replace_all(&m["a"], "").trim(),
+        get_caption_html(&caption)
+      );
+      captions.push(caption);
+      html
+    });
+
+    // Tables are found backwards from their caption, markdown tables can not be nested
+    let mut output = String::new();
+    let mut last = 0;
+    for m in REG_TABLE_CAPTION.captures_iter(&html) {
+      let end = m.get(0).unwrap();
+      let start = match html[last..end.start()].rfind("<table") {
+        Some(start) => last + start,
+        None => continue,
+      };
+      let tag_end = start + html[start..].find('>').unwrap_or_default() + 1;
+      let id = html[start..tag_end]
+        .strip_prefix(r#"<table id=""#)
+        .and_then(|tag| tag.strip_suffix(r#"">"#))
+        .map(|id| id.to_string());
+
+      self.tables += 1;
+      let caption = Caption {
+        kind: LabelKind::Table,
+        id: id.unwrap_or_else(|| format!("_table-{}", self.tables)),
+        number: self.table_label.replace("{n}", &self.tables.to_string()),
+        title: m["t"].to_string(),
+      };
+      output.push_str(&html[last..start]);
+      output.push_str(&format!(
+        r#"<table id="{}"><caption>{}</caption>"#,
+        caption.id,
+        get_caption_html(&caption)
+      ));
+      output.push_str(&html[tag_end..end.start()]);
+      output.push_str("</table>");
+      last = end.end();
+      captions.push(caption);
+    }
+    output.push_str(&html[last..]);
+
+    (output, captions)
+  }
+}
+
+fn get_caption_html(caption: &Caption) -> String {
+  format!(
+    r#"<span class="caption-number">{}</span> {}"#,
+    caption.number, caption.title
+  )
+}
+
+/// Build a list of figures or tables page, linking to every caption
+pub fn to_list_html(title: &str, class: &str, captions: &[Caption]) -> String {
+  let entries: String = captions
+    .iter()
+    .map(|caption| {
+      format!(
+        r##"<div class="caption-list-entry"><a href="#{}">{}</a></div>"##,
+        caption.id,
+        get_caption_html(caption)
+      )
+    })
+    .collect();
+
+  format!(
+    r#"<div class="caption-list {}"><div class="caption-list-title">{}</div>{}</div>"#,
+    class, title, entries
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_caption_html() {
+    let mut numbers = CaptionNumbers::new(&CaptionsConfig::default());
+    let html = concat!(
+      r#"<p><img src="a.png" alt="A" /></p>"#,
+      "\n",
+      r#"<p><img id="fig-b" src="b.png" alt="B" title="A &amp; B" /></p>"#,
+      "\n<table>\n<tr><td>1</td></tr>\n</table>\n<p>Table: Prices</p>\n"
+    );
+
+    let (html, captions) = numbers.caption_html(html);
+
+    assert_eq!(
+      html,
+      concat!(
+        r#"<p><img src="a.png" alt="A" /></p>"#,
+        "\n",
+        r#"<figure class="figure" id="fig-b"><img src="b.png" alt="B" /><figcaption><span class="caption-number">Figure 1</span> A &amp; B</figcaption></figure>"#,
+        "\n",
+        r#"<table id="_table-1"><caption><span class="caption-number">Table 1</span> Prices</caption>"#,
+        "\n<tr><td>1</td></tr>\n</table>\n"
+      )
+    );
+    assert_eq!(
+      captions.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(),
+      ["fig-b", "_table-1"]
+    );
+  }
+
+  #[test]
+  fn test_caption_html_numbers_across_chapters() {
+    let mut numbers = CaptionNumbers::new(&CaptionsConfig {
+      table_label: "Tab. {n}".to_string(),
+      ..CaptionsConfig::default()
+    });
+    let html = "<table id=\"tab-a\">\n</table>\n<p>Table: A</p>\n";
+
+    numbers.caption_html(html);
+    let (_, captions) = numbers.caption_html(html);
+
+    assert_eq!(captions[0].id, "tab-a");
+    assert_eq!(captions[0].number, "Tab. 2");
+  }
+}
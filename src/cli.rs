@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use clap::{ColorChoice, Parser, Subcommand};
+use clap::{ColorChoice, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -39,5 +39,31 @@ pub enum Commands {
     theme: Option<String>,
     /// File output name
     output: Option<String>,
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = Format::Pdf)]
+    format: Format,
   },
+  /// Preview the document in the browser, reloaded on every change
+  Serve {
+    /// Which theme should be used: light, dark or something else ?
+    theme: Option<String>,
+    /// Port of the local server
+    #[arg(short, long, default_value_t = 3000)]
+    port: u16,
+  },
+  /// Watch the project files and rebuild the PDF on every change
+  Watch {
+    /// Which theme should be used: light, dark or something else ?
+    theme: Option<String>,
+  },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+  /// PDF document, printed with chrome headless
+  Pdf,
+  /// EPUB 3 ebook
+  Epub,
+  /// Multi-page website
+  Html,
 }
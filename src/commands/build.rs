@@ -1,26 +1,42 @@
 use anyhow::Result;
 use colored::*;
+use headless_chrome::Browser;
 use std::path::PathBuf;
 
-use crate::{builders::HtmlGen, builders::PdfGen, config::Config, error::WordsmithError};
+use crate::{
+  builders::{EpubGen, HtmlGen, PdfGen, SiteGen},
+  cli::Format,
+  config::Config,
+  diagrams::DiagramCache,
+  error::WordsmithError,
+};
 
 #[derive(Debug)]
 pub struct Build {
   pub theme: Option<String>,
   pub path: PathBuf,
+  pub format: Format,
 }
 
 impl Build {
-  pub fn new(path: PathBuf, theme: Option<String>) -> Self {
-    Self { theme, path }
+  pub fn new(path: PathBuf, theme: Option<String>, format: Format) -> Self {
+    Self {
+      theme,
+      path,
+      format,
+    }
   }
 
-  fn load_config(&self) -> Result<Config> {
+  pub(crate) fn load_config(&self) -> Result<Config> {
     let config_file = &self.path.join("ws.yaml");
     if !config_file.exists() {
       return Ok(Config::default());
     }
-    Config::new().load_from_file(config_file)
+    let (config, warnings) = Config::load_from_file(config_file)?;
+    for warning in warnings {
+      eprintln!("{} {}", "Warning:".yellow(), warning);
+    }
+    Ok(config)
   }
 
   pub fn execute(&self) -> Result<()> {
@@ -34,16 +50,45 @@ impl Build {
 
     let config = &self.load_config()?;
 
+    match self.format {
+      Format::Pdf => self.build_pdf(config)?,
+      Format::Epub => self.build_epub(config)?,
+      Format::Html => self.build_site(config)?,
+    }
+
+    println!("{}", "Done!".green());
+
+    log::debug!("Build is complete");
+    Ok(())
+  }
+
+  fn build_pdf(&self, config: &Config) -> Result<()> {
+    let browser = PdfGen::launch_browser(PdfGen::DEFAULT_IDLE_TIMEOUT)?;
+    self.build_pdf_with_browser(config, &browser)
+  }
+
+  /// Build the PDF reusing a running browser
+  pub(crate) fn build_pdf_with_browser(&self, config: &Config, browser: &Browser) -> Result<()> {
     let doc_builder = HtmlGen::new(config.clone(), self.path.clone(), self.theme.clone());
-    let (html_file, _) = doc_builder.build()?;
+    let (html_file, toc) = doc_builder.build()?;
 
-    PdfGen::new(config).generate(html_file, self.path.join("output/pdf.pdf"))?;
+    PdfGen::new(config)
+      .with_toc(toc)
+      .with_diagram_cache(DiagramCache::new(&self.path, &config.diagrams))
+      .generate_with_browser(browser, html_file, self.path.join("output/pdf.pdf"))?;
 
     doc_builder.clean_after_build();
 
-    println!("{}", "Done!".green());
-
-    log::debug!("Build is complete");
     Ok(())
   }
+
+  fn build_epub(&self, config: &Config) -> Result<()> {
+    EpubGen::new(config.clone(), self.path.clone(), self.theme.clone())
+      .generate(self.path.join("output/epub.epub"))
+  }
+
+  fn build_site(&self, config: &Config) -> Result<()> {
+    SiteGen::new(config.clone(), self.path.clone(), self.theme.clone())
+      .generate(self.path.join("output/site"))
+  }
 }
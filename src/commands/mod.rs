@@ -1,5 +1,9 @@
 mod build;
 mod init;
+mod serve;
+mod watch;
 
 pub use build::Build;
 pub use init::Init;
+pub use serve::Serve;
+pub use watch::Watch;
@@ -0,0 +1,330 @@
+use anyhow::Result;
+use colored::*;
+use std::{
+  fs,
+  io::{self, BufRead, BufReader, Write},
+  net::{TcpListener, TcpStream},
+  path::{Component, Path, PathBuf},
+  sync::{
+    mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+    Arc, Mutex, RwLock,
+  },
+  thread,
+  time::Duration,
+};
+
+use crate::{
+  builders::HtmlGen,
+  cli::Format,
+  commands::{Build, Watch},
+  components::ComponentArg,
+  error::WordsmithError,
+  utils::{escape_html, get_media_type},
+};
+
+/// Url of the server-sent events stream used to reload the page
+const EVENTS_URL: &str = "/__wordsmith/events";
+
+/// Script injected into the page, reloads it when the sources change
+const RELOAD_SCRIPT: &str = r#"<script>new EventSource("/__wordsmith/events").onmessage = function () { window.location.reload(); };</script>"#;
+
+/// Folders served next to the page
+const SERVED_FOLDERS: [&str; 2] = ["assets", "themes"];
+
+/// Interval of the keep alive messages, also used to detect closed connections
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// State shared by the HTTP server and the watcher
+#[derive(Debug, Default)]
+struct Preview {
+  /// Current page, with the reload script
+  html: RwLock<String>,
+  /// Pages waiting for a reload event
+  clients: Mutex<Vec<Sender<()>>>,
+}
+
+impl Preview {
+  fn get_html(&self) -> String {
+    self.html.read().unwrap().clone()
+  }
+
+  fn set_html(&self, html: String) {
+    *self.html.write().unwrap() = html;
+  }
+
+  fn subscribe(&self) -> Receiver<()> {
+    let (tx, rx) = channel();
+    self.clients.lock().unwrap().push(tx);
+    rx
+  }
+
+  /// Ask all open pages to reload, dropping closed connections
+  fn reload(&self) {
+    self
+      .clients
+      .lock()
+      .unwrap()
+      .retain(|client| client.send(()).is_ok());
+  }
+}
+
+#[derive(Debug)]
+pub struct Serve {
+  pub theme: Option<String>,
+  pub path: PathBuf,
+  pub port: u16,
+}
+
+impl Serve {
+  pub fn new(path: PathBuf, theme: Option<String>, port: u16) -> Self {
+    Self { theme, path, port }
+  }
+
+  /// Add the reload script at the end of the body
+  fn inject_reload_script(html: &str) -> String {
+    match html.rfind("</body>") {
+      Some(index) => format!("{}{}{}", &html[..index], RELOAD_SCRIPT, &html[index..]),
+      None => format!("{}{}", html, RELOAD_SCRIPT),
+    }
+  }
+
+  /// Page displayed while the project has errors, so it reloads once they are fixed
+  fn get_error_html(error: &anyhow::Error) -> String {
+    format!(
+      r#"<!DOCTYPE html><html><head><meta charset="utf-8"><title>Build failed</title></head><body><h1>Build failed</h1><pre>{}</pre></body></html>"#,
+      escape_html(&format!("{:?}", error))
+    )
+  }
+
+  /// Generate the document HTML, assets, themes and diagram scripts use relative urls
+  fn generate_html(&self, root: &Path) -> Result<String> {
+    let config = Build::new(root.to_path_buf(), self.theme.clone(), Format::Html).load_config()?;
+
+    HtmlGen::new(config, root.to_path_buf(), self.theme.clone())
+      .with_component_arg("assets_path", ComponentArg::String("assets".to_string()))
+      .with_component_arg("themes_path", ComponentArg::String("themes".to_string()))
+      .with_relative_script_urls()
+      .generate_html_file_content()
+  }
+
+  /// Regenerate the page and reload the open pages
+  fn refresh(&self, root: &Path, preview: &Preview) {
+    println!("{}", "Building...".yellow());
+
+    let html = match self.generate_html(root) {
+      Ok(html) => {
+        println!("{}", "Done!".green());
+        html
+      }
+      Err(e) => {
+        println!("{} {}", "Build failed:".red(), e);
+        Self::get_error_html(&e)
+      }
+    };
+
+    preview.set_html(Self::inject_reload_script(&html));
+    preview.reload();
+  }
+
+  /// Decode `%XX` sequences of an url path, e.g.: `my%20image.png`
+  fn decode_url_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut index = 0;
+    while index < bytes.len() {
+      let hex = bytes
+        .get(index + 1..index + 3)
+        .and_then(|hex| std::str::from_utf8(hex).ok())
+        .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+      match (bytes[index], hex) {
+        (b'%', Some(byte)) => {
+          decoded.push(byte);
+          index += 3;
+        }
+        (byte, _) => {
+          decoded.push(byte);
+          index += 1;
+        }
+      }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+  }
+
+  /// Map an url path to a file of the served folders.
+  ///
+  /// Return `None` for paths outside of them, e.g.: `/assets/../ws.yaml`
+  fn get_file_path(root: &Path, url_path: &str) -> Option<PathBuf> {
+    let relative = PathBuf::from(url_path.trim_start_matches('/'));
+
+    let mut components = relative.components();
+    let folder = match components.next() {
+      Some(Component::Normal(folder)) => folder.to_string_lossy().to_string(),
+      _ => return None,
+    };
+    if !SERVED_FOLDERS.contains(&folder.as_str()) {
+      return None;
+    }
+    if !components.all(|c| matches!(c, Component::Normal(_))) {
+      return None;
+    }
+
+    Some(root.join(relative))
+  }
+
+  fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+  ) -> io::Result<()> {
+    write!(
+      stream,
+      "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+      status,
+      content_type,
+      body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+  }
+
+  /// Keep the connection open and send a message on every reload
+  fn stream_events(stream: &mut TcpStream, preview: &Preview) -> io::Result<()> {
+    write!(
+      stream,
+      "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\nConnection: keep-alive\r\n\r\n"
+    )?;
+    stream.flush()?;
+
+    let events = preview.subscribe();
+    loop {
+      match events.recv_timeout(KEEP_ALIVE) {
+        Ok(_) => write!(stream, "data: reload\n\n")?,
+        Err(RecvTimeoutError::Timeout) => write!(stream, ": keep-alive\n\n")?,
+        Err(RecvTimeoutError::Disconnected) => return Ok(()),
+      }
+      stream.flush()?;
+    }
+  }
+
+  fn handle_connection(mut stream: TcpStream, root: &Path, preview: &Preview) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip headers
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+      header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let url_path = Self::decode_url_path(target.split('?').next().unwrap_or_default());
+
+    log::debug!("{} {}", method, url_path);
+
+    if method != "GET" {
+      return Self::respond(&mut stream, "405 Method Not Allowed", "text/plain", b"");
+    }
+
+    match url_path.as_str() {
+      "/" | "/index.html" => Self::respond(
+        &mut stream,
+        "200 OK",
+        "text/html; charset=utf-8",
+        preview.get_html().as_bytes(),
+      ),
+      EVENTS_URL => Self::stream_events(&mut stream, preview),
+      path => match Self::get_file_path(root, path).filter(|file| file.is_file()) {
+        Some(file) => Self::respond(
+          &mut stream,
+          "200 OK",
+          get_media_type(&file).unwrap_or("application/octet-stream"),
+          &fs::read(&file)?,
+        ),
+        None => Self::respond(&mut stream, "404 Not Found", "text/plain", b"Not found"),
+      },
+    }
+  }
+
+  /// Accept connections in a background thread, one thread per connection
+  fn start_server(&self, root: &Path, preview: Arc<Preview>) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", self.port))?;
+    println!(
+      "{} http://127.0.0.1:{}",
+      "Serving on".cyan(),
+      listener.local_addr()?.port()
+    );
+
+    let root = root.to_path_buf();
+    thread::spawn(move || {
+      for stream in listener.incoming().flatten() {
+        let root = root.clone();
+        let preview = Arc::clone(&preview);
+        thread::spawn(move || {
+          if let Err(e) = Self::handle_connection(stream, &root, &preview) {
+            log::debug!("Connection closed: {}", e);
+          }
+        });
+      }
+    });
+
+    Ok(())
+  }
+
+  pub fn execute(&self) -> Result<()> {
+    if !self.path.join(".ws-lock").exists() {
+      return Err(WordsmithError::ProjectNotFound.into());
+    }
+
+    let root = self.path.canonicalize()?;
+    let preview = Arc::new(Preview::default());
+
+    self.refresh(&root, &preview);
+    self.start_server(&root, Arc::clone(&preview))?;
+
+    Watch::watch_changes(&root, || self.refresh(&root, &preview))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_inject_reload_script() {
+    let html = Serve::inject_reload_script("<html><body><p>text</p></body></html>");
+
+    assert_eq!(
+      html,
+      format!("<html><body><p>text</p>{}</body></html>", RELOAD_SCRIPT)
+    );
+  }
+
+  #[test]
+  fn test_decode_url_path() {
+    assert_eq!(
+      Serve::decode_url_path("/assets/images/my%20cover.png"),
+      "/assets/images/my cover.png"
+    );
+    assert_eq!(Serve::decode_url_path("/100%"), "/100%");
+  }
+
+  #[test]
+  fn test_get_file_path() {
+    let root = Path::new("/book");
+
+    assert_eq!(
+      Serve::get_file_path(root, "/assets/images/cover.jpg"),
+      Some(PathBuf::from("/book/assets/images/cover.jpg"))
+    );
+    assert_eq!(Serve::get_file_path(root, "/ws.yaml"), None);
+    assert_eq!(Serve::get_file_path(root, "/assets/../ws.yaml"), None);
+    assert_eq!(Serve::get_file_path(root, "/content/001-intro.md"), None);
+  }
+}
@@ -0,0 +1,282 @@
+use anyhow::Result;
+use colored::*;
+use headless_chrome::Browser;
+use notify::{
+  event::{ModifyKind, RenameMode},
+  Event, EventKind, RecursiveMode, Watcher,
+};
+use std::{
+  collections::BTreeMap,
+  fmt,
+  path::{Path, PathBuf},
+  sync::mpsc::{channel, RecvTimeoutError},
+  time::{Duration, Instant},
+};
+
+use crate::{builders::PdfGen, cli::Format, commands::Build, error::WordsmithError};
+
+/// Folders of the project that trigger a rebuild
+const WATCHED_FOLDERS: [&str; 3] = ["content", "themes", "assets"];
+
+/// Configuration file, also triggers a rebuild
+const CONFIG_FILE: &str = "ws.yaml";
+
+/// Wait for more events before rebuilding, editors usually write a file more than once
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Keep the browser connection open between rebuilds
+const BROWSER_IDLE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// Kind of change of a project file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+  Created,
+  Modified,
+  Removed,
+}
+
+impl Change {
+  /// Map file system events to changes.
+  ///
+  /// Access and metadata events are ignored, chrome reads assets
+  /// while printing and must not trigger another rebuild.
+  fn from_kind(kind: &EventKind) -> Option<Self> {
+    match kind {
+      EventKind::Create(_) => Some(Change::Created),
+      EventKind::Remove(_) => Some(Change::Removed),
+      EventKind::Modify(ModifyKind::Metadata(_)) => None,
+      EventKind::Modify(ModifyKind::Name(RenameMode::From)) => Some(Change::Removed),
+      EventKind::Modify(ModifyKind::Name(RenameMode::To)) => Some(Change::Created),
+      EventKind::Modify(_) | EventKind::Any => Some(Change::Modified),
+      EventKind::Access(_) | EventKind::Other => None,
+    }
+  }
+}
+
+impl fmt::Display for Change {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let label = match self {
+      Change::Created => "created",
+      Change::Modified => "modified",
+      Change::Removed => "removed",
+    };
+    // Pad to align the file names of the summary
+    f.pad(label)
+  }
+}
+
+#[derive(Debug)]
+pub struct Watch {
+  pub theme: Option<String>,
+  pub path: PathBuf,
+}
+
+impl Watch {
+  pub fn new(path: PathBuf, theme: Option<String>) -> Self {
+    Self { theme, path }
+  }
+
+  /// Check if a path, relative to the project folder, should trigger a rebuild.
+  ///
+  /// Hidden files and editor backups (`file.md~`) are ignored.
+  fn is_watched(relative: &Path) -> bool {
+    let file_name = relative
+      .file_name()
+      .map(|name| name.to_string_lossy())
+      .unwrap_or_default();
+    if file_name.starts_with('.') || file_name.ends_with('~') {
+      return false;
+    }
+
+    if relative == Path::new(CONFIG_FILE) {
+      return true;
+    }
+
+    let mut components = relative.components();
+    let folder = components.next().map(|c| c.as_os_str().to_string_lossy());
+    components.next().is_some()
+      && matches!(folder, Some(folder) if WATCHED_FOLDERS.contains(&folder.as_ref()))
+  }
+
+  /// Add the project files of an event to the list of changes
+  fn collect_changes(
+    root: &Path,
+    event: notify::Result<Event>,
+    changes: &mut BTreeMap<PathBuf, Change>,
+  ) {
+    let event = match event {
+      Ok(event) => event,
+      Err(e) => {
+        log::warn!("Watch error: {}", e);
+        return;
+      }
+    };
+
+    let change = match Change::from_kind(&event.kind) {
+      Some(change) => change,
+      None => return,
+    };
+
+    for path in event.paths {
+      let relative = match path.strip_prefix(root) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => continue,
+      };
+      if !Self::is_watched(&relative) {
+        continue;
+      }
+
+      changes
+        .entry(relative)
+        .and_modify(|current| {
+          // A new file that is written afterwards is still a new file
+          if *current != Change::Created || change == Change::Removed {
+            *current = change;
+          }
+        })
+        .or_insert(change);
+    }
+  }
+
+  fn print_changes(changes: &BTreeMap<PathBuf, Change>) {
+    println!("{}", "Changes detected:".yellow());
+    for (path, change) in changes {
+      println!("  {:<8} {}", change, path.display());
+    }
+  }
+
+  /// Build the PDF, launching a new browser if there is none
+  /// or the connection was closed
+  fn build(&self, build: &Build, browser: &mut Option<Browser>) -> Result<()> {
+    let config = build.load_config()?;
+
+    if browser.as_ref().is_some_and(|b| b.get_version().is_err()) {
+      log::debug!("Browser connection closed");
+      *browser = None;
+    }
+    if browser.is_none() {
+      log::debug!("Launching browser");
+      *browser = Some(PdfGen::launch_browser(BROWSER_IDLE_TIMEOUT)?);
+    }
+
+    build.build_pdf_with_browser(&config, browser.as_ref().unwrap())
+  }
+
+  /// Rebuild and report the result, errors do not stop watching
+  fn rebuild(&self, build: &Build, browser: &mut Option<Browser>) {
+    println!("{}", "Building...".yellow());
+    let started = Instant::now();
+
+    match self.build(build, browser) {
+      Ok(_) => println!("{} ({:.2?})", "Done!".green(), started.elapsed()),
+      Err(e) => println!("{} {}", "Build failed:".red(), e),
+    }
+  }
+
+  /// Watch the project files of `root` and call `on_change` once no
+  /// more events are received for a while. Blocks until the watcher stops.
+  pub(crate) fn watch_changes<F>(root: &Path, mut on_change: F) -> Result<()>
+  where
+    F: FnMut(),
+  {
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for folder in WATCHED_FOLDERS {
+      let path = root.join(folder);
+      if path.exists() {
+        watcher.watch(&path, RecursiveMode::Recursive)?;
+      }
+    }
+    // The project folder itself is watched for changes in the configuration file
+    watcher.watch(root, RecursiveMode::NonRecursive)?;
+
+    println!("{}", "Watching for changes, press Ctrl+C to stop".cyan());
+
+    loop {
+      let mut changes = BTreeMap::new();
+      Self::collect_changes(root, rx.recv()?, &mut changes);
+
+      // Wait until no events are received for a while
+      loop {
+        match rx.recv_timeout(DEBOUNCE) {
+          Ok(event) => Self::collect_changes(root, event, &mut changes),
+          Err(RecvTimeoutError::Timeout) => break,
+          Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+      }
+
+      if changes.is_empty() {
+        continue;
+      }
+
+      Self::print_changes(&changes);
+      on_change();
+    }
+  }
+
+  pub fn execute(&self) -> Result<()> {
+    if !self.path.join(".ws-lock").exists() {
+      return Err(WordsmithError::ProjectNotFound.into());
+    }
+
+    let root = self.path.canonicalize()?;
+    let build = Build::new(root.clone(), self.theme.clone(), Format::Pdf);
+    let mut browser: Option<Browser> = None;
+
+    self.rebuild(&build, &mut browser);
+
+    Self::watch_changes(&root, || self.rebuild(&build, &mut browser))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use notify::event::{AccessKind, CreateKind, DataChange, MetadataKind};
+
+  #[test]
+  fn test_is_watched() {
+    assert!(Watch::is_watched(Path::new("ws.yaml")));
+    assert!(Watch::is_watched(Path::new("content/001-intro.md")));
+    assert!(Watch::is_watched(Path::new("assets/images/cover.jpg")));
+    assert!(!Watch::is_watched(Path::new("content")));
+    assert!(!Watch::is_watched(Path::new("output/pdf.pdf")));
+    assert!(!Watch::is_watched(Path::new("content/.001-intro.md.swp")));
+    assert!(!Watch::is_watched(Path::new("content/001-intro.md~")));
+  }
+
+  #[test]
+  fn test_collect_changes() {
+    let root = Path::new("/book");
+    let event = |kind: EventKind, path: &str| Ok(Event::new(kind).add_path(root.join(path)));
+    let mut changes = BTreeMap::new();
+
+    for event in [
+      event(EventKind::Create(CreateKind::File), "content/002-new.md"),
+      event(
+        EventKind::Modify(ModifyKind::Data(DataChange::Any)),
+        "content/002-new.md",
+      ),
+      event(
+        EventKind::Modify(ModifyKind::Data(DataChange::Any)),
+        "ws.yaml",
+      ),
+      event(EventKind::Access(AccessKind::Any), "assets/images/a.png"),
+      event(
+        EventKind::Modify(ModifyKind::Metadata(MetadataKind::Any)),
+        "content/001-intro.md",
+      ),
+      event(EventKind::Create(CreateKind::File), "output/pdf.pdf"),
+    ] {
+      Watch::collect_changes(root, event, &mut changes);
+    }
+
+    assert_eq!(
+      changes,
+      BTreeMap::from([
+        (PathBuf::from("content/002-new.md"), Change::Created),
+        (PathBuf::from("ws.yaml"), Change::Modified),
+      ])
+    );
+  }
+}
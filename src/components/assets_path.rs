@@ -1,55 +1,47 @@
-use lazy_static::lazy_static;
-use regex::Regex;
-
-use super::Component;
-
-lazy_static! {
-    /// Match tag util to include a reference to the assets path
-    static ref REG_ASSETS_PATH: Regex = Regex::new(r"@assets_path").unwrap();
-}
+use super::{Attributes, Component, Context};
 
+/// `@assets_path`, replaced by the `assets_path` argument, a folder path or url
 #[derive(Debug, Clone)]
-pub struct AssetsPath {
-  assets_path: String,
-}
+pub struct AssetsPath;
 
-impl AssetsPath {
-  pub fn new(path: String) -> Self {
-    Self { assets_path: path }
+impl Component for AssetsPath {
+  fn is_source_tag(&self) -> bool {
+    true
   }
-}
 
-impl Component for AssetsPath {
-  fn compile(&self, input: &str) -> anyhow::Result<String> {
-    log::info!(
-      "compile_tag_assets_path: {:?} => {}",
-      REG_ASSETS_PATH.as_str(),
-      &self.assets_path
-    );
-    Ok(
-      REG_ASSETS_PATH
-        .replace_all(input, &self.assets_path)
-        .to_string(),
-    )
+  fn compile(
+    &self,
+    _content: &str,
+    _attributes: &Attributes,
+    context: &Context,
+  ) -> anyhow::Result<String> {
+    let assets_path = context.get_string_arg("assets_path");
+    log::info!("compile assets_path: {}", &assets_path);
+    Ok(assets_path)
   }
 }
 
 #[cfg(test)]
 mod tests {
+  use std::collections::HashMap;
+
   use super::*;
+  use crate::components::ComponentArg;
 
   #[test]
   fn test_compile() {
-    let assets_path = AssetsPath {
-      assets_path: "/path/to/assets".to_owned(),
+    let args = HashMap::from([(
+      "assets_path".to_string(),
+      ComponentArg::String("/path/to/assets".to_string()),
+    )]);
+    let context = Context {
+      name: "assets_path",
+      args: &args,
     };
 
-    let input = "random text @assets_path here";
-    let expected_output = "random text /path/to/assets here";
-
-    let result = assets_path.compile(input);
+    let result = AssetsPath.compile("", &Attributes::default(), &context);
 
     assert!(result.is_ok());
-    assert_eq!(result.unwrap(), expected_output);
+    assert_eq!(result.unwrap(), "/path/to/assets");
   }
 }
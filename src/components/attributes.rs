@@ -0,0 +1,258 @@
+use std::{collections::HashMap, fmt};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    /// Match attribute names, e.g.: `title`, `data-id`
+    static ref REG_ATTRIBUTE_NAME: Regex = Regex::new(r"^[A-Za-z_][A-Za-z0-9_-]*$").unwrap();
+
+    /// Match numbers, e.g.: `10`, `-1.5`
+    static ref REG_NUMBER: Regex = Regex::new(r"^-?\d+(\.\d+)?$").unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComponentArg {
+  String(String),
+  Number(f64),
+  Bool(bool),
+  List(Vec<ComponentArg>),
+}
+
+impl ComponentArg {
+  /// Type a value written without quotes, e.g.: `true`, `10`, `intro.md`
+  fn from_bare(value: &str) -> Self {
+    match value {
+      "true" => ComponentArg::Bool(true),
+      "false" => ComponentArg::Bool(false),
+      value if REG_NUMBER.is_match(value) => ComponentArg::Number(value.parse().unwrap()),
+      value => ComponentArg::String(value.to_string()),
+    }
+  }
+}
+
+/// Values as written in HTML, list items are separated by spaces (e.g.: CSS classes)
+impl fmt::Display for ComponentArg {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ComponentArg::String(value) => write!(f, "{}", value),
+      ComponentArg::Number(value) => write!(f, "{}", value),
+      ComponentArg::Bool(value) => write!(f, "{}", value),
+      ComponentArg::List(values) => {
+        let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        write!(f, "{}", values.join(" "))
+      }
+    }
+  }
+}
+
+/// Arguments of a tag, e.g.: `@info(title="Heads up", icon=bulb)` or `@ref(intro)`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Attributes {
+  /// Values without a name, in order
+  pub positional: Vec<ComponentArg>,
+  pub named: HashMap<String, ComponentArg>,
+}
+
+impl Attributes {
+  pub fn get(&self, name: &str) -> Option<&ComponentArg> {
+    self.named.get(name)
+  }
+
+  pub fn get_positional(&self, index: usize) -> Option<&ComponentArg> {
+    self.positional.get(index)
+  }
+
+  /// Named or positional value as text, e.g.: to fill a template
+  pub fn get_string(&self, name: &str) -> Option<String> {
+    match name.parse::<usize>() {
+      Ok(index) => self.get_positional(index),
+      Err(_) => self.get(name),
+    }
+    .map(|value| value.to_string())
+  }
+
+  /// Parse arguments, the source starts after the opening parenthesis.
+  ///
+  /// Return the arguments and the length of the source used, including the
+  /// closing parenthesis. Values can be quoted, lists (`[a, b]`), numbers,
+  /// booleans, or text without quotes, e.g.: `@include(intro.md, lines=1-10)`
+  pub fn parse(source: &str) -> Result<(Self, usize), String> {
+    let mut parser = AttributesParser {
+      chars: source.char_indices().collect(),
+      position: 0,
+    };
+    let attributes = parser.parse_attributes()?;
+    let length = parser
+      .chars
+      .get(parser.position)
+      .map(|(index, _)| *index)
+      .unwrap_or(source.len());
+    Ok((attributes, length))
+  }
+}
+
+struct AttributesParser {
+  chars: Vec<(usize, char)>,
+  position: usize,
+}
+
+impl AttributesParser {
+  fn peek(&self) -> Option<char> {
+    self.chars.get(self.position).map(|(_, c)| *c)
+  }
+
+  fn skip_whitespace(&mut self) {
+    while self.peek().is_some_and(|c| c.is_whitespace()) {
+      self.position += 1;
+    }
+  }
+
+  fn parse_quoted(&mut self, quote: char) -> Result<String, String> {
+    self.position += 1;
+    let mut value = String::new();
+    loop {
+      match self.peek() {
+        Some('\\') => {
+          self.position += 1;
+          if let Some(c) = self.peek() {
+            value.push(c);
+            self.position += 1;
+          }
+        }
+        Some(c) if c == quote => {
+          self.position += 1;
+          return Ok(value);
+        }
+        Some(c) => {
+          value.push(c);
+          self.position += 1;
+        }
+        None => return Err(format!("missing {} at the end of the text", quote)),
+      }
+    }
+  }
+
+  /// Text until the next separator
+  fn parse_bare(&mut self) -> String {
+    let mut value = String::new();
+    while let Some(c) = self.peek().filter(|c| !matches!(c, ',' | ')' | ']' | '=')) {
+      value.push(c);
+      self.position += 1;
+    }
+    value.trim().to_string()
+  }
+
+  fn parse_value(&mut self) -> Result<ComponentArg, String> {
+    self.skip_whitespace();
+    match self.peek() {
+      Some(quote @ ('"' | '\'')) => Ok(ComponentArg::String(self.parse_quoted(quote)?)),
+      Some('[') => {
+        self.position += 1;
+        let mut values = Vec::new();
+        loop {
+          self.skip_whitespace();
+          match self.peek() {
+            Some(']') => {
+              self.position += 1;
+              return Ok(ComponentArg::List(values));
+            }
+            Some(',') => self.position += 1,
+            Some(_) => values.push(self.parse_value()?),
+            None => return Err("missing ] at the end of the list".to_string()),
+          }
+        }
+      }
+      _ => Ok(ComponentArg::from_bare(&self.parse_bare())),
+    }
+  }
+
+  fn parse_attributes(&mut self) -> Result<Attributes, String> {
+    let mut attributes = Attributes::default();
+
+    loop {
+      self.skip_whitespace();
+      match self.peek() {
+        Some(')') => {
+          self.position += 1;
+          return Ok(attributes);
+        }
+        Some(',') => {
+          self.position += 1;
+          continue;
+        }
+        None => return Err("missing ) at the end of the arguments".to_string()),
+        Some(_) => {}
+      }
+
+      let start = self.position;
+      let value = self.parse_value()?;
+      if self.position == start {
+        return Err(format!("unexpected {}", self.peek().unwrap_or_default()));
+      }
+      self.skip_whitespace();
+
+      if self.peek() != Some('=') {
+        attributes.positional.push(value);
+        continue;
+      }
+
+      // Named argument, the value parsed is the name
+      let name = match value {
+        ComponentArg::String(name) if REG_ATTRIBUTE_NAME.is_match(&name) => name,
+        _ => {
+          let text: String = self.chars[start..self.position]
+            .iter()
+            .map(|(_, c)| c)
+            .collect();
+          return Err(format!("{} is not a valid argument name", text.trim()));
+        }
+      };
+      self.position += 1;
+      let value = self.parse_value()?;
+      attributes.named.insert(name, value);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    let (attributes, length) = Attributes::parse(
+      r#"src/main.rs, title="Heads \"up\"", lines=10-30, n=2, open=true, class=[a, 'b c']) text"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+      attributes.positional,
+      vec![ComponentArg::String("src/main.rs".to_string())]
+    );
+    assert_eq!(attributes.get_string("title").unwrap(), r#"Heads "up""#);
+    assert_eq!(attributes.get_string("lines").unwrap(), "10-30");
+    assert_eq!(attributes.get("n"), Some(&ComponentArg::Number(2.0)));
+    assert_eq!(attributes.get("open"), Some(&ComponentArg::Bool(true)));
+    assert_eq!(attributes.get_string("class").unwrap(), "a b c");
+    assert_eq!(attributes.get_string("0").unwrap(), "src/main.rs");
+    assert_eq!(length, 81);
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    assert_eq!(
+      Attributes::parse(r#"title="a"#),
+      Err("missing \" at the end of the text".to_string())
+    );
+    assert_eq!(
+      Attributes::parse("a, b"),
+      Err("missing ) at the end of the arguments".to_string())
+    );
+    assert_eq!(Attributes::parse("a])"), Err("unexpected ]".to_string()));
+    assert_eq!(
+      Attributes::parse("a b=c)"),
+      Err("a b is not a valid argument name".to_string())
+    );
+  }
+}
@@ -1,49 +1,40 @@
-use lazy_static::lazy_static;
-use regex::Regex;
-
 use crate::components::BREAK_PAGE_HTML;
 
-use super::Component;
-
-lazy_static! {
-    /// Match tag util to include page break
-    static ref REG_BREAK: Regex = Regex::new(r"@break").unwrap();
-}
+use super::{Attributes, Component, Context};
 
+/// `@break`, starts a new page
 #[derive(Debug, Clone)]
 pub struct BreakTag;
 
-impl BreakTag {
-  pub fn new() -> Self {
-    Self {}
-  }
-}
-
 impl Component for BreakTag {
-  fn compile(&self, input: &str) -> anyhow::Result<String> {
-    log::info!("break_tag: {:?}", REG_BREAK.as_str());
-    Ok(
-      REG_BREAK
-        .replace_all(input, BREAK_PAGE_HTML.to_string())
-        .to_string(),
-    )
+  fn compile(
+    &self,
+    _content: &str,
+    _attributes: &Attributes,
+    _context: &Context,
+  ) -> anyhow::Result<String> {
+    log::info!("compile break tag");
+    Ok(BREAK_PAGE_HTML.to_string())
   }
 }
 
 #[cfg(test)]
 mod tests {
+  use std::collections::HashMap;
+
   use super::*;
 
   #[test]
   fn test_compile() {
-    let break_tag = BreakTag {};
-
-    let input = "random text @break here";
-    let expected_output = format!("random text {} here", *BREAK_PAGE_HTML);
+    let args = HashMap::new();
+    let context = Context {
+      name: "break",
+      args: &args,
+    };
 
-    let result = break_tag.compile(input);
+    let result = BreakTag.compile("", &Attributes::default(), &context);
 
     assert!(result.is_ok());
-    assert_eq!(result.unwrap(), expected_output);
+    assert_eq!(result.unwrap(), *BREAK_PAGE_HTML);
   }
 }
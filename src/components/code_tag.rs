@@ -0,0 +1,142 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use super::{include_tag::get_snippet, Attributes, Component, Context};
+
+/// `@code(path, lang, region)`, inserts a source file of the project as a fenced code block,
+/// so the code of the document is the tested code, e.g.:
+/// `@code(../examples/src/main.rs, rust, setup)`.
+///
+/// The path is relative to the content folder, the language defaults to the file
+/// extension. `region` (or `region=`) and `lines` select part of the file like `@include`,
+/// the common indentation of the lines is removed.
+/// The tag must be the only content of its line.
+#[derive(Debug, Clone)]
+pub struct CodeTag;
+
+impl Component for CodeTag {
+  fn is_source_tag(&self) -> bool {
+    true
+  }
+
+  fn compile(&self, content: &str, attributes: &Attributes, _context: &Context) -> Result<String> {
+    log::info!("compile code tag");
+
+    let lang = attributes
+      .get_string("1")
+      .or_else(|| attributes.get_string("lang"))
+      .or_else(|| {
+        attributes
+          .get_string("0")
+          .and_then(|file| Some(Path::new(&file).extension()?.to_str()?.to_string()))
+      })
+      .unwrap_or_default();
+    if attributes.positional.len() > 3 {
+      return Err(anyhow!("too many arguments, use @code(path, lang, region)"));
+    }
+    // The third argument is the region, e.g.: `@code(main.rs, rust, setup)`
+    let mut attributes = attributes.clone();
+    if let Some(region) = attributes.get_positional(2).cloned() {
+      attributes
+        .named
+        .entry("region".to_string())
+        .or_insert(region);
+    }
+    let code = remove_indentation(&get_snippet(content, &attributes)?);
+
+    // The fence is longer than any backtick run of the code
+    let longest = code
+      .split(|c| c != '`')
+      .map(|run| run.len())
+      .max()
+      .unwrap_or_default();
+    let fence = "`".repeat(longest.max(2) + 1);
+
+    Ok(format!(
+      "{fence}{lang}\n{code}\n{fence}",
+      code = code.trim_end_matches('\n')
+    ))
+  }
+}
+
+/// Remove the indentation shared by all lines, empty lines are ignored
+fn remove_indentation(code: &str) -> String {
+  let indentation = code
+    .lines()
+    .filter(|line| !line.trim().is_empty())
+    .map(|line| line.len() - line.trim_start().len())
+    .min()
+    .unwrap_or_default();
+
+  code
+    .split_inclusive('\n')
+    .map(|line| {
+      line
+        .get(indentation..)
+        .unwrap_or(line.trim_start_matches(' '))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use super::*;
+
+  const SOURCE: &str =
+    "fn main() {\n    // ANCHOR: setup\n    let a = 1;\n\n    if a > 0 {\n        println!(\"```\");\n    }\n    // ANCHOR_END: setup\n}\n";
+
+  fn compile(attributes: &str) -> Result<String> {
+    let (attributes, _) = Attributes::parse(attributes).unwrap();
+    let args = HashMap::new();
+    let context = Context {
+      name: "code",
+      args: &args,
+    };
+    CodeTag.compile(SOURCE, &attributes, &context)
+  }
+
+  #[test]
+  fn test_compile() {
+    assert_eq!(
+      compile("src/main.rs, region=setup)").unwrap(),
+      "````rs\nlet a = 1;\n\nif a > 0 {\n    println!(\"```\");\n}\n````"
+    );
+    assert_eq!(
+      compile("src/main.rs, rust, lines=1)").unwrap(),
+      "```rust\nfn main() {\n```"
+    );
+  }
+
+  #[test]
+  fn test_compile_positional_region() {
+    assert_eq!(
+      compile("src/main.rs, rust, setup)").unwrap(),
+      compile("src/main.rs, rust, region=setup)").unwrap()
+    );
+    assert_eq!(
+      compile("src/main.rs, rust, teardown)")
+        .unwrap_err()
+        .to_string(),
+      "region teardown not found"
+    );
+    assert_eq!(
+      compile("src/main.rs, rust, setup, extra)")
+        .unwrap_err()
+        .to_string(),
+      "too many arguments, use @code(path, lang, region)"
+    );
+  }
+
+  #[test]
+  fn test_compile_missing_region() {
+    assert_eq!(
+      compile("src/main.rs, region=teardown)")
+        .unwrap_err()
+        .to_string(),
+      "region teardown not found"
+    );
+  }
+}
@@ -1,66 +1,80 @@
-use lazy_static::lazy_static;
-use regex::Regex;
-
-use crate::error::WordsmithError;
-
-use super::Component;
-
-lazy_static! {
-    /// Custom tags block
-    ///
-    /// Rust does not support look ahead/behind/around.
-    /// To validate the end tag, we use captures and the naming group reference
-    ///
-    /// Expect the capture naming groups:
-    /// - t: for the tag
-    /// - c: for the content
-    /// - e: for the end tag
-    static ref REG_TAG_BLOCK: Regex = Regex::new(r"(@(?P<t>(info|warn|danger|quote)))(?s)(?P<c>.*?)(?s)(?P<e>@end(info|warn|danger|quote)?)").unwrap();
-}
+use crate::utils::escape_html;
+
+use super::{Attributes, Component, Context, TagKind};
 
+/// Block tags (`@info`, `@warn`, `@danger` and `@quote`),
+/// wrap their content in a blockquote styled by the theme, with the `<name>-block` class.
+///
+/// Supported arguments: `title`, `icon` (added as an `icon-<name>` class)
+/// and `class`, e.g.: `@info(title="Heads up", icon=bulb, class=[wide, boxed])`
 #[derive(Debug, Clone)]
 pub struct CustomBlock;
 
 impl CustomBlock {
-  pub fn new() -> Self {
-    Self {}
-  }
+  /// Names of the block tags
+  pub const NAMES: [&'static str; 4] = ["info", "warn", "danger", "quote"];
 }
 
 impl Component for CustomBlock {
-  fn compile(&self, input: &str) -> anyhow::Result<String> {
-    log::info!("break_tag: {:?}", REG_TAG_BLOCK.as_str());
-
-    // Check for invalid tags
-    for m in REG_TAG_BLOCK.captures_iter(input) {
-      let tag = &m["t"];
-      let end_tag = &m["e"];
-
-      log::warn!("Validating tag block: {} = {}?", tag, end_tag);
+  fn kind(&self) -> TagKind {
+    TagKind::Block
+  }
 
-      if !end_tag.ends_with(tag) {
-        return Err(
-          WordsmithError::InvalidComponentClosingTag(tag.to_string(), end_tag.to_string()).into(),
-        );
-      }
+  fn compile(
+    &self,
+    content: &str,
+    attributes: &Attributes,
+    context: &Context,
+  ) -> anyhow::Result<String> {
+    log::info!("compile block tag: {}", context.name);
+
+    let mut classes = format!("{}-block", context.name);
+    if let Some(class) = attributes.get_string("class") {
+      classes.push_str(&format!(" {}", class));
     }
 
-    Ok(
-      REG_TAG_BLOCK
-        .replace_all(input, r#"<blockquote class="$t-block">$c</blockquote>"#)
-        .to_string(),
-    )
+    let icon = attributes
+      .get_string("icon")
+      .map(|icon| {
+        format!(
+          r#"<span class="block-icon icon-{}"></span>"#,
+          escape_html(&icon)
+        )
+      })
+      .unwrap_or_default();
+    let title = attributes
+      .get_string("title")
+      .map(|title| escape_html(&title))
+      .unwrap_or_default();
+    let header = match icon.is_empty() && title.is_empty() {
+      true => String::new(),
+      false => format!(r#"<div class="block-title">{}{}</div>"#, icon, title),
+    };
+
+    Ok(format!(
+      r#"<blockquote class="{}">{}{}</blockquote>"#,
+      escape_html(&classes),
+      header,
+      content
+    ))
   }
 }
 
 #[cfg(test)]
 mod tests {
+  use std::collections::HashMap;
+
   use super::*;
 
-  fn test_valid_block(input: &str, expected_output: &str) {
-    let custom_block = CustomBlock::new();
+  fn test_valid_block(name: &str, expected_output: &str) {
+    let args = HashMap::new();
+    let context = Context { name, args: &args };
 
-    let result = custom_block.compile(input);
+    let result = CustomBlock.compile(
+      " This is some text inside a block ",
+      &Attributes::default(),
+      &context,
+    );
 
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), expected_output);
@@ -68,47 +82,48 @@ mod tests {
 
   #[test]
   fn test_compile_valid_info_block() {
-    let input = "@info This is some text inside a info block @endinfo";
     let expected_output =
-      r#"<blockquote class="info-block"> This is some text inside a info block </blockquote>"#;
-    test_valid_block(input, expected_output);
+      r#"<blockquote class="info-block"> This is some text inside a block </blockquote>"#;
+    test_valid_block("info", expected_output);
   }
 
   #[test]
   fn test_compile_valid_warn_block() {
-    let input = "@warn This is some text inside a warn block @endwarn";
     let expected_output =
-      r#"<blockquote class="warn-block"> This is some text inside a warn block </blockquote>"#;
-    test_valid_block(input, expected_output);
+      r#"<blockquote class="warn-block"> This is some text inside a block </blockquote>"#;
+    test_valid_block("warn", expected_output);
   }
 
   #[test]
   fn test_compile_valid_danger_block() {
-    let input = "@danger This is some text inside a danger block @enddanger";
     let expected_output =
-      r#"<blockquote class="danger-block"> This is some text inside a danger block </blockquote>"#;
-    test_valid_block(input, expected_output);
+      r#"<blockquote class="danger-block"> This is some text inside a block </blockquote>"#;
+    test_valid_block("danger", expected_output);
   }
 
   #[test]
   fn test_compile_valid_quote_block() {
-    let input = "@quote This is some text inside a quote block @endquote";
     let expected_output =
-      r#"<blockquote class="quote-block"> This is some text inside a quote block </blockquote>"#;
-    test_valid_block(input, expected_output);
+      r#"<blockquote class="quote-block"> This is some text inside a block </blockquote>"#;
+    test_valid_block("quote", expected_output);
   }
 
   #[test]
-  fn test_compile_invalid_input() {
-    let custom_block = CustomBlock::new();
-
-    let input = "@info This is some text inside a info block @endwarn";
-    let expected_error =
-      WordsmithError::InvalidComponentClosingTag("info".to_owned(), "@endwarn".to_owned());
-
-    let result = custom_block.compile(input);
-
-    assert!(result.is_err());
-    assert_eq!(result.unwrap_err().to_string(), expected_error.to_string());
+  fn test_compile_with_attributes() {
+    let (attributes, _) =
+      Attributes::parse(r#"title="Heads & up", icon=bulb, class=[wide, boxed])"#).unwrap();
+
+    let args = HashMap::new();
+    let context = Context {
+      name: "info",
+      args: &args,
+    };
+
+    let result = CustomBlock.compile("<p>Text</p>", &attributes, &context);
+
+    assert_eq!(
+      result.unwrap(),
+      r#"<blockquote class="info-block wide boxed"><div class="block-title"><span class="block-icon icon-bulb"></span>Heads &amp; up</div><p>Text</p></blockquote>"#
+    );
   }
 }
@@ -0,0 +1,178 @@
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::{Attributes, Component, Context};
+
+lazy_static! {
+    /// Match the start of a region in a source file, a comment alone on its line,
+    /// e.g.: `// ANCHOR: setup`, `# ANCHOR: setup`, `<!-- ANCHOR: setup -->`
+    ///
+    /// Expect the capture naming groups:
+    /// - n: for the region name
+    static ref REG_ANCHOR: Regex = Regex::new(r"^\s*(//|#|--|;|%|/\*|<!--|\(\*|\{-)\s*ANCHOR:\s*(?P<n>[\w-]+)\s*(\*/|-->|\*\)|-\})?\s*$").unwrap();
+
+    /// Match the end of a region in a source file, a comment alone on its line,
+    /// e.g.: `// ANCHOR_END: setup`
+    ///
+    /// Expect the capture naming groups:
+    /// - n: for the region name
+    static ref REG_ANCHOR_END: Regex = Regex::new(r"^\s*(//|#|--|;|%|/\*|<!--|\(\*|\{-)\s*ANCHOR_END:\s*(?P<n>[\w-]+)\s*(\*/|-->|\*\)|-\})?\s*$").unwrap();
+}
+
+/// `@include(path)`, inserts a file of the content folder, e.g.: a disclaimer
+/// repeated in several chapters. Tags of the file are compiled.
+///
+/// `lines` keeps a range of lines (`lines=10-30`, `lines=10-`) and `region`
+/// the lines between `ANCHOR: name` and `ANCHOR_END: name` comments,
+/// e.g.: `@include(src/main.rs, region=setup)`
+#[derive(Debug, Clone)]
+pub struct IncludeTag;
+
+impl Component for IncludeTag {
+  fn is_source_tag(&self) -> bool {
+    true
+  }
+
+  fn compile(&self, content: &str, attributes: &Attributes, _context: &Context) -> Result<String> {
+    log::info!("compile include tag");
+    let content = get_snippet(content, attributes)?;
+    Ok(content.trim_end_matches('\n').to_string())
+  }
+}
+
+/// Part of a file selected by the `region` and `lines` arguments.
+/// Anchor comments are removed
+pub fn get_snippet(content: &str, attributes: &Attributes) -> Result<String> {
+  let content = match attributes.get_string("region") {
+    Some(region) => get_region(content, &region)?,
+    None => remove_anchors(content),
+  };
+  match attributes.get_string("lines") {
+    Some(lines) => get_lines(&content, &lines),
+    None => Ok(content),
+  }
+}
+
+fn is_anchor(line: &str) -> bool {
+  REG_ANCHOR.is_match(line) || REG_ANCHOR_END.is_match(line)
+}
+
+fn remove_anchors(content: &str) -> String {
+  content
+    .split_inclusive('\n')
+    .filter(|line| !is_anchor(line))
+    .collect()
+}
+
+/// Lines between the `ANCHOR: name` and `ANCHOR_END: name` comments
+fn get_region(content: &str, name: &str) -> Result<String> {
+  let mut region: Option<String> = None;
+  for line in content.split_inclusive('\n') {
+    match (
+      &mut region,
+      REG_ANCHOR.captures(line),
+      REG_ANCHOR_END.captures(line),
+    ) {
+      (None, Some(anchor), _) if &anchor["n"] == name => region = Some(String::new()),
+      (Some(region), _, Some(anchor)) if &anchor["n"] == name => return Ok(region.clone()),
+      (Some(region), _, _) if !is_anchor(line) => region.push_str(line),
+      _ => {}
+    }
+  }
+
+  match region {
+    Some(_) => Err(anyhow!(
+      "region {} is never closed, add ANCHOR_END: {}",
+      name,
+      name
+    )),
+    None => Err(anyhow!("region {} not found", name)),
+  }
+}
+
+/// Lines of a range starting at 1, e.g.: `10-30`, `10-` or `10`
+fn get_lines(content: &str, range: &str) -> Result<String> {
+  let invalid = || anyhow!("invalid lines {}, use a range like 10-30", range);
+  let parse = |value: &str| value.trim().parse::<usize>().map_err(|_| invalid());
+  let (start, end) = match range.split_once('-') {
+    Some((start, "")) => (parse(start)?, usize::MAX),
+    Some((start, end)) => (parse(start)?, parse(end)?),
+    None => (parse(range)?, parse(range)?),
+  };
+  if start == 0 || start > end {
+    return Err(invalid());
+  }
+
+  let lines: Vec<&str> = content.split_inclusive('\n').collect();
+  if start > lines.len() {
+    return Err(anyhow!(
+      "lines {} not found, the file has {} lines",
+      range,
+      lines.len()
+    ));
+  }
+  Ok(lines[start - 1..end.min(lines.len())].concat())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SOURCE: &str =
+    "fn main() {\n  // ANCHOR: setup\n  let a = 1;\n  // ANCHOR: inner\n  let b = 2;\n  // ANCHOR_END: inner\n  // ANCHOR_END: setup\n}\n";
+
+  fn snippet(attributes: &str) -> Result<String> {
+    let (attributes, _) = Attributes::parse(attributes).unwrap();
+    get_snippet(SOURCE, &attributes)
+  }
+
+  #[test]
+  fn test_get_snippet() {
+    assert_eq!(
+      snippet("main.rs)").unwrap(),
+      "fn main() {\n  let a = 1;\n  let b = 2;\n}\n"
+    );
+    assert_eq!(
+      snippet("main.rs, region=setup)").unwrap(),
+      "  let a = 1;\n  let b = 2;\n"
+    );
+    assert_eq!(
+      snippet("main.rs, region=setup, lines=2)").unwrap(),
+      "  let b = 2;\n"
+    );
+    assert_eq!(snippet("main.rs, lines=3-)").unwrap(), "  let b = 2;\n}\n");
+    assert_eq!(snippet("main.rs, lines=4-20)").unwrap(), "}\n");
+  }
+
+  #[test]
+  fn test_get_snippet_anchor_comments() {
+    let (attributes, _) = Attributes::parse("a.md, region=note)").unwrap();
+    let source = "<!-- ANCHOR: note -->\nprint(\"ANCHOR: x\")\n# ANCHOR_END: note\n";
+
+    assert_eq!(
+      get_snippet(source, &attributes).unwrap(),
+      "print(\"ANCHOR: x\")\n"
+    );
+    assert_eq!(
+      remove_anchors("a\n  /* ANCHOR: b */\nlet c = \"ANCHOR: c\";\n"),
+      "a\nlet c = \"ANCHOR: c\";\n"
+    );
+  }
+
+  #[test]
+  fn test_get_snippet_errors() {
+    assert_eq!(
+      snippet("main.rs, region=missing)").unwrap_err().to_string(),
+      "region missing not found"
+    );
+    assert_eq!(
+      snippet("main.rs, lines=3-1)").unwrap_err().to_string(),
+      "invalid lines 3-1, use a range like 10-30"
+    );
+    assert_eq!(
+      snippet("main.rs, lines=20)").unwrap_err().to_string(),
+      "lines 20 not found, the file has 4 lines"
+    );
+  }
+}
@@ -1,99 +1,428 @@
-use std::{collections::HashMap, fmt};
+use std::{
+  collections::HashMap,
+  fmt,
+  fs::{read_dir, read_to_string},
+  path::{Path, PathBuf},
+};
 
 use anyhow::Result;
 use lazy_static::lazy_static;
+use regex::Regex;
 
 use crate::error::WordsmithError;
 
 use self::{
-  assets_path::AssetsPath, break_tag::BreakTag, custom_block_tag::CustomBlock,
-  themes_path::ThemesPath,
+  parser::{Mark, Node, Tag},
+  template_block::TemplateBlock,
+};
+
+pub(crate) use self::parser::IndentedCode;
+pub use self::{
+  attributes::{Attributes, ComponentArg},
+  parser::TagKind,
+  ref_tag::RefTag,
+  registry::{ComponentRegistry, BUILTIN_PRIORITY, PROJECT_PRIORITY},
 };
 
-// I know.
-// Regex is not the best for this. But, this should work for now (or forever muhahaha)
-// Just kidding. Or not.
 mod assets_path;
+mod attributes;
 mod break_tag;
+mod code_tag;
 mod custom_block_tag;
+mod include_tag;
+mod parser;
+mod ref_tag;
+mod registry;
+mod template_block;
 mod themes_path;
+mod toc_tag;
 
 lazy_static! {
     /// HTML used to create page breaks
     pub static ref BREAK_PAGE_HTML: &'static str = r#"<div style="page-break-after: always;"></div>"#;
+
+    /// Valid names of project components, e.g.: `tip`, `side_note`
+    pub static ref REG_COMPONENT_NAME: Regex = Regex::new(r"^[a-z][a-z0-9_]*$").unwrap();
 }
 
-pub trait Component: Clone + fmt::Debug {
-  fn compile(&self, input: &str) -> anyhow::Result<String>;
+/// Names of the built-in inline tags, they can not be replaced by project components
+pub const INLINE_TAGS: [&str; 7] = [
+  "toc",
+  "break",
+  "ref",
+  "include",
+  "code",
+  "assets_path",
+  "themes_path",
+];
+
+/// Names of the tags compiled in theme files
+pub const THEME_TAGS: [&str; 2] = ["assets_path", "themes_path"];
+
+/// Check if a project component name can be used as a block tag
+pub fn is_valid_block_name(name: &str) -> bool {
+  REG_COMPONENT_NAME.is_match(name) && !name.starts_with("end") && !INLINE_TAGS.contains(&name)
 }
 
-#[derive(Debug, Clone)]
-pub enum ComponentArg {
-  String(String),
+/// Values available to components while rendering a tag
+#[derive(Debug, Clone, Copy)]
+pub struct Context<'c> {
+  /// Name of the tag, e.g.: `info`
+  pub name: &'c str,
+  /// Components arguments, e.g.: `assets_path`
+  pub args: &'c HashMap<String, ComponentArg>,
+}
+
+impl Context<'_> {
+  /// Return string value from list of arguments
+  /// If argument does not exist return empty string
+  pub fn get_string_arg(&self, key: &str) -> String {
+    self
+      .args
+      .get(key)
+      .map(|arg| arg.to_string())
+      .unwrap_or_default()
+  }
+}
+
+/// A tag that can be used in documents, registered in a [ComponentRegistry]
+pub trait Component: fmt::Debug {
+  /// Inline tags (`@name`) by default, block tags are closed with `@endname`
+  fn kind(&self) -> TagKind {
+    TagKind::Inline
+  }
+
+  /// Render the tag while parsing the sources, before markdown.
+  /// Used for values written in links and HTML attributes, e.g.: `@assets_path`
+  fn is_source_tag(&self) -> bool {
+    false
+  }
+
+  /// Render the tag. `content` is the compiled content of block tags,
+  /// empty for inline tags. `attributes` are the tag arguments,
+  /// e.g.: `@info(title="Note")`
+  fn compile(&self, content: &str, attributes: &Attributes, context: &Context) -> Result<String>;
 }
 
+/// Compile `@tag` and `@tag ... @endtag` components in two steps:
+///
+/// 1. [Components::compile_source] parses markdown and theme sources, replacing
+///    path tags with their values and other tags with placeholders.
+/// 2. [Components::compile_all] renders the placeholders of the generated HTML,
+///    so blocks wrap their HTML content.
 #[derive(Debug, Clone)]
-pub struct Components<'a> {
+pub struct Components {
   args: HashMap<String, ComponentArg>,
-  components: Vec<&'a str>,
+  registry: ComponentRegistry,
+  /// Project folder, files of source tags are relative to its content folder
+  path: PathBuf,
+}
+
+/// File parsed by [Components::compile_source], to find include cycles
+struct SourceFile {
+  path: PathBuf,
+  /// Path used in error messages, e.g.: `content/intro.md`
+  name: String,
 }
 
-impl<'a> Components<'a> {
-  pub fn new(args: HashMap<String, ComponentArg>) -> Self {
+impl Components {
+  /// Register the built-in components and the block components of the project:
+  /// templates of `themes/components/<name>.html`, then `blocks` defined in `ws.yaml`.
+  ///
+  /// Project components are registered with [PROJECT_PRIORITY],
+  /// they replace built-in blocks with the same name, e.g.: `info`
+  pub fn new(
+    args: HashMap<String, ComponentArg>,
+    project_path: &Path,
+    blocks: &HashMap<String, String>,
+  ) -> Self {
+    let mut registry = ComponentRegistry::with_builtins();
+    let templates = Self::get_template_files(&project_path.join("themes/components"));
+    for (name, template) in templates.iter().chain(blocks) {
+      if let Err(e) =
+        registry.register_with_priority(name, PROJECT_PRIORITY, TemplateBlock::new(template))
+      {
+        log::warn!("Ignoring component {}: {}", name, e);
+      }
+    }
+
     Self {
       args,
-      components: ["break", "custom_block", "assets_path", "themes_path"].to_vec(),
+      registry,
+      path: project_path.to_path_buf(),
+    }
+  }
+
+  /// Load the HTML templates of a folder, files that can not be used are ignored
+  fn get_template_files(folder: &Path) -> HashMap<String, String> {
+    let mut templates = HashMap::new();
+    let entries = match read_dir(folder) {
+      Ok(entries) => entries,
+      Err(_) => return templates,
+    };
+
+    for path in entries.flatten().map(|entry| entry.path()) {
+      if path.extension().and_then(|e| e.to_str()) != Some("html") {
+        continue;
+      }
+      let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+      if !is_valid_block_name(&name) {
+        log::warn!("Ignoring component {}, invalid name", path.display());
+        continue;
+      }
+      match read_to_string(&path) {
+        Ok(template) => {
+          log::debug!("Registering component {}", name);
+          templates.insert(name, template);
+        }
+        Err(e) => log::warn!("Ignoring component {}: {}", path.display(), e),
+      }
+    }
+
+    templates
+  }
+
+  /// Add or replace an argument
+  pub fn with_arg(mut self, key: &str, value: ComponentArg) -> Self {
+    self.args.insert(key.to_string(), value);
+    self
+  }
+
+  /// Add arguments, existing arguments are kept
+  pub fn with_args(mut self, args: HashMap<String, ComponentArg>) -> Self {
+    for (key, value) in args {
+      self.args.entry(key).or_insert(value);
     }
+    self
+  }
+
+  /// Add the components of a registry, see [ComponentRegistry] for the priority rules
+  pub fn with_registry(mut self, registry: ComponentRegistry) -> Self {
+    self.registry.extend(registry);
+    self
   }
 
   /// Return string value from list of arguments
   /// If argument does not exist return empty string
-  fn get_string_arg(&self, key: &str) -> String {
-    if let Some(component) = self.args.get(key) {
-      let ComponentArg::String(value) = component;
-      return value.to_owned();
+  pub fn get_string_arg(&self, key: &str) -> String {
+    self.get_context(key).get_string_arg(key)
+  }
+
+  fn get_context<'c>(&'c self, name: &'c str) -> Context<'c> {
+    Context {
+      name,
+      args: &self.args,
+    }
+  }
+
+  /// Kind of a registered tag, `None` for unknown tags
+  pub fn get_tag_kind(&self, name: &str) -> Option<TagKind> {
+    self.registry.get(name).map(|component| component.kind())
+  }
+
+  /// Render a tag with its component
+  fn compile_tag(&self, tag: &Tag, content: &str) -> Result<String> {
+    let name = tag.name.as_str();
+    match self.registry.get(name) {
+      Some(component) => component.compile(content, &tag.attributes, &self.get_context(name)),
+      None => Err(WordsmithError::InvalidTag(name.to_string()).into()),
+    }
+  }
+
+  /// Render a source tag. The file of the tag is read and the output parsed,
+  /// `files` are the sources being parsed, the last one contains the tag
+  fn render_source_tag(
+    &self,
+    tag: &Tag,
+    files: &mut Vec<SourceFile>,
+    output: &mut String,
+  ) -> Result<()> {
+    let parent = files.last().map(|f| f.name.clone()).unwrap_or_default();
+    if self.registry.is_line_only(&tag.name) && !tag.standalone {
+      return Err(
+        WordsmithError::TagSyntax {
+          file: parent,
+          line: tag.line,
+          message: format!("@{} must be the only content of its line", tag.name),
+        }
+        .into(),
+      );
     }
-    "".to_string()
+
+    let file = match self.registry.get_file(&tag.name, &tag.attributes) {
+      Some(file) => self.get_source_file(&file, tag, files)?,
+      None => {
+        output.push_str(&self.compile_tag(tag, "")?);
+        return Ok(());
+      }
+    };
+
+    let source = self
+      .compile_tag(tag, &read_to_string(&file.path)?)
+      .map_err(|e| WordsmithError::TagSyntax {
+        file: parent,
+        line: tag.line,
+        message: format!("@{}({}): {}", tag.name, tag.attributes_source, e),
+      })?;
+
+    let nodes = parser::parse(&source, &file.name, |name| self.get_tag_kind(name))?;
+    files.push(file);
+    self.render_source(&nodes, files, output)?;
+    files.pop();
+    Ok(())
+  }
+
+  /// Find the file of a source tag, relative to the content folder.
+  ///
+  /// Return [WordsmithError::IncludeNotFound] if the file does not exist
+  /// and [WordsmithError::IncludeCycle] if it is already being parsed
+  fn get_source_file(&self, file: &str, tag: &Tag, files: &[SourceFile]) -> Result<SourceFile> {
+    let parent = files.last().map(|f| f.name.clone()).unwrap_or_default();
+    let name = Path::new("content").join(file).display().to_string();
+    let path =
+      self
+        .path
+        .join(&name)
+        .canonicalize()
+        .map_err(|_| WordsmithError::IncludeNotFound {
+          file: parent.clone(),
+          line: tag.line,
+          path: name.clone(),
+        })?;
+
+    if files.iter().any(|f| f.path == path) {
+      let mut names: Vec<String> = files.iter().map(|f| f.name.clone()).collect();
+      names.push(name);
+      return Err(
+        WordsmithError::IncludeCycle {
+          file: parent,
+          line: tag.line,
+          files: names,
+        }
+        .into(),
+      );
+    }
+
+    Ok(SourceFile { path, name })
   }
 
-  pub fn compile_tag_themes_path(&self, input: &str) -> Result<String> {
-    let path = self.get_string_arg("themes_path");
-    ThemesPath::new(path).compile(input)
+  fn render_source(
+    &self,
+    nodes: &[Node],
+    files: &mut Vec<SourceFile>,
+    output: &mut String,
+  ) -> Result<()> {
+    for node in nodes {
+      match node {
+        Node::Text(text) => output.push_str(text),
+        Node::Tag(tag) => match self.registry.get(&tag.name) {
+          Some(component) if component.is_source_tag() => {
+            self.render_source_tag(tag, files, output)?
+          }
+          _ => output.push_str(&parser::to_placeholder(tag, Mark::Inline)),
+        },
+        Node::Block { tag, end, children } => {
+          output.push_str(&parser::to_placeholder(tag, Mark::Open));
+          self.render_source(children, files, output)?;
+          output.push_str(&parser::to_placeholder(end, Mark::Close));
+        }
+      }
+    }
+    Ok(())
   }
 
-  pub fn compile_tag_assets_path(&self, input: &str) -> Result<String> {
-    let path = self.get_string_arg("assets_path");
-    AssetsPath::new(path).compile(input)
+  /// Parse a markdown source, `file` is used in error messages.
+  ///
+  /// Tags inside code are not compiled, files of `@include` and `@code` tags are inserted.
+  /// Return [WordsmithError::TagSyntax] for unclosed or mismatched blocks
+  pub fn compile_source(&self, source: &str, file: &str) -> Result<String> {
+    let nodes = parser::parse(source, file, |name| self.get_tag_kind(name))?;
+    self.compile_nodes(&nodes, file)
   }
 
-  pub fn compile_custom_block(&self, input: &str) -> Result<String> {
-    CustomBlock::new().compile(input)
+  /// Parse a theme source, only [THEME_TAGS] are compiled.
+  ///
+  /// Other `@` words are CSS at-rules (e.g.: `@page`, `@media`), they must not be
+  /// replaced by project components with the same name
+  pub fn compile_theme_source(&self, source: &str, file: &str) -> Result<String> {
+    let nodes = parser::parse(source, file, |name| match THEME_TAGS.contains(&name) {
+      true => self.get_tag_kind(name),
+      false => None,
+    })?;
+    self.compile_nodes(&nodes, file)
   }
 
-  pub fn compile_break_tag(&self, input: &str) -> Result<String> {
-    BreakTag::new().compile(input)
+  fn compile_nodes(&self, nodes: &[Node], file: &str) -> Result<String> {
+    let path = self.path.join(file);
+    let mut files = vec![SourceFile {
+      path: path.canonicalize().unwrap_or(path),
+      name: file.to_string(),
+    }];
+    let mut output = String::new();
+    self.render_source(nodes, &mut files, &mut output)?;
+    Ok(output)
   }
 
-  pub fn compile_all(&self, input: &str) -> Result<String> {
-    let mut new_value = input.to_string();
-    for name in self.components.clone() {
-      new_value = match name {
-        "break" => self.compile_break_tag(&new_value)?,
-        "custom_block" => self.compile_custom_block(&new_value)?,
-        "assets_path" => self.compile_tag_assets_path(&new_value)?,
-        "themes_path" => self.compile_tag_themes_path(&new_value)?,
-        t => return Err(WordsmithError::InvalidTag(t.to_string()).into()),
+  fn render(&self, nodes: &[Node]) -> Result<String> {
+    let mut output = String::new();
+    for node in nodes {
+      match node {
+        Node::Text(text) => output.push_str(text),
+        Node::Tag(tag) => output.push_str(&self.compile_tag(tag, "")?),
+        Node::Block { tag, children, .. } => {
+          let content = self.render(children)?;
+          output.push_str(&self.compile_tag(tag, &content)?);
+        }
       }
-      .to_string();
     }
+    Ok(output)
+  }
 
-    Ok(new_value)
+  /// Check if the table of contents is placed manually with `@toc`
+  pub fn has_toc_tag(&self, html: &str) -> bool {
+    parser::has_placeholder(html, "toc")
+  }
+
+  /// Arguments of every `name` tag of HTML generated from compiled sources,
+  /// including tags nested in blocks
+  pub fn get_tags(&self, html: &str, name: &str) -> Result<Vec<Attributes>> {
+    fn collect(nodes: &[Node], name: &str, tags: &mut Vec<Attributes>) {
+      for node in nodes {
+        match node {
+          Node::Text(_) => {}
+          Node::Tag(tag) => {
+            if tag.name == name {
+              tags.push(tag.attributes.clone());
+            }
+          }
+          Node::Block { tag, children, .. } => {
+            if tag.name == name {
+              tags.push(tag.attributes.clone());
+            }
+            collect(children, name, tags);
+          }
+        }
+      }
+    }
+
+    let mut tags = Vec::new();
+    collect(&parser::parse_placeholders(html)?, name, &mut tags);
+    Ok(tags)
+  }
+
+  /// Render the components of HTML generated from compiled sources
+  pub fn compile_all(&self, html: &str) -> Result<String> {
+    self.render(&parser::parse_placeholders(html)?)
   }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::test_utils::create_files;
 
   #[test]
   fn test_get_string_arg() {
@@ -107,7 +436,7 @@ mod tests {
         ComponentArg::String("test".to_string()),
       ),
     ]);
-    let components = Components::new(args);
+    let components = Components::new(args, Path::new(""), &HashMap::new());
 
     assert_eq!(components.get_string_arg("themes_path"), "test".to_string());
     assert_eq!(components.get_string_arg("assets_path"), "test".to_string());
@@ -115,15 +444,230 @@ mod tests {
   }
 
   #[test]
-  fn test_compile_tag_themes_path() {
+  fn test_compile_source_themes_path() {
     let args = HashMap::from([(
       "themes_path".to_string(),
       ComponentArg::String("/themes".to_string()),
     )]);
 
-    let components = Components::new(args);
-    let result = components.compile_tag_themes_path("<img src=@themes_path/images/example.png>");
+    let components = Components::new(args, Path::new(""), &HashMap::new());
+    let result = components.compile_source(
+      "<img src=@themes_path/images/example.png>",
+      "themes/light.html",
+    );
 
     assert_eq!(result.unwrap(), "<img src=/themes/images/example.png>");
   }
+
+  #[test]
+  fn test_compile_theme_source() {
+    let blocks = HashMap::from([("page".to_string(), "<b>{{content}}</b>".to_string())]);
+    let args = HashMap::from([(
+      "themes_path".to_string(),
+      ComponentArg::String("/themes".to_string()),
+    )]);
+    let components = Components::new(args, Path::new(""), &blocks);
+    let source = "@page :first { margin: 0; } @endpage url(@themes_path/a.png)";
+
+    let html = components
+      .compile_theme_source(source, "themes/__base-head.html")
+      .unwrap();
+
+    assert_eq!(
+      components.compile_all(&html).unwrap(),
+      "@page :first { margin: 0; } @endpage url(/themes/a.png)"
+    );
+  }
+
+  #[test]
+  fn test_compile_markdown() {
+    let components = Components::new(HashMap::new(), Path::new(""), &HashMap::new());
+    let source = "@info\n\n**Note** @break\n\n`@break`\n\n@endinfo\n";
+
+    let markdown = components.compile_source(source, "content/a.md").unwrap();
+    let html = comrak::markdown_to_html(&markdown, &comrak::ComrakOptions::default());
+
+    assert_eq!(
+      components.compile_all(&html).unwrap(),
+      format!(
+        "<blockquote class=\"info-block\"><p><strong>Note</strong> {}</p>\n<p><code>@break</code></p>\n</blockquote>",
+        *BREAK_PAGE_HTML
+      )
+    );
+  }
+
+  #[test]
+  fn test_compile_source_unclosed_block() {
+    let components = Components::new(HashMap::new(), Path::new(""), &HashMap::new());
+
+    let result = components.compile_source("text\n\n@warn something", "content/a.md");
+
+    assert_eq!(
+      result.unwrap_err().to_string(),
+      "Invalid tag in content/a.md at line 3: @warn is never closed, add @endwarn"
+    );
+  }
+
+  #[test]
+  fn test_compile_project_blocks() {
+    let project = create_files(&[
+      (
+        "themes/components/tip.html",
+        r#"<aside class="{{ name }}">{{ content }}</aside>"#,
+      ),
+      ("themes/components/break.html", "ignored"),
+    ]);
+
+    let blocks = HashMap::from([("info".to_string(), "<div>{{content}}</div>".to_string())]);
+    let components = Components::new(HashMap::new(), project.path(), &blocks);
+
+    let html = components
+      .compile_source("@tip a @info b @endinfo @endtip @break", "content/a.md")
+      .unwrap();
+
+    assert_eq!(
+      components.compile_all(&html).unwrap(),
+      format!(
+        r#"<aside class="tip"> a <div> b </div> </aside> {}"#,
+        *BREAK_PAGE_HTML
+      )
+    );
+  }
+
+  #[test]
+  fn test_compile_attributes() {
+    let components = Components::new(HashMap::new(), Path::new(""), &HashMap::new());
+    let source = "@warn(title=\"Don't, stop\") a @endwarn\n\n@info(icon=[a\nb\n";
+
+    let html = components
+      .compile_source(&source[..source.find('\n').unwrap()], "content/a.md")
+      .unwrap();
+    let html = comrak::markdown_to_html(&html, &comrak::ComrakOptions::default());
+
+    assert_eq!(
+      components.compile_all(&html).unwrap(),
+      "<blockquote class=\"warn-block\"><div class=\"block-title\">Don't, stop</div> a </blockquote>\n"
+    );
+    assert_eq!(
+      components
+        .compile_source(source, "content/a.md")
+        .unwrap_err()
+        .to_string(),
+      "Invalid tag in content/a.md at line 3: invalid arguments of @info, missing ] at the end of the list"
+    );
+  }
+
+  #[test]
+  fn test_compile_attributes_same_line() {
+    let components = Components::new(HashMap::new(), Path::new(""), &HashMap::new());
+    let source = "@warn(title=a) x @endwarn @info(title=b) y @endinfo";
+
+    let html = components.compile_source(source, "content/a.md").unwrap();
+    let options = comrak::ComrakOptions {
+      extension: comrak::ComrakExtensionOptions {
+        strikethrough: true,
+        ..comrak::ComrakExtensionOptions::default()
+      },
+      ..comrak::ComrakOptions::default()
+    };
+    let html = comrak::markdown_to_html(&html, &options);
+    let html = components.compile_all(&html).unwrap();
+
+    assert!(html.contains(r#"<div class="block-title">a</div>"#));
+    assert!(html.contains(r#"<div class="block-title">b</div>"#));
+    assert!(!html.contains("<del>"));
+    assert!(!html.contains("<p>"));
+  }
+
+  #[test]
+  fn test_compile_include() {
+    let project = create_files(&[
+      (
+        "content/snippets/note.md",
+        "@info Read @include(snippets/a.md) @endinfo\n",
+      ),
+      ("content/snippets/a.md", "first\nsecond\n"),
+      ("content/snippets/loop.md", "\n@include(snippets/loop.md)\n"),
+    ]);
+    let components = Components::new(HashMap::new(), project.path(), &HashMap::new());
+
+    let html = components
+      .compile_source("@include(snippets/note.md)\n", "content/a.md")
+      .unwrap();
+    let cycle = components
+      .compile_source("@include(snippets/loop.md)", "content/a.md")
+      .unwrap_err();
+    let not_found = components
+      .compile_source("\n@include(missing.md)", "content/a.md")
+      .unwrap_err();
+
+    assert_eq!(
+      components.compile_all(&html).unwrap(),
+      "<blockquote class=\"info-block\"> Read first\nsecond </blockquote>\n"
+    );
+    assert_eq!(
+      cycle.to_string(),
+      "Include cycle in content/snippets/loop.md at line 2: content/a.md -> content/snippets/loop.md -> content/snippets/loop.md"
+    );
+    assert_eq!(
+      not_found.to_string(),
+      "File content/missing.md included in content/a.md at line 2 not found"
+    );
+  }
+
+  #[test]
+  fn test_compile_code() {
+    let project = create_files(&[("content/main.rs", "fn main() {}\n")]);
+    let components = Components::new(HashMap::new(), project.path(), &HashMap::new());
+
+    let source = components
+      .compile_source("Code:\n\n@code(main.rs)\n", "content/a.md")
+      .unwrap();
+    let inline = components
+      .compile_source("\nSee @code(main.rs)\n", "content/a.md")
+      .unwrap_err();
+
+    assert_eq!(source, "Code:\n\n```rs\nfn main() {}\n```\n");
+    assert_eq!(
+      inline.to_string(),
+      "Invalid tag in content/a.md at line 2: @code must be the only content of its line"
+    );
+  }
+
+  #[derive(Debug)]
+  struct Badge;
+
+  impl Component for Badge {
+    fn compile(
+      &self,
+      _content: &str,
+      attributes: &Attributes,
+      context: &Context,
+    ) -> Result<String> {
+      Ok(format!(
+        "<span class=\"{}\">{}</span>",
+        context.name,
+        attributes.get_string("0").unwrap_or_default()
+      ))
+    }
+  }
+
+  #[test]
+  fn test_compile_registry() {
+    let mut registry = ComponentRegistry::new();
+    registry.register("badge", Badge).unwrap();
+    registry.register("info", Badge).unwrap();
+    let blocks = HashMap::from([("warn".to_string(), "<b>{{content}}</b>".to_string())]);
+    let components =
+      Components::new(HashMap::new(), Path::new(""), &blocks).with_registry(registry);
+
+    let html = components
+      .compile_source("@badge(new) @info(v2) @warn a @endwarn", "content/a.md")
+      .unwrap();
+
+    assert_eq!(
+      components.compile_all(&html).unwrap(),
+      r#"<span class="badge">new</span> <span class="info">v2</span> <b> a </b>"#
+    );
+  }
 }
@@ -0,0 +1,735 @@
+use anyhow::Result;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::error::WordsmithError;
+
+use super::Attributes;
+
+/// Marks compiled tags in the content until markdown is rendered.
+/// Comrak keeps it as plain text, so tags survive paragraphs, lists...
+const PLACEHOLDER: char = '\u{FFFC}';
+
+lazy_static! {
+    /// Match placeholders, alone in a paragraph or inline
+    ///
+    /// Expect the capture naming groups:
+    /// - m/im: for the mark, `+` for blocks and `/` for end tags
+    /// - n/in: for the tag name
+    /// - a/ia: for the arguments source, hex encoded
+    static ref REG_PLACEHOLDER: Regex = Regex::new(r"<p>\x{FFFC}(?P<m>[+/]?)(?P<n>\w+)(:(?P<a>[0-9a-f]*))?\x{FFFC}</p>\n?|\x{FFFC}(?P<im>[+/]?)(?P<in>\w+)(:(?P<ia>[0-9a-f]*))?\x{FFFC}").unwrap();
+
+    /// Match list items, e.g.: `- item`, `1. item`
+    static ref REG_LIST_ITEM: Regex = Regex::new(r"^\s*([-*+]|\d+[.)])(\s|$)").unwrap();
+}
+
+/// How a tag is written in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagKind {
+  /// A single tag, e.g.: `@break`
+  Inline,
+  /// A tag wrapping content until its end tag, e.g.: `@info ... @endinfo`
+  Block,
+}
+
+/// A tag found in the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+  pub name: String,
+  /// Line of the tag in the source file, starting at 1
+  pub line: usize,
+  /// The tag is the only content of its line
+  pub standalone: bool,
+  /// Arguments, e.g.: `@info(title="Note")`
+  pub attributes: Attributes,
+  /// Arguments as written in the source, without parentheses
+  pub attributes_source: String,
+}
+
+impl Tag {
+  fn new(name: &str, line: usize, standalone: bool) -> Self {
+    Self {
+      name: name.to_string(),
+      line,
+      standalone,
+      attributes: Attributes::default(),
+      attributes_source: String::new(),
+    }
+  }
+}
+
+/// Part of the source replaced by a placeholder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+  Inline,
+  Open,
+  Close,
+}
+
+impl Mark {
+  fn as_str(&self) -> &'static str {
+    match self {
+      Mark::Inline => "",
+      Mark::Open => "+",
+      Mark::Close => "/",
+    }
+  }
+}
+
+/// Element of a parsed source
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+  Text(String),
+  Tag(Tag),
+  Block {
+    tag: Tag,
+    end: Tag,
+    children: Vec<Node>,
+  },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token<'s> {
+  Text(&'s str),
+  Inline(Tag),
+  Open(Tag),
+  Close(Tag),
+}
+
+impl<'s> Token<'s> {
+  fn map_tag<M: FnOnce(Tag) -> Tag>(self, map: M) -> Self {
+    match self {
+      Token::Inline(tag) => Token::Inline(map(tag)),
+      Token::Open(tag) => Token::Open(map(tag)),
+      Token::Close(tag) => Token::Close(map(tag)),
+      text => text,
+    }
+  }
+
+  fn with_standalone(self, standalone: bool) -> Self {
+    self.map_tag(|tag| Tag { standalone, ..tag })
+  }
+}
+
+/// Width of the line indentation, tabs count as 4 spaces
+fn get_indentation(line: &str) -> usize {
+  line
+    .chars()
+    .take_while(|c| *c == ' ' || *c == '\t')
+    .map(|c| if c == '\t' { 4 } else { 1 })
+    .sum()
+}
+
+/// Find the lines of indented code blocks, read one line after the other.
+///
+/// A line indented by 4 spaces is code after a blank line or other code lines,
+/// but not when it continues a list item, e.g.: a nested paragraph
+#[derive(Debug)]
+pub(crate) struct IndentedCode {
+  in_code: bool,
+  previous_blank: bool,
+  in_list: bool,
+}
+
+impl IndentedCode {
+  pub fn new() -> Self {
+    Self {
+      in_code: false,
+      previous_blank: true,
+      in_list: false,
+    }
+  }
+
+  /// Return true if the line is indented code. Lines of fenced code
+  /// blocks (`fenced`) are never indented code
+  pub fn is_code(&mut self, line: &str, fenced: bool) -> bool {
+    let blank = line.trim().is_empty();
+    let indentation = get_indentation(line);
+    let code = !fenced
+      && !blank
+      && indentation >= 4
+      && (self.in_code || (self.previous_blank && !self.in_list));
+
+    if code {
+      self.in_code = true;
+    } else if !fenced && !blank {
+      self.in_code = false;
+      if indentation < 4 {
+        self.in_list = REG_LIST_ITEM.is_match(line) || (self.in_list && !self.previous_blank);
+      }
+    }
+    self.previous_blank = blank;
+    code
+  }
+}
+
+/// Split a source into text and tags.
+///
+/// Tags inside code (fenced and indented code blocks, code spans) are text.
+/// Unknown tags are text as well, e.g.: emails or `@font-face` rules of themes.
+struct Tokenizer<'s, F> {
+  source: &'s str,
+  file: &'s str,
+  get_tag_kind: F,
+  tokens: Vec<Token<'s>>,
+}
+
+impl<'s, F> Tokenizer<'s, F>
+where
+  F: Fn(&str) -> Option<TagKind>,
+{
+  fn new(source: &'s str, file: &'s str, get_tag_kind: F) -> Self {
+    Self {
+      source,
+      file,
+      get_tag_kind,
+      tokens: Vec::new(),
+    }
+  }
+
+  fn push_text(&mut self, text: &'s str) {
+    if !text.is_empty() {
+      self.tokens.push(Token::Text(text));
+    }
+  }
+
+  /// Return the fence character and length if the line opens a code block, e.g.: "```rust"
+  fn get_fence(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start();
+    let fence = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let length = trimmed.chars().take_while(|c| *c == fence).count();
+    (length >= 3).then_some((fence, length))
+  }
+
+  fn is_closing_fence(line: &str, (fence, length): (char, usize)) -> bool {
+    let trimmed = line.trim();
+    trimmed.chars().take_while(|c| *c == fence).count() >= length
+      && trimmed.chars().all(|c| c == fence)
+  }
+
+  /// Tag, end tag or `None` for unknown names
+  fn get_token(&self, name: &str, tag: Tag) -> Option<Token<'s>> {
+    match (self.get_tag_kind)(name) {
+      Some(TagKind::Inline) => Some(Token::Inline(tag)),
+      Some(TagKind::Block) => Some(Token::Open(tag)),
+      None => name
+        .strip_prefix("end")
+        .filter(|name| (self.get_tag_kind)(name) == Some(TagKind::Block))
+        .map(|name| {
+          Token::Close(Tag {
+            name: name.to_string(),
+            ..tag
+          })
+        }),
+    }
+  }
+
+  /// Parse the arguments written after a tag name, e.g.: `@info(title="Note")`.
+  /// End tags do not have arguments.
+  ///
+  /// Return the token and the end of the tag in the line
+  fn parse_attributes(
+    &self,
+    token: Token<'s>,
+    line: &str,
+    name_end: usize,
+    number: usize,
+  ) -> Result<(Token<'s>, usize)> {
+    if !line[name_end..].starts_with('(') || matches!(token, Token::Close(_)) {
+      return Ok((token, name_end));
+    }
+
+    let source = &line[name_end + 1..];
+    let (attributes, length) = Attributes::parse(source).map_err(|message| {
+      let name = match &token {
+        Token::Inline(tag) | Token::Open(tag) | Token::Close(tag) => tag.name.as_str(),
+        Token::Text(_) => "",
+      };
+      get_syntax_error(
+        self.file,
+        number,
+        format!("invalid arguments of @{}, {}", name, message),
+      )
+    })?;
+
+    let attributes_source = source[..length - 1].to_string();
+    let token = token.map_tag(|tag| Tag {
+      attributes,
+      attributes_source,
+      ..tag
+    });
+    Ok((token, name_end + 1 + length))
+  }
+
+  /// Find tags of a line, skipping code spans
+  fn scan_line(&mut self, line: &'s str, number: usize) -> Result<()> {
+    let bytes = line.as_bytes();
+    let mut text_start = 0;
+    let mut index = 0;
+
+    while index < bytes.len() {
+      match bytes[index] {
+        b'`' => {
+          let run = bytes[index..].iter().take_while(|b| **b == b'`').count();
+          let after = index + run;
+          // A code span ends with a run of the same length
+          let mut end = None;
+          let mut search = after;
+          while search < bytes.len() {
+            if bytes[search] == b'`' {
+              let length = bytes[search..].iter().take_while(|b| **b == b'`').count();
+              if length == run {
+                end = Some(search + length);
+                break;
+              }
+              search += length;
+            } else {
+              search += 1;
+            }
+          }
+          index = end.unwrap_or(after);
+        }
+        b'@' => {
+          let escaped = index > 0
+            && (bytes[index - 1].is_ascii_alphanumeric()
+              || matches!(bytes[index - 1], b'_' | b'\\'));
+          let name_end = index
+            + 1
+            + bytes[index + 1..]
+              .iter()
+              .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+              .count();
+          let name = &line[index + 1..name_end];
+
+          let token = match self.get_token(name, Tag::new(name, number, false)) {
+            Some(token) if !escaped => token,
+            _ => {
+              index = name_end.max(index + 1);
+              continue;
+            }
+          };
+
+          let (token, tag_end) = self.parse_attributes(token, line, name_end, number)?;
+          let standalone = line.trim() == &line[index..tag_end];
+          self.push_text(&line[text_start..index]);
+          self.tokens.push(token.with_standalone(standalone));
+          text_start = tag_end;
+          index = tag_end;
+        }
+        _ => index += 1,
+      }
+    }
+
+    self.push_text(&line[text_start..]);
+    Ok(())
+  }
+
+  fn tokenize(mut self) -> Result<Vec<Token<'s>>> {
+    let mut fence: Option<(char, usize)> = None;
+    let mut indented_code = IndentedCode::new();
+
+    for (index, line) in self.source.split_inclusive('\n').enumerate() {
+      if let Some(open) = fence {
+        if Self::is_closing_fence(line, open) {
+          fence = None;
+        }
+        indented_code.is_code(line, true);
+        self.push_text(line);
+      } else if let Some(open) = Self::get_fence(line) {
+        fence = Some(open);
+        indented_code.is_code(line, true);
+        self.push_text(line);
+      } else if indented_code.is_code(line, false) {
+        self.push_text(line);
+      } else {
+        self.scan_line(line, index + 1)?;
+      }
+    }
+
+    Ok(self.tokens)
+  }
+}
+
+/// Block waiting for its end tag
+struct OpenBlock {
+  tag: Tag,
+  children: Vec<Node>,
+}
+
+/// Children of the innermost open block, or the root nodes
+fn get_nodes<'n>(root: &'n mut Vec<Node>, stack: &'n mut [OpenBlock]) -> &'n mut Vec<Node> {
+  match stack.last_mut() {
+    Some(block) => &mut block.children,
+    None => root,
+  }
+}
+
+/// Add a node, joining consecutive texts and skipping empty ones
+fn push_node(nodes: &mut Vec<Node>, node: Node) {
+  match (nodes.last_mut(), node) {
+    (_, Node::Text(text)) if text.is_empty() => {}
+    (Some(Node::Text(text)), Node::Text(more)) => text.push_str(&more),
+    (_, node) => nodes.push(node),
+  }
+}
+
+fn get_syntax_error(file: &str, line: usize, message: String) -> anyhow::Error {
+  WordsmithError::TagSyntax {
+    file: file.to_string(),
+    line,
+    message,
+  }
+  .into()
+}
+
+/// Parse a markdown or theme source into nodes.
+///
+/// `get_tag_kind` returns the kind of the registered tags, other names are text.
+/// Return [WordsmithError::TagSyntax] with the line of unclosed or mismatched blocks
+pub fn parse<F>(source: &str, file: &str, get_tag_kind: F) -> Result<Vec<Node>>
+where
+  F: Fn(&str) -> Option<TagKind>,
+{
+  let mut root = Vec::new();
+  let mut stack: Vec<OpenBlock> = Vec::new();
+
+  for token in Tokenizer::new(source, file, get_tag_kind).tokenize()? {
+    match token {
+      Token::Text(text) => push_node(
+        get_nodes(&mut root, &mut stack),
+        Node::Text(text.to_string()),
+      ),
+      Token::Inline(tag) => get_nodes(&mut root, &mut stack).push(Node::Tag(tag)),
+      Token::Open(tag) => stack.push(OpenBlock {
+        tag,
+        children: Vec::new(),
+      }),
+      Token::Close(end) => {
+        let block = match stack.pop() {
+          Some(block) if block.tag.name == end.name => block,
+          Some(block) => {
+            return Err(get_syntax_error(
+              file,
+              end.line,
+              format!(
+                "expected @end{} to close @{} from line {}, found @end{}",
+                block.tag.name, block.tag.name, block.tag.line, end.name
+              ),
+            ))
+          }
+          None => {
+            return Err(get_syntax_error(
+              file,
+              end.line,
+              format!("@end{} without a matching @{}", end.name, end.name),
+            ))
+          }
+        };
+
+        get_nodes(&mut root, &mut stack).push(Node::Block {
+          tag: block.tag,
+          end,
+          children: block.children,
+        });
+      }
+    }
+  }
+
+  match stack.pop() {
+    Some(block) => Err(get_syntax_error(
+      file,
+      block.tag.line,
+      format!(
+        "@{} is never closed, add @end{}",
+        block.tag.name, block.tag.name
+      ),
+    )),
+    None => Ok(root),
+  }
+}
+
+/// Placeholder of a tag, standalone tags are kept in their own paragraph
+pub fn to_placeholder(tag: &Tag, mark: Mark) -> String {
+  // Arguments are hex encoded after a `:`, so markdown does not change them
+  // (`~` would be read as strikethrough between two tags of the same line)
+  let attributes: String = match tag.attributes_source.is_empty() {
+    true => String::new(),
+    false => tag
+      .attributes_source
+      .bytes()
+      .fold(":".to_string(), |hex, b| format!("{}{:02x}", hex, b)),
+  };
+  let placeholder = format!(
+    "{p}{m}{name}{a}{p}",
+    p = PLACEHOLDER,
+    m = mark.as_str(),
+    name = tag.name,
+    a = attributes
+  );
+  match tag.standalone {
+    true => format!("\n{}\n", placeholder),
+    false => placeholder,
+  }
+}
+
+fn decode_hex(hex: &str) -> String {
+  let bytes: Vec<u8> = (0..hex.len() / 2)
+    .filter_map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok())
+    .collect();
+  String::from_utf8_lossy(&bytes).to_string()
+}
+
+/// Check if a paragraph only contains blocks, e.g.: `@info text @endinfo` written on one line
+fn has_only_blocks(paragraph: &str) -> bool {
+  let mut depth = 0;
+  let mut last = 0;
+  for captures in REG_PLACEHOLDER.captures_iter(paragraph) {
+    let matched = captures.get(0).unwrap();
+    if depth == 0 && !paragraph[last..matched.start()].trim().is_empty() {
+      return false;
+    }
+    last = matched.end();
+
+    match captures.name("im").map(|m| m.as_str()) {
+      Some("+") => depth += 1,
+      Some("/") if depth > 0 => depth -= 1,
+      _ if depth == 0 => return false,
+      _ => {}
+    }
+  }
+  last > 0 && depth == 0 && paragraph[last..].trim().is_empty()
+}
+
+/// Remove the paragraphs containing only blocks,
+/// blocks are rendered as block elements which are not allowed in a paragraph
+fn unwrap_block_paragraphs(html: &str) -> String {
+  let mut output = String::with_capacity(html.len());
+  let mut rest = html;
+  while let Some(start) = rest.find(&format!("<p>{}+", PLACEHOLDER)) {
+    let end = match rest[start..].find("</p>") {
+      Some(end) => start + end,
+      None => break,
+    };
+    let paragraph = &rest[start + "<p>".len()..end];
+    output.push_str(&rest[..start]);
+    match has_only_blocks(paragraph) {
+      true => output.push_str(paragraph),
+      false => output.push_str(&rest[start..end + "</p>".len()]),
+    }
+    rest = &rest[end + "</p>".len()..];
+  }
+  output.push_str(rest);
+  output
+}
+
+/// Parse the placeholders of rendered content back into nodes.
+///
+/// Placeholders alone in a paragraph replace the paragraph,
+/// as well as paragraphs containing only blocks
+pub fn parse_placeholders(html: &str) -> Result<Vec<Node>> {
+  let html = &unwrap_block_paragraphs(html);
+  let mut root = Vec::new();
+  let mut stack: Vec<OpenBlock> = Vec::new();
+  let mut last = 0;
+
+  for captures in REG_PLACEHOLDER.captures_iter(html) {
+    let matched = captures.get(0).unwrap();
+    let mark = captures.name("m").or(captures.name("im")).unwrap().as_str();
+    let name = captures.name("n").or(captures.name("in")).unwrap().as_str();
+    let attributes_source = decode_hex(
+      captures
+        .name("a")
+        .or(captures.name("ia"))
+        .map(|a| a.as_str())
+        .unwrap_or_default(),
+    );
+    let (attributes, _) = match attributes_source.is_empty() {
+      true => (Attributes::default(), 0),
+      false => Attributes::parse(&format!("{})", attributes_source)).map_err(|e| {
+        WordsmithError::InvalidTag(format!("@{}({}): {}", name, attributes_source, e))
+      })?,
+    };
+    let tag = Tag {
+      attributes,
+      attributes_source,
+      ..Tag::new(name, 0, captures.name("n").is_some())
+    };
+
+    push_node(
+      get_nodes(&mut root, &mut stack),
+      Node::Text(html[last..matched.start()].to_string()),
+    );
+    last = matched.end();
+
+    match mark {
+      "+" => stack.push(OpenBlock {
+        tag,
+        children: Vec::new(),
+      }),
+      "/" => {
+        let block = match stack.pop() {
+          Some(block) if block.tag.name == name => block,
+          _ => return Err(WordsmithError::InvalidTag(format!("@end{}", name)).into()),
+        };
+        get_nodes(&mut root, &mut stack).push(Node::Block {
+          tag: block.tag,
+          end: tag,
+          children: block.children,
+        });
+      }
+      _ => get_nodes(&mut root, &mut stack).push(Node::Tag(tag)),
+    }
+  }
+
+  if let Some(block) = stack.pop() {
+    return Err(WordsmithError::InvalidTag(format!("@{}", block.tag.name)).into());
+  }
+
+  push_node(&mut root, Node::Text(html[last..].to_string()));
+  Ok(root)
+}
+
+/// Check if a rendered content has a tag
+pub fn has_placeholder(html: &str, name: &str) -> bool {
+  html.contains(&format!("{}{}{}", PLACEHOLDER, name, PLACEHOLDER))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn get_tag_kind(name: &str) -> Option<TagKind> {
+    match name {
+      "break" | "toc" => Some(TagKind::Inline),
+      "info" | "warn" => Some(TagKind::Block),
+      _ => None,
+    }
+  }
+
+  fn tag(name: &str, line: usize, standalone: bool) -> Tag {
+    Tag::new(name, line, standalone)
+  }
+
+  fn text(value: &str) -> Node {
+    Node::Text(value.to_string())
+  }
+
+  #[test]
+  fn test_parse_nested_blocks() {
+    let nodes = parse(
+      "@info\na @warn b @endwarn\n@endinfo\n",
+      "a.md",
+      get_tag_kind,
+    )
+    .unwrap();
+
+    assert_eq!(
+      nodes,
+      vec![
+        Node::Block {
+          tag: tag("info", 1, true),
+          end: tag("info", 3, true),
+          children: vec![
+            text("\na "),
+            Node::Block {
+              tag: tag("warn", 2, false),
+              end: tag("warn", 2, false),
+              children: vec![text(" b ")],
+            },
+            text("\n"),
+          ],
+        },
+        text("\n"),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_parse_skips_code_and_unknown_tags() {
+    let source = "```\n@break\n```\n`@toc` name@email.com @breakfast \\@break\n\n    @break\n~~~~\n@toc\n~~~~\n";
+
+    assert_eq!(
+      parse(source, "a.md", get_tag_kind).unwrap(),
+      vec![text(source)]
+    );
+  }
+
+  #[test]
+  fn test_parse_list_is_not_code() {
+    let nodes = parse("- item\n\n    @break\n", "a.md", get_tag_kind).unwrap();
+
+    assert_eq!(
+      nodes,
+      vec![
+        text("- item\n\n    "),
+        Node::Tag(tag("break", 3, true)),
+        text("\n"),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    let error = |source: &str| {
+      parse(source, "content/a.md", get_tag_kind)
+        .unwrap_err()
+        .to_string()
+    };
+
+    assert_eq!(
+      error("text\n@info\n"),
+      "Invalid tag in content/a.md at line 2: @info is never closed, add @endinfo"
+    );
+    assert_eq!(
+      error("@info\n@warn\n@endinfo"),
+      "Invalid tag in content/a.md at line 3: expected @endwarn to close @warn from line 2, found @endinfo"
+    );
+    assert_eq!(
+      error("\n@endwarn"),
+      "Invalid tag in content/a.md at line 2: @endwarn without a matching @warn"
+    );
+  }
+
+  #[test]
+  fn test_parse_placeholders() {
+    let info = tag("info", 1, true);
+    let html = format!(
+      "<p>{}</p>\n<p>a{}</p>\n<p>{}</p>\n",
+      to_placeholder(&info, Mark::Open).trim(),
+      to_placeholder(&tag("break", 1, false), Mark::Inline),
+      to_placeholder(&info, Mark::Close).trim()
+    );
+
+    assert_eq!(
+      parse_placeholders(&html).unwrap(),
+      vec![Node::Block {
+        tag: tag("info", 0, true),
+        end: tag("info", 0, true),
+        children: vec![
+          text("<p>a"),
+          Node::Tag(tag("break", 0, false)),
+          text("</p>\n")
+        ],
+      }]
+    );
+  }
+
+  #[test]
+  fn test_unwrap_block_paragraphs() {
+    let info = |mark| to_placeholder(&tag("info", 1, false), mark);
+    let block = format!("{}a{}", info(Mark::Open), info(Mark::Close));
+    let badge = to_placeholder(&tag("break", 1, false), Mark::Inline);
+
+    assert_eq!(
+      unwrap_block_paragraphs(&format!("<p>{} {}</p>\n", block, block)),
+      format!("{} {}\n", block, block)
+    );
+    for paragraph in [
+      format!("<p>{} b</p>", block),
+      format!("<p>{}{}</p>", block, badge),
+      format!("<p>{}</p>", info(Mark::Open)),
+    ] {
+      assert_eq!(unwrap_block_paragraphs(&paragraph), paragraph);
+    }
+  }
+}
@@ -0,0 +1,120 @@
+use crate::{error::WordsmithError, references::References, utils::escape_html};
+
+use super::{Attributes, Component, Context};
+
+/// `@ref(label)`, link to a labelled heading, figure or table, e.g.: `3.2 Setup`.
+///
+/// `text` replaces the text of the link: `@ref(setup, text="the setup")`.
+/// When references have pages, an empty `ref-page` span is added, filled with
+/// the page number while printing the PDF.
+/// Without references (components compiled outside of a build), tags are left
+/// unresolved: a link to the label, using the label as text
+#[derive(Debug, Clone, Default)]
+pub struct RefTag {
+  references: Option<References>,
+}
+
+impl RefTag {
+  pub fn new(references: References) -> Self {
+    Self {
+      references: Some(references),
+    }
+  }
+}
+
+impl Component for RefTag {
+  fn compile(
+    &self,
+    _content: &str,
+    attributes: &Attributes,
+    _context: &Context,
+  ) -> anyhow::Result<String> {
+    log::info!("compile ref tag");
+
+    let label = attributes.get_string("0").unwrap_or_default();
+    let text = attributes.get_string("text").map(|text| escape_html(&text));
+    let references = match &self.references {
+      Some(references) => references,
+      None => {
+        return Ok(format!(
+          r##"<a class="ref" href="#{}">{}</a>"##,
+          escape_html(&label),
+          text.unwrap_or_else(|| escape_html(&label))
+        ))
+      }
+    };
+
+    let reference = references
+      .get(&label)
+      .ok_or_else(|| WordsmithError::InvalidTag(format!("@ref({}): label not found", label)))?;
+
+    let text = text.unwrap_or_else(|| reference.text.clone());
+    let page = match references.has_pages() {
+      true => format!(
+        r#"<span class="ref-page" data-ref-page="{}"></span>"#,
+        escape_html(&label)
+      ),
+      false => String::new(),
+    };
+
+    Ok(format!(
+      r#"<a class="ref" href="{}">{}{}</a>"#,
+      escape_html(&reference.href),
+      text,
+      page
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use super::*;
+  use crate::references::Label;
+
+  #[test]
+  fn test_compile() {
+    let (_, labels) = Label::parse_html(r#"<img src="a.png" alt="A diagram" />{#fig-a}"#);
+    let mut references = References::new().with_pages(true);
+    references.insert(&labels[0], "#fig-a");
+    let args = HashMap::new();
+    let context = Context {
+      name: "ref",
+      args: &args,
+    };
+    let tag = RefTag::new(references);
+
+    let (attributes, _) = Attributes::parse("fig-a)").unwrap();
+    assert_eq!(
+      tag.compile("", &attributes, &context).unwrap(),
+      r##"<a class="ref" href="#fig-a">A diagram<span class="ref-page" data-ref-page="fig-a"></span></a>"##
+    );
+
+    let (attributes, _) = Attributes::parse("missing)").unwrap();
+    assert_eq!(
+      tag
+        .compile("", &attributes, &context)
+        .unwrap_err()
+        .to_string(),
+      "Invalid tag @ref(missing): label not found"
+    );
+  }
+
+  #[test]
+  fn test_compile_without_references() {
+    let args = HashMap::new();
+    let context = Context {
+      name: "ref",
+      args: &args,
+    };
+
+    let (attributes, _) = Attributes::parse("fig-a)").unwrap();
+    assert_eq!(
+      RefTag::default()
+        .compile("", &attributes, &context)
+        .unwrap(),
+      r##"<a class="ref" href="#fig-a">fig-a</a>"##
+    );
+  }
+}
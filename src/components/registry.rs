@@ -0,0 +1,232 @@
+use std::{fmt, sync::Arc};
+
+use anyhow::Result;
+
+use crate::error::WordsmithError;
+
+use super::{
+  assets_path::AssetsPath, break_tag::BreakTag, code_tag::CodeTag, custom_block_tag::CustomBlock,
+  include_tag::IncludeTag, ref_tag::RefTag, themes_path::ThemesPath, toc_tag::TocTag, Attributes,
+  Component, REG_COMPONENT_NAME,
+};
+
+/// Priority of the built-in components
+pub const BUILTIN_PRIORITY: i32 = 0;
+
+/// Priority of the project components, from `ws.yaml` and `themes/components`
+pub const PROJECT_PRIORITY: i32 = 100;
+
+#[derive(Clone)]
+struct Entry {
+  name: String,
+  priority: i32,
+  component: Arc<dyn Component>,
+  /// Built-in source tag reading the file of its first argument, e.g.: `@include(setup.md)`
+  reads_file: bool,
+  /// The tag must be the only content of its line, e.g.: `@code` outputs a fenced code block
+  line_only: bool,
+}
+
+impl fmt::Debug for Entry {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Entry")
+      .field("name", &self.name)
+      .field("priority", &self.priority)
+      .field("reads_file", &self.reads_file)
+      .field("line_only", &self.line_only)
+      .finish()
+  }
+}
+
+/// Components available to the documents, by tag name.
+///
+/// Several components can be registered with the same name, the one with
+/// the highest priority is used. With the same priority, the last registered wins,
+/// e.g.: a component registered with [BUILTIN_PRIORITY] replaces the built-in one
+#[derive(Debug, Clone, Default)]
+pub struct ComponentRegistry {
+  entries: Vec<Entry>,
+}
+
+impl ComponentRegistry {
+  /// Create an empty registry
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Create a registry with the built-in components:
+  /// `@toc`, `@break`, `@ref`, `@include`, `@code`, `@assets_path`, `@themes_path`,
+  /// `@info`, `@warn`, `@danger` and `@quote`
+  pub fn with_builtins() -> Self {
+    let mut registry = Self::new();
+    registry.insert("toc", BUILTIN_PRIORITY, Arc::new(TocTag));
+    registry.insert("break", BUILTIN_PRIORITY, Arc::new(BreakTag));
+    registry.insert("ref", BUILTIN_PRIORITY, Arc::new(RefTag::default()));
+    registry.insert_file_tag("include", Arc::new(IncludeTag), false);
+    registry.insert_file_tag("code", Arc::new(CodeTag), true);
+    registry.insert("assets_path", BUILTIN_PRIORITY, Arc::new(AssetsPath));
+    registry.insert("themes_path", BUILTIN_PRIORITY, Arc::new(ThemesPath));
+    for name in CustomBlock::NAMES {
+      registry.insert(name, BUILTIN_PRIORITY, Arc::new(CustomBlock));
+    }
+    registry
+  }
+
+  fn insert(&mut self, name: &str, priority: i32, component: Arc<dyn Component>) {
+    self.entries.push(Entry {
+      name: name.to_string(),
+      priority,
+      component,
+      reads_file: false,
+      line_only: false,
+    });
+  }
+
+  /// Insert a built-in tag compiled with the content of a file, see [ComponentRegistry::get_file]
+  fn insert_file_tag(&mut self, name: &str, component: Arc<dyn Component>, line_only: bool) {
+    self.insert(name, BUILTIN_PRIORITY, component);
+    if let Some(entry) = self.entries.last_mut() {
+      entry.reads_file = true;
+      entry.line_only = line_only;
+    }
+  }
+
+  /// Register a component with [BUILTIN_PRIORITY], see [ComponentRegistry::register_with_priority]
+  pub fn register(&mut self, name: &str, component: impl Component + 'static) -> Result<()> {
+    self.register_with_priority(name, BUILTIN_PRIORITY, component)
+  }
+
+  /// Register a component used by `@name` tags.
+  ///
+  /// Return [WordsmithError::InvalidComponentName] if the name is not lowercase
+  /// letters, digits and `_`, or starts with `end`
+  pub fn register_with_priority(
+    &mut self,
+    name: &str,
+    priority: i32,
+    component: impl Component + 'static,
+  ) -> Result<()> {
+    if !REG_COMPONENT_NAME.is_match(name) || name.starts_with("end") {
+      return Err(WordsmithError::InvalidComponentName(name.to_string()).into());
+    }
+    log::debug!("Registering component {} (priority {})", name, priority);
+    self.insert(name, priority, Arc::new(component));
+    Ok(())
+  }
+
+  /// Add the components of another registry, registered after the current ones
+  pub fn extend(&mut self, other: ComponentRegistry) {
+    self.entries.extend(other.entries);
+  }
+
+  fn get_entry(&self, name: &str) -> Option<&Entry> {
+    self
+      .entries
+      .iter()
+      .filter(|entry| entry.name == name)
+      .max_by_key(|entry| entry.priority)
+  }
+
+  /// Component used for a tag name, `None` for unknown tags
+  pub fn get(&self, name: &str) -> Option<&dyn Component> {
+    self.get_entry(name).map(|entry| entry.component.as_ref())
+  }
+
+  /// The tag must be the only content of its line, e.g.: `@code`
+  pub(crate) fn is_line_only(&self, name: &str) -> bool {
+    self.get_entry(name).is_some_and(|entry| entry.line_only)
+  }
+
+  /// File read by a built-in source tag, relative to the content folder,
+  /// e.g.: `setup.md` for `@include(setup.md)`. The file is given as `content`
+  /// to [Component::compile], and tags of the output are compiled
+  pub(crate) fn get_file(&self, name: &str, attributes: &Attributes) -> Option<String> {
+    match self.get_entry(name)?.reads_file {
+      true => attributes.get_string("0"),
+      false => None,
+    }
+  }
+
+  /// Sorted names of the registered components
+  pub fn get_names(&self) -> Vec<&str> {
+    let mut names: Vec<&str> = self.entries.iter().map(|e| e.name.as_str()).collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use super::*;
+  use crate::components::{Attributes, Context, TagKind};
+
+  #[derive(Debug)]
+  struct Text(&'static str);
+
+  impl Component for Text {
+    fn compile(
+      &self,
+      _content: &str,
+      _attributes: &Attributes,
+      _context: &Context,
+    ) -> Result<String> {
+      Ok(self.0.to_string())
+    }
+  }
+
+  fn compile(registry: &ComponentRegistry, name: &str) -> String {
+    let args = HashMap::new();
+    registry
+      .get(name)
+      .unwrap()
+      .compile("", &Attributes::default(), &Context { name, args: &args })
+      .unwrap()
+  }
+
+  #[test]
+  fn test_get_priority() {
+    let mut registry = ComponentRegistry::with_builtins();
+    registry
+      .register_with_priority("note", 10, Text("high"))
+      .unwrap();
+    registry.register("note", Text("low")).unwrap();
+    registry.register("break", Text("replaced")).unwrap();
+
+    assert_eq!(compile(&registry, "note"), "high");
+    assert_eq!(compile(&registry, "break"), "replaced");
+    assert_eq!(registry.get("info").unwrap().kind(), TagKind::Block);
+    assert!(registry.get("missing").is_none());
+    assert_eq!(
+      registry.get_names(),
+      [
+        "assets_path",
+        "break",
+        "code",
+        "danger",
+        "include",
+        "info",
+        "note",
+        "quote",
+        "ref",
+        "themes_path",
+        "toc",
+        "warn"
+      ]
+    );
+  }
+
+  #[test]
+  fn test_register_invalid_name() {
+    let mut registry = ComponentRegistry::new();
+
+    for name in ["Note", "endnote", "my-note", ""] {
+      assert_eq!(
+        registry.register(name, Text("")).unwrap_err().to_string(),
+        format!("Invalid component name {}", name)
+      );
+    }
+  }
+}
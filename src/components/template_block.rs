@@ -0,0 +1,91 @@
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+use crate::utils::escape_html;
+
+use super::{Attributes, Component, Context, TagKind};
+
+lazy_static! {
+    /// Match template placeholders, e.g.: `{{ content }}`
+    ///
+    /// Expect the capture naming groups:
+    /// - n: for the placeholder name
+    static ref REG_TEMPLATE_PLACEHOLDER: Regex = Regex::new(r"\{\{\s*(?P<n>\w+)\s*\}\}").unwrap();
+}
+
+/// Block component defined by the project, in `ws.yaml` or `themes/components/<name>.html`.
+///
+/// `{{ content }}` is replaced by the block content, other placeholders by the
+/// tag argument with the same name, e.g.: `{{ title }}`, or positional `{{ 0 }}`,
+/// then by the tag name (`{{ name }}`) and the components arguments, e.g.: `{{ assets_path }}`
+#[derive(Debug, Clone)]
+pub struct TemplateBlock {
+  template: String,
+}
+
+impl TemplateBlock {
+  pub fn new(template: &str) -> Self {
+    Self {
+      template: template.to_string(),
+    }
+  }
+}
+
+impl Component for TemplateBlock {
+  fn kind(&self) -> TagKind {
+    TagKind::Block
+  }
+
+  fn compile(
+    &self,
+    content: &str,
+    attributes: &Attributes,
+    context: &Context,
+  ) -> anyhow::Result<String> {
+    Ok(
+      REG_TEMPLATE_PLACEHOLDER
+        .replace_all(&self.template, |captures: &Captures| match &captures["n"] {
+          "content" => content.to_string(),
+          name => escape_html(
+            &attributes
+              .get_string(name)
+              .or_else(|| match name {
+                "name" => Some(context.name.to_string()),
+                name => context.args.get(name).map(|arg| arg.to_string()),
+              })
+              .unwrap_or_default(),
+          ),
+        })
+        .to_string(),
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use super::*;
+  use crate::components::ComponentArg;
+
+  #[test]
+  fn test_compile() {
+    let block = TemplateBlock::new(
+      r#"<aside class="{{name}}" title="{{ title }}"><img src="{{ assets_path }}/{{0}}.png">{{ content }}{{ missing }}</aside>"#,
+    );
+    let args = HashMap::from([(
+      "assets_path".to_string(),
+      ComponentArg::String("assets".to_string()),
+    )]);
+    let context = Context {
+      name: "tip",
+      args: &args,
+    };
+    let (attributes, _) = Attributes::parse(r#"bulb, title="<Note>")"#).unwrap();
+
+    assert_eq!(
+      block.compile("<p>Text</p>", &attributes, &context).unwrap(),
+      r#"<aside class="tip" title="&lt;Note&gt;"><img src="assets/bulb.png"><p>Text</p></aside>"#
+    );
+  }
+}
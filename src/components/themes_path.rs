@@ -1,55 +1,47 @@
-use lazy_static::lazy_static;
-use regex::Regex;
-
-use super::Component;
-
-lazy_static! {
-    /// Match tag util to include a reference to the assets path
-    static ref REG_THEMES_PATH: Regex = Regex::new(r"@themes_path").unwrap();
-}
+use super::{Attributes, Component, Context};
 
+/// `@themes_path`, replaced by the `themes_path` argument, a folder path or url
 #[derive(Debug, Clone)]
-pub struct ThemesPath {
-  themes_path: String,
-}
+pub struct ThemesPath;
 
-impl ThemesPath {
-  pub fn new(path: String) -> Self {
-    Self { themes_path: path }
+impl Component for ThemesPath {
+  fn is_source_tag(&self) -> bool {
+    true
   }
-}
 
-impl Component for ThemesPath {
-  fn compile(&self, input: &str) -> anyhow::Result<String> {
-    log::info!(
-      "compile themes_path: {:?} => {}",
-      REG_THEMES_PATH.as_str(),
-      &self.themes_path
-    );
-    Ok(
-      REG_THEMES_PATH
-        .replace_all(input, &self.themes_path)
-        .to_string(),
-    )
+  fn compile(
+    &self,
+    _content: &str,
+    _attributes: &Attributes,
+    context: &Context,
+  ) -> anyhow::Result<String> {
+    let themes_path = context.get_string_arg("themes_path");
+    log::info!("compile themes_path: {}", &themes_path);
+    Ok(themes_path)
   }
 }
 
 #[cfg(test)]
 mod tests {
+  use std::collections::HashMap;
+
   use super::*;
+  use crate::components::ComponentArg;
 
   #[test]
   fn test_compile() {
-    let themes_path = ThemesPath {
-      themes_path: "/path/to/themes".to_owned(),
+    let args = HashMap::from([(
+      "themes_path".to_string(),
+      ComponentArg::String("/path/to/themes".to_string()),
+    )]);
+    let context = Context {
+      name: "themes_path",
+      args: &args,
     };
 
-    let input = "random text @themes_path here";
-    let expected_output = "random text /path/to/themes here";
-
-    let result = themes_path.compile(input);
+    let result = ThemesPath.compile("", &Attributes::default(), &context);
 
     assert!(result.is_ok());
-    assert_eq!(result.unwrap(), expected_output);
+    assert_eq!(result.unwrap(), "/path/to/themes");
   }
 }
@@ -0,0 +1,42 @@
+use super::{Attributes, Component, Context};
+
+/// `@toc`, places the table of contents, given as the `toc` argument
+#[derive(Debug, Clone)]
+pub struct TocTag;
+
+impl Component for TocTag {
+  fn compile(
+    &self,
+    _content: &str,
+    _attributes: &Attributes,
+    context: &Context,
+  ) -> anyhow::Result<String> {
+    log::info!("compile toc tag");
+    Ok(context.get_string_arg("toc"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use super::*;
+  use crate::components::ComponentArg;
+
+  #[test]
+  fn test_compile() {
+    let args = HashMap::from([(
+      "toc".to_string(),
+      ComponentArg::String(r#"<div id="toc"></div>"#.to_string()),
+    )]);
+    let context = Context {
+      name: "toc",
+      args: &args,
+    };
+
+    let result = TocTag.compile("", &Attributes::default(), &context);
+
+    assert!(result.is_ok());
+    assert_eq!(result.unwrap(), r#"<div id="toc"></div>"#);
+  }
+}
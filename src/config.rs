@@ -1,24 +1,123 @@
-use std::{fs, path::Path};
+use std::{collections::HashMap, fmt, fs, marker::PhantomData, path::Path};
 
 use anyhow::Result;
-use yaml_rust::yaml::Hash;
-use yaml_rust::{Yaml, YamlLoader};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{
+  de::{
+    value::{MapAccessDeserializer, SeqAccessDeserializer},
+    MapAccess, SeqAccess, Visitor,
+  },
+  Deserialize, Deserializer,
+};
+use yaml_rust::{
+  parser::{Event, MarkedEventReceiver, Parser},
+  scanner::Marker,
+  YamlLoader,
+};
 
-use crate::error::WordsmithError;
+use crate::{
+  components::{is_valid_block_name, INLINE_TAGS},
+  diagrams::DiagramKind,
+  error::{ConfigIssue, WordsmithError},
+};
 
-#[derive(Default, PartialEq, Debug, Clone)]
+lazy_static! {
+    /// Location appended to yaml error messages, e.g.: ` at line 3 column 5`
+    static ref REG_ERROR_LOCATION: Regex = Regex::new(r" at line \d+ column \d+$").unwrap();
+
+    /// Length with an optional unit, e.g.: `10`, `1.5cm`
+    static ref REG_LENGTH: Regex = Regex::new(r"^(?P<n>-?\d+(\.\d+)?)\s*(?P<u>[a-z]+)?$").unwrap();
+
+    /// Paper size, e.g.: `6x9in`
+    static ref REG_PAPER_SIZE: Regex = Regex::new(r"^(?P<w>\d+(\.\d+)?)x(?P<h>\d+(\.\d+)?)(?P<u>mm|cm|in|pt)$").unwrap();
+
+    /// Highlight scheme, a css file name, e.g.: `dark-github-gist`
+    static ref REG_SCHEME: Regex = Regex::new(r"^[A-Za-z0-9_.-]+$").unwrap();
+
+    /// Language tag, e.g.: `en`, `pt-BR`
+    static ref REG_LANGUAGE: Regex = Regex::new(r"^[A-Za-z]{2,3}(-[A-Za-z0-9]{2,8})*$").unwrap();
+}
+
+#[derive(Default, PartialEq, Debug, Clone, Deserialize)]
 pub struct Config {
   /// The exported document title
+  #[serde(default = "default_title")]
   pub title: String,
 
   /// Document configurations - margins, dimension
+  #[serde(default)]
   pub document: DocumentConfig,
 
-  /// Cover page configuration
+  /// Cover page configuration, no cover page when not defined
+  #[serde(default = "default_cover")]
   pub cover: CoverConfig,
 
   /// List of authors
+  #[serde(default, deserialize_with = "deserialize_authors")]
   pub authors: Option<Vec<String>>,
+
+  /// Table of contents configuration
+  #[serde(default)]
+  pub toc: TocConfig,
+
+  /// PDF outline (bookmarks) configuration
+  #[serde(default, deserialize_with = "deserialize_bool_or")]
+  pub outline: OutlineConfig,
+
+  /// Heading numbering configuration
+  #[serde(default, deserialize_with = "deserialize_bool_or")]
+  pub numbering: NumberingConfig,
+
+  /// Figure and table captions configuration
+  #[serde(default, deserialize_with = "deserialize_bool_or")]
+  pub captions: CaptionsConfig,
+
+  /// Code blocks syntax highlighting configuration
+  #[serde(default, deserialize_with = "deserialize_bool_or")]
+  pub highlight: HighlightConfig,
+
+  /// Typeset `$...$` and `$$...$$` LaTeX formulas as MathML
+  #[serde(default = "default_math")]
+  pub math: bool,
+
+  /// Fenced diagram blocks configuration, the script rendering each language
+  #[serde(default, deserialize_with = "deserialize_bool_or")]
+  pub diagrams: DiagramsConfig,
+
+  /// Reading order of the content files, instead of sorting them by name.
+  /// `content/SUMMARY.md` is used when not defined
+  #[serde(default, deserialize_with = "deserialize_chapters")]
+  pub chapters: Option<Vec<ChapterEntry>>,
+
+  /// Block components of the project, name and HTML template.
+  /// `{{ content }}` is replaced by the block content
+  #[serde(default)]
+  pub components: HashMap<String, String>,
+
+  /// Running page header
+  #[serde(default, deserialize_with = "deserialize_header_footer")]
+  pub header: Option<HeaderFooterConfig>,
+
+  /// Running page footer
+  #[serde(default, deserialize_with = "deserialize_header_footer")]
+  pub footer: Option<HeaderFooterConfig>,
+
+  /// Short description of the document subject
+  #[serde(default, deserialize_with = "deserialize_optional_string")]
+  pub subject: Option<String>,
+
+  /// List of keywords
+  #[serde(default, deserialize_with = "deserialize_keywords")]
+  pub keywords: Option<Vec<String>>,
+
+  /// Document language, e.g.: `en`, `pt-BR`
+  #[serde(default, deserialize_with = "deserialize_optional_string")]
+  pub language: Option<String>,
+
+  /// Publisher name
+  #[serde(default, deserialize_with = "deserialize_optional_string")]
+  pub publisher: Option<String>,
 }
 
 impl Config {
@@ -26,140 +125,759 @@ impl Config {
     Self {
       title: Default::default(),
       document: DocumentConfig::default(),
-      cover: CoverConfig::default(),
+      cover: default_cover(),
       authors: Default::default(),
+      toc: TocConfig::default(),
+      outline: OutlineConfig::default(),
+      numbering: NumberingConfig::default(),
+      captions: CaptionsConfig::default(),
+      highlight: HighlightConfig::default(),
+      math: default_math(),
+      diagrams: DiagramsConfig::default(),
+      chapters: None,
+      components: HashMap::new(),
+      header: None,
+      footer: None,
+      subject: None,
+      keywords: None,
+      language: None,
+      publisher: None,
     }
   }
 
-  fn yaml_key(&self, key: &str) -> Yaml {
-    Yaml::String(key.to_string())
+  /// Return the document language, defaults to `en`
+  pub fn get_language(&self) -> &str {
+    self.language.as_deref().unwrap_or("en")
   }
 
-  /// Load project configuration file from a yaml file
-  pub fn load_from_file(&mut self, file: &Path) -> Result<Self> {
+  /// Return the list of authors without e-mail addresses,
+  /// e.g.: `Name <name@email.com>` becomes `Name`
+  pub fn get_author_names(&self) -> Vec<String> {
+    self
+      .authors
+      .iter()
+      .flatten()
+      .map(|author| match author.find('<') {
+        Some(index) => author[..index].trim().to_string(),
+        None => author.trim().to_string(),
+      })
+      .filter(|author| !author.is_empty())
+      .collect()
+  }
+
+  /// Load project configuration file from a yaml file.
+  ///
+  /// Return the configuration and the unknown keys, displayed as warnings by the commands.
+  pub fn load_from_file(file: &Path) -> Result<(Self, Vec<ConfigIssue>)> {
     let source = fs::read_to_string(file)?;
-    let doc = YamlLoader::load_from_str(&source)?;
+    Ok(Self::from_yaml(&source, &file.display().to_string())?)
+  }
+
+  /// Parse and validate a yaml configuration. Files of the configuration,
+  /// e.g.: the cover template, are relative to the folder of `file`.
+  ///
+  /// Return the configuration and the list of unknown keys, or an error
+  /// with every invalid value found, including key paths and line numbers.
+  pub fn from_yaml(source: &str, file: &str) -> Result<(Self, Vec<ConfigIssue>), WordsmithError> {
+    let folder = Path::new(file).parent().unwrap_or_else(|| Path::new(""));
+    let documents =
+      YamlLoader::load_from_str(source).map_err(|e| WordsmithError::ConfigSyntax {
+        file: file.to_string(),
+        line: e.marker().line(),
+        message: strip_error_location(&e.to_string()),
+      })?;
+
+    // Empty files use the default configuration
+    if documents.first().is_none_or(|doc| doc.is_null()) {
+      return Ok((Self::default(), Vec::new()));
+    }
+
+    let lines = KeyLines::from_yaml(source);
+    let invalid_config = |issues: Vec<ConfigIssue>| WordsmithError::InvalidConfig {
+      file: file.to_string(),
+      issues,
+    };
+
+    let mut unknown_keys = Vec::new();
+    let mut track_unknown_key = |path: serde_ignored::Path| {
+      unknown_keys.push(get_ignored_key_path(&path));
+    };
+    let deserializer = serde_ignored::Deserializer::new(
+      serde_yaml::Deserializer::from_str(source),
+      &mut track_unknown_key,
+    );
 
-    if doc.is_empty() {
-      return Ok(Self::default());
+    let config: Self = match serde_path_to_error::deserialize(deserializer) {
+      Ok(config) => config,
+      Err(e) => {
+        let mut issues = vec![get_deserialize_issue(&e, &lines)];
+        issues.extend(Self::get_other_issues(source, &e, &lines, folder));
+        return Err(invalid_config(issues));
+      }
+    };
+
+    let issues = config.get_validation_issues(&lines, folder);
+    if !issues.is_empty() {
+      return Err(invalid_config(issues));
     }
 
-    let doc = &doc[0];
-    let title = self.get_title_from_yaml(doc);
-    let authors = self.get_authors_from_yaml(doc);
-    let document = self.get_document_config_from_yaml(doc);
-    let cover = self.get_cover_from_yaml(doc)?;
+    let warnings = unknown_keys
+      .into_iter()
+      .map(|path| ConfigIssue {
+        line: lines.find(&path),
+        path,
+        message: "unknown key, ignored".to_string(),
+      })
+      .collect();
 
-    Ok(Self {
-      title,
-      document,
-      authors,
-      cover,
-    })
+    Ok((config, warnings))
   }
 
-  /// Extract the title from configuration file.
+  /// Keep looking for problems after the first invalid value.
   ///
-  /// The title will be used as an alternative text (alt) for cover image,
-  /// pdf title and as a fallback if there is no cover image
-  fn get_title_from_yaml(&self, doc: &Yaml) -> String {
-    doc["title"].as_str().unwrap_or("Default title").to_string()
+  /// The section (top level key) of the invalid value is removed and the configuration
+  /// deserialized again, until the remaining sections are valid and can be validated.
+  fn get_other_issues(
+    source: &str,
+    error: &serde_path_to_error::Error<serde_yaml::Error>,
+    lines: &KeyLines,
+    folder: &Path,
+  ) -> Vec<ConfigIssue> {
+    let mut value: serde_yaml::Value = match serde_yaml::from_str(source) {
+      Ok(value) => value,
+      Err(_) => return Vec::new(),
+    };
+
+    let mut issues = Vec::new();
+    let mut section = get_key_section(&error.path().to_string());
+    loop {
+      let removed = value
+        .as_mapping_mut()
+        .and_then(|mapping| mapping.remove(&section));
+      if removed.is_none() {
+        return issues;
+      }
+
+      match serde_path_to_error::deserialize::<_, Self>(value.clone()) {
+        Ok(config) => {
+          issues.extend(config.get_validation_issues(lines, folder));
+          return issues;
+        }
+        Err(e) => {
+          // Required sections can not be removed
+          let next = get_key_section(&e.path().to_string());
+          if next == section || next.is_empty() {
+            return issues;
+          }
+          issues.push(get_deserialize_issue(&e, lines));
+          section = next;
+        }
+      }
+    }
   }
 
-  /// Get list of authors.
-  ///
-  /// The list of authors will be included in the pdf metadata
-  fn get_authors_from_yaml(&self, doc: &Yaml) -> Option<Vec<String>> {
-    let mut authors = Vec::<String>::new();
-    for entry in doc["authors"].as_vec().unwrap() {
-      authors.push(entry.as_str().unwrap().to_string());
+  /// Validation problems of the configuration, see [Config::validate]
+  fn get_validation_issues(&self, lines: &KeyLines, folder: &Path) -> Vec<ConfigIssue> {
+    self
+      .validate(folder)
+      .into_iter()
+      .map(|(path, message)| ConfigIssue {
+        line: lines.find(&path),
+        path,
+        message,
+      })
+      .collect()
+  }
+
+  /// Check values that are valid yaml but can not be used, and files of the
+  /// project folder that do not exist. Return the key path and a message for each problem
+  fn validate(&self, folder: &Path) -> Vec<(String, String)> {
+    let mut issues = Vec::new();
+
+    let (doc_w, doc_h) = self.document.dimensions.get_values();
+    if self.document.get_size() == PaperSize::Custom && (doc_w <= 0.0 || doc_h <= 0.0) {
+      issues.push((
+        "document.dimensions".to_string(),
+        "width and height are required by the custom paper size".to_string(),
+      ));
+    }
+    if let Some(Dimensions(width, height)) =
+      self.document.size.as_ref().and_then(|s| s.get_dimensions())
+    {
+      if width <= 0.0 || height <= 0.0 {
+        issues.push((
+          "document.size".to_string(),
+          "must be greater than 0".to_string(),
+        ));
+      }
+    }
+
+    let (left, top, right, bottom) = self.document.margins.get_values();
+    let (cover_w, cover_h) = self.cover.dimension.get_values();
+    let mut sizes = vec![
+      ("document.dimensions[0]".to_string(), doc_w),
+      ("document.dimensions[1]".to_string(), doc_h),
+      ("document.margins.left".to_string(), left),
+      ("document.margins.top".to_string(), top),
+      ("document.margins.right".to_string(), right),
+      ("document.margins.bottom".to_string(), bottom),
+      ("cover.dimensions[0]".to_string(), cover_w),
+      ("cover.dimensions[1]".to_string(), cover_h),
+    ];
+
+    for (key, section) in [("header", &self.header), ("footer", &self.footer)] {
+      if let Some(section) = section {
+        sizes.push((format!("{}.height", key), section.height));
+        if section.font_size <= 0.0 {
+          issues.push((
+            format!("{}.font_size", key),
+            "must be greater than 0".to_string(),
+          ));
+        }
+      }
     }
-    Some(authors)
+
+    for (path, value) in sizes {
+      if value < 0.0 {
+        issues.push((path, "must not be negative".to_string()));
+      }
+    }
+
+    if self.cover.mode == CoverMode::Image && self.cover.filename.trim().is_empty() {
+      issues.push(("cover.file".to_string(), "must not be empty".to_string()));
+    }
+    let cover_files = [
+      ("cover.template", self.cover.template.clone()),
+      (
+        "cover.back",
+        self
+          .cover
+          .back
+          .as_ref()
+          .map(|back| format!("assets/images/{}", back)),
+      ),
+    ];
+    for (path, file) in cover_files {
+      match file {
+        Some(file) if !folder.join(&file).is_file() => {
+          issues.push((path.to_string(), format!("{} not found", file)))
+        }
+        _ => {}
+      }
+    }
+
+    for (path, depth) in [
+      ("toc.depth", self.toc.depth),
+      ("outline.depth", self.outline.depth),
+      ("numbering.depth", self.numbering.depth),
+    ] {
+      if !(1..=6).contains(&depth) {
+        issues.push((path.to_string(), "must be between 1 and 6".to_string()));
+      }
+    }
+
+    if let Some(chapters) = &self.chapters {
+      validate_chapters(chapters, "chapters", &mut issues);
+    }
+
+    let mut names: Vec<&String> = self.components.keys().collect();
+    names.sort();
+    for name in names {
+      if !is_valid_block_name(name) {
+        issues.push((
+          format!("components.{}", name),
+          format!(
+            "{} can not be used as a tag, use lowercase letters, digits and _ (not {})",
+            name,
+            INLINE_TAGS.join(", ")
+          ),
+        ));
+      }
+    }
+
+    for (theme, scheme) in &self.highlight.schemes {
+      if !REG_SCHEME.is_match(scheme) {
+        issues.push((
+          format!("highlight.schemes.{}", theme),
+          format!(
+            "{} is not a valid scheme, use a css file name of themes/css without extension",
+            scheme
+          ),
+        ));
+      }
+    }
+
+    if let Some(language) = &self.language {
+      if !REG_LANGUAGE.is_match(language) {
+        issues.push((
+          "language".to_string(),
+          format!("{} is not a valid language tag, e.g.: en, pt-BR", language),
+        ));
+      }
+    }
+
+    issues
   }
+}
+
+/// Check that every entry is a file, or a part with a folder or chapters
+fn validate_chapters(chapters: &[ChapterEntry], path: &str, issues: &mut Vec<(String, String)>) {
+  for (index, entry) in chapters.iter().enumerate() {
+    let path = format!("{}[{}]", path, index);
+    let is_part = entry.part.is_some() || entry.folder.is_some() || entry.chapters.is_some();
 
-  /// Get document configuration,
-  /// which will be used to  determine the PDF's dimensions and margins
-  fn get_document_config_from_yaml(&self, doc: &Yaml) -> DocumentConfig {
-    if doc["document"].as_hash().is_none() {
-      return DocumentConfig::default();
+    match &entry.file {
+      Some(file) if file.trim().is_empty() => {
+        issues.push((format!("{}.file", path), "must not be empty".to_string()))
+      }
+      Some(_) if is_part => issues.push((
+        path.clone(),
+        "a chapter file can not have part, folder or chapters".to_string(),
+      )),
+      Some(_) => {}
+      None if entry.folder.is_none() && entry.chapters.is_none() => issues.push((
+        path.clone(),
+        "set a file, or the folder or chapters of a part".to_string(),
+      )),
+      None => {}
     }
 
-    let doc = doc["document"].as_hash().unwrap();
+    if let Some(chapters) = &entry.chapters {
+      validate_chapters(chapters, &format!("{}.chapters", path), issues);
+    }
+  }
+}
 
-    let margins = if let Some(m) = doc.get(&self.yaml_key("margins")) {
-      self.get_position_values(&mut m.as_hash().unwrap().clone())
-    } else {
-      PositionValues::default()
-    };
+fn default_title() -> String {
+  "Default title".to_string()
+}
 
-    let dimensions = if let Some(document_entry) = doc.get(&self.yaml_key("dimensions")) {
-      self.get_dimension_from_vec(&mut document_entry.as_vec().unwrap().clone())
-    } else {
-      Dimensions::default()
-    };
+fn default_cover() -> CoverConfig {
+  CoverConfig {
+    mode: CoverMode::None,
+    ..CoverConfig::default()
+  }
+}
+
+fn default_math() -> bool {
+  false
+}
+
+/// Remove the location added to yaml error messages,
+/// it is displayed separately
+fn strip_error_location(message: &str) -> String {
+  REG_ERROR_LOCATION.replace(message, "").to_string()
+}
 
-    DocumentConfig {
-      dimensions,
-      margins,
+/// Issue of a value that can not be deserialized.
+/// Errors without location (e.g.: custom errors) use the line of their key
+fn get_deserialize_issue(
+  error: &serde_path_to_error::Error<serde_yaml::Error>,
+  lines: &KeyLines,
+) -> ConfigIssue {
+  let path = match error.path().to_string().as_str() {
+    "." => String::new(),
+    path => path.to_string(),
+  };
+  let line = error
+    .inner()
+    .location()
+    .map(|location| location.line())
+    .or_else(|| lines.find(&path));
+  let message = strip_error_location(&error.inner().to_string());
+  let message = message
+    .strip_prefix(&format!("{}: ", path))
+    .unwrap_or(&message)
+    .to_string();
+
+  ConfigIssue {
+    path,
+    line,
+    message,
+  }
+}
+
+/// Top level key of a key path, e.g.: `document` for `document.margins.left`
+fn get_key_section(path: &str) -> String {
+  match path {
+    "." => String::new(),
+    path => path
+      .split(['.', '['])
+      .next()
+      .unwrap_or_default()
+      .to_string(),
+  }
+}
+
+/// Key path of an unknown key, using the same format as error paths,
+/// e.g.: `document.margins.lef`
+fn get_ignored_key_path(path: &serde_ignored::Path) -> String {
+  match path {
+    serde_ignored::Path::Root => String::new(),
+    serde_ignored::Path::Seq { parent, index } => {
+      format!("{}[{}]", get_ignored_key_path(parent), index)
     }
+    serde_ignored::Path::Map { parent, key } => join_key_path(&get_ignored_key_path(parent), key),
+    serde_ignored::Path::Some { parent }
+    | serde_ignored::Path::NewtypeStruct { parent }
+    | serde_ignored::Path::NewtypeVariant { parent } => get_ignored_key_path(parent),
   }
+}
 
-  /// Get list of position values (left, top, right, bottom)
-  fn get_position_values(&self, doc: &mut Hash) -> PositionValues {
-    let get_position_value = |pos: &str| match doc.get(&self.yaml_key(pos)) {
-      Some(v) => v.as_f64().unwrap_or(0.0),
-      None => 0.0,
-    };
+fn join_key_path(parent: &str, key: &str) -> String {
+  if parent.is_empty() {
+    key.to_string()
+  } else {
+    format!("{}.{}", parent, key)
+  }
+}
 
-    PositionValues(
-      get_position_value("left"),
-      get_position_value("top"),
-      get_position_value("right"),
-      get_position_value("bottom"),
-    )
+/// Yaml node being read while collecting key lines
+enum KeyLinesNode {
+  Mapping { path: String, key: Option<String> },
+  Sequence { path: String, index: usize },
+}
+
+/// Line number of every key path of a yaml document,
+/// e.g.: `document.margins.left` or `cover.dimensions[1]`
+#[derive(Default)]
+struct KeyLines {
+  nodes: Vec<KeyLinesNode>,
+  lines: HashMap<String, usize>,
+}
+
+impl KeyLines {
+  fn from_yaml(source: &str) -> Self {
+    let mut key_lines = Self::default();
+    // Syntax errors are reported before, while loading the document
+    let _ = Parser::new(source.chars()).load(&mut key_lines, false);
+    key_lines
   }
 
-  fn get_dimension_from_vec(&self, doc: &mut [Yaml]) -> Dimensions {
-    Dimensions(
-      doc[0].as_f64().unwrap_or(21.0),
-      doc[1].as_f64().unwrap_or(29.0),
-    )
+  /// Line of a key path, or of the closest parent key
+  fn find(&self, path: &str) -> Option<usize> {
+    let mut path = path;
+    loop {
+      if let Some(line) = self.lines.get(path) {
+        return Some(*line);
+      }
+      path = &path[..path.rfind(['.', '['])?];
+    }
   }
 
-  fn get_cover_from_yaml(&self, doc: &Yaml) -> Result<CoverConfig, WordsmithError> {
-    if doc["cover"].as_hash().is_none() {
-      return Err(WordsmithError::ConfigCoverNotDefined);
+  /// Path of the value starting at the current position
+  fn get_value_path(&mut self, mark: &Marker) -> String {
+    match self.nodes.last() {
+      Some(KeyLinesNode::Mapping {
+        path,
+        key: Some(key),
+      }) => join_key_path(path, key),
+      Some(KeyLinesNode::Sequence { path, index }) => {
+        let path = format!("{}[{}]", path, index);
+        self.lines.insert(path.clone(), mark.line());
+        path
+      }
+      _ => String::new(),
     }
+  }
 
-    let cover = doc["cover"].as_hash().unwrap();
+  /// Move to the next key or sequence item
+  fn end_value(&mut self) {
+    match self.nodes.last_mut() {
+      Some(KeyLinesNode::Mapping { key, .. }) => *key = None,
+      Some(KeyLinesNode::Sequence { index, .. }) => *index += 1,
+      None => {}
+    }
+  }
+}
 
-    let filename = if let Some(filename_entry) = cover.get(&self.yaml_key("file")) {
-      filename_entry.as_str().unwrap().to_string()
-    } else {
-      return Err(WordsmithError::ConfigCoverFileIsInvalid);
-    };
+impl MarkedEventReceiver for KeyLines {
+  fn on_event(&mut self, ev: Event, mark: Marker) {
+    match ev {
+      Event::MappingStart(_) => {
+        let path = self.get_value_path(&mark);
+        self.nodes.push(KeyLinesNode::Mapping { path, key: None });
+      }
+      Event::SequenceStart(_) => {
+        let path = self.get_value_path(&mark);
+        self.nodes.push(KeyLinesNode::Sequence { path, index: 0 });
+      }
+      Event::MappingEnd | Event::SequenceEnd => {
+        self.nodes.pop();
+        self.end_value();
+      }
+      Event::Scalar(value, ..) => match self.nodes.last_mut() {
+        Some(KeyLinesNode::Mapping {
+          path,
+          key: key @ None,
+        }) => {
+          self.lines.insert(join_key_path(path, &value), mark.line());
+          *key = Some(value);
+        }
+        _ => {
+          self.get_value_path(&mark);
+          self.end_value();
+        }
+      },
+      Event::Alias(_) => {
+        self.get_value_path(&mark);
+        self.end_value();
+      }
+      _ => {}
+    }
+  }
+}
 
-    let position = if let Some(position_entry) = cover.get(&self.yaml_key("position")) {
-      self.get_position_values(&mut position_entry.as_hash().unwrap().clone())
-    } else {
-      PositionValues::default()
-    };
+/// Accept a list or a single text, optionally split by a separator
+struct StringListVisitor {
+  separator: Option<char>,
+}
+
+impl<'de> Visitor<'de> for StringListVisitor {
+  type Value = Option<Vec<String>>;
 
-    let dimension = if let Some(dimension_entry) = cover.get(&self.yaml_key("dimensions")) {
-      self.get_dimension_from_vec(&mut dimension_entry.as_vec().unwrap().clone())
-    } else {
-      Dimensions::default()
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("a list or a text")
+  }
+
+  fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+    Ok(None)
+  }
+
+  fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+    let values = match self.separator {
+      Some(separator) => value.split(separator).map(|v| v.to_string()).collect(),
+      None => vec![value.to_string()],
     };
+    Ok(Some(clean_string_list(values)))
+  }
 
-    Ok(CoverConfig {
-      filename,
-      dimension,
-      position,
-    })
+  fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+    let values = Vec::<String>::deserialize(SeqAccessDeserializer::new(seq))?;
+    Ok(Some(clean_string_list(values)))
   }
 }
 
-#[derive(Default, PartialEq, Debug, Clone)]
+fn clean_string_list(values: Vec<String>) -> Vec<String> {
+  values
+    .into_iter()
+    .map(|value| value.trim().to_string())
+    .filter(|value| !value.is_empty())
+    .collect()
+}
+
+/// Authors can be defined as a list or a single name
+fn deserialize_authors<'de, D: Deserializer<'de>>(
+  deserializer: D,
+) -> Result<Option<Vec<String>>, D::Error> {
+  deserializer.deserialize_any(StringListVisitor { separator: None })
+}
+
+/// Keywords can be defined as a list or as a comma separated text
+fn deserialize_keywords<'de, D: Deserializer<'de>>(
+  deserializer: D,
+) -> Result<Option<Vec<String>>, D::Error> {
+  deserializer.deserialize_any(StringListVisitor {
+    separator: Some(','),
+  })
+}
+
+/// Optional text entry. Empty values are ignored
+fn deserialize_optional_string<'de, D: Deserializer<'de>>(
+  deserializer: D,
+) -> Result<Option<String>, D::Error> {
+  Ok(
+    Option::<String>::deserialize(deserializer)?
+      .map(|value| value.trim().to_string())
+      .filter(|value| !value.is_empty()),
+  )
+}
+
+/// Sections that can be enabled or disabled with a boolean, e.g.: `outline: false`,
+/// or configured with a map of their values
+fn deserialize_bool_or<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+  D: Deserializer<'de>,
+  T: Default + From<bool> + Deserialize<'de>,
+{
+  struct BoolOrVisitor<T>(PhantomData<T>);
+
+  impl<'de, T> Visitor<'de> for BoolOrVisitor<T>
+  where
+    T: Default + From<bool> + Deserialize<'de>,
+  {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a boolean or a section")
+    }
+
+    fn visit_bool<E: serde::de::Error>(self, enabled: bool) -> Result<Self::Value, E> {
+      Ok(T::from(enabled))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+      T::deserialize(MapAccessDeserializer::new(map))
+    }
+  }
+
+  deserializer.deserialize_any(BoolOrVisitor(PhantomData))
+}
+
+/// Chapters accept file names (`- intro.md`) or entries with
+/// `file`, `part`, `folder`, `chapters` and `draft`
+fn deserialize_chapters<'de, D: Deserializer<'de>>(
+  deserializer: D,
+) -> Result<Option<Vec<ChapterEntry>>, D::Error> {
+  struct ChapterItem(ChapterEntry);
+
+  impl<'de> Deserialize<'de> for ChapterItem {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+      struct ChapterVisitor;
+
+      impl<'de> Visitor<'de> for ChapterVisitor {
+        type Value = ChapterEntry;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+          formatter.write_str("a file name or a chapter with file, part, folder or chapters")
+        }
+
+        fn visit_str<E: serde::de::Error>(self, file: &str) -> Result<Self::Value, E> {
+          Ok(ChapterEntry {
+            file: Some(file.to_string()),
+            ..ChapterEntry::default()
+          })
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+          ChapterEntry::deserialize(MapAccessDeserializer::new(map))
+        }
+      }
+
+      deserializer
+        .deserialize_any(ChapterVisitor)
+        .map(ChapterItem)
+    }
+  }
+
+  let items: Option<Vec<ChapterItem>> = Option::deserialize(deserializer)?;
+  Ok(items.map(|items| items.into_iter().map(|item| item.0).collect()))
+}
+
+/// Header and footer accept a template (`header: "{title}"`) or a section
+/// with `template`, `height` and `font_size`. Empty templates are ignored
+fn deserialize_header_footer<'de, D: Deserializer<'de>>(
+  deserializer: D,
+) -> Result<Option<HeaderFooterConfig>, D::Error> {
+  struct HeaderFooterVisitor;
+
+  impl<'de> Visitor<'de> for HeaderFooterVisitor {
+    type Value = Option<HeaderFooterConfig>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a template or a section with template, height and font_size")
+    }
+
+    fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+      Ok(None)
+    }
+
+    fn visit_str<E: serde::de::Error>(self, template: &str) -> Result<Self::Value, E> {
+      Ok(Some(HeaderFooterConfig {
+        template: template.to_string(),
+        ..HeaderFooterConfig::default()
+      }))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+      HeaderFooterConfig::deserialize(MapAccessDeserializer::new(map)).map(Some)
+    }
+  }
+
+  Ok(
+    deserializer
+      .deserialize_any(HeaderFooterVisitor)?
+      .filter(|section| !section.template.trim().is_empty()),
+  )
+}
+
+/// Length converted to millimeters.
+///
+/// Accepts numbers (millimeters) or texts with a unit: `mm`, `cm`, `in` or `pt`,
+/// e.g.: `8.5in`
+#[derive(Default, PartialEq, Debug, Clone, Copy)]
+struct Length(f64);
+
+impl Length {
+  fn parse(value: &str) -> Option<Self> {
+    let captures = REG_LENGTH.captures(value.trim())?;
+    let number: f64 = captures["n"].parse().ok()?;
+    let unit = captures.name("u").map(|u| u.as_str()).unwrap_or("mm");
+    Some(Self(number * get_unit_in_mm(unit)?))
+  }
+}
+
+/// Size of a unit in millimeters
+fn get_unit_in_mm(unit: &str) -> Option<f64> {
+  match unit {
+    "mm" => Some(1.0),
+    "cm" => Some(10.0),
+    "in" => Some(25.4),
+    "pt" => Some(25.4 / 72.0),
+    _ => None,
+  }
+}
+
+impl<'de> Deserialize<'de> for Length {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    struct LengthVisitor;
+
+    impl<'de> Visitor<'de> for LengthVisitor {
+      type Value = Length;
+
+      fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a length in mm, or with a unit: 10mm, 1cm, 0.5in, 12pt")
+      }
+
+      fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        Ok(Length(value))
+      }
+
+      fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        Ok(Length(value as f64))
+      }
+
+      fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(Length(value as f64))
+      }
+
+      fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Length::parse(value)
+          .ok_or_else(|| E::invalid_value(serde::de::Unexpected::Str(value), &self))
+      }
+    }
+
+    deserializer.deserialize_any(LengthVisitor)
+  }
+}
+
+fn deserialize_length<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+  Ok(Length::deserialize(deserializer)?.0)
+}
+
+/// Position values (left, top, right, bottom) as written in the config file
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Position {
+  left: Length,
+  top: Length,
+  right: Length,
+  bottom: Length,
+}
+
+/// Left, top, right and bottom values in mm
+#[derive(Default, PartialEq, Debug, Clone, Deserialize)]
+#[serde(from = "Position")]
 pub struct PositionValues(f64, f64, f64, f64);
 
 impl PositionValues {
@@ -168,7 +886,20 @@ impl PositionValues {
   }
 }
 
-#[derive(Default, PartialEq, Debug, Clone)]
+impl From<Position> for PositionValues {
+  fn from(position: Position) -> Self {
+    Self(
+      position.left.0,
+      position.top.0,
+      position.right.0,
+      position.bottom.0,
+    )
+  }
+}
+
+/// Width and height in mm, written as a list: `[210, 297]` or `[6in, 9in]`
+#[derive(Default, PartialEq, Debug, Clone, Deserialize)]
+#[serde(from = "(Length, Length)")]
 pub struct Dimensions(f64, f64);
 
 impl Dimensions {
@@ -177,22 +908,731 @@ impl Dimensions {
   }
 }
 
-#[derive(Default, PartialEq, Debug, Clone)]
+impl From<(Length, Length)> for Dimensions {
+  fn from((width, height): (Length, Length)) -> Self {
+    Self(width.0, height.0)
+  }
+}
+
+/// Paper size of the document
+#[derive(PartialEq, Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub enum PaperSize {
+  A4,
+  A5,
+  B5,
+  Letter,
+  Legal,
+  /// Width and height, e.g.: `6x9in`
+  Size(Dimensions),
+  /// Use `document.dimensions`
+  Custom,
+}
+
+impl PaperSize {
+  /// Width and height in mm, `None` for [PaperSize::Custom]
+  pub fn get_dimensions(&self) -> Option<Dimensions> {
+    match self {
+      PaperSize::A4 => Some(Dimensions(210.0, 297.0)),
+      PaperSize::A5 => Some(Dimensions(148.0, 210.0)),
+      PaperSize::B5 => Some(Dimensions(176.0, 250.0)),
+      PaperSize::Letter => Some(Dimensions(215.9, 279.4)),
+      PaperSize::Legal => Some(Dimensions(215.9, 355.6)),
+      PaperSize::Size(dimensions) => Some(dimensions.clone()),
+      PaperSize::Custom => None,
+    }
+  }
+}
+
+impl TryFrom<String> for PaperSize {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    let size = match value.trim().to_lowercase().as_str() {
+      "a4" => PaperSize::A4,
+      "a5" => PaperSize::A5,
+      "b5" => PaperSize::B5,
+      "letter" => PaperSize::Letter,
+      "legal" => PaperSize::Legal,
+      "custom" => PaperSize::Custom,
+      size => {
+        let captures = REG_PAPER_SIZE.captures(size).ok_or_else(|| {
+          format!(
+            "unknown paper size {}, expected A4, A5, B5, Letter, Legal, custom or a size like 6x9in",
+            value
+          )
+        })?;
+        let unit = get_unit_in_mm(&captures["u"]).unwrap_or(1.0);
+        let length = |key: &str| captures[key].parse::<f64>().unwrap_or_default() * unit;
+        PaperSize::Size(Dimensions(length("w"), length("h")))
+      }
+    };
+    Ok(size)
+  }
+}
+
+/// Page orientation
+#[derive(Default, PartialEq, Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+  #[default]
+  Portrait,
+  Landscape,
+}
+
+/// Entry of `chapters`: a markdown file, or a part grouping chapters
+#[derive(Default, PartialEq, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ChapterEntry {
+  /// Markdown file, relative to `content/` or to the folder of the part
+  pub file: Option<String>,
+
+  /// Title of the part
+  pub part: Option<String>,
+
+  /// Folder of the part, relative to `content/`.
+  /// Without `chapters`, its markdown files are included sorted by path
+  pub folder: Option<String>,
+
+  /// Chapters of the part, in reading order
+  #[serde(deserialize_with = "deserialize_chapters")]
+  pub chapters: Option<Vec<ChapterEntry>>,
+
+  /// Exclude the file or the part from builds
+  pub draft: bool,
+}
+
+/// Content of the cover page
+#[derive(Default, PartialEq, Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoverMode {
+  /// Full page image of `assets/images`
+  #[default]
+  Image,
+  /// Title page built from an HTML template with the title, subtitle and authors
+  Template,
+  /// No cover page
+  None,
+}
+
+#[derive(Default, PartialEq, Debug, Clone, Deserialize)]
 pub struct CoverConfig {
-  /// Cover image filename
+  /// Cover page content
+  #[serde(default)]
+  pub mode: CoverMode,
+
+  /// Cover image filename, used by the image mode
+  #[serde(default, rename = "file")]
   pub filename: String,
 
-  /// Cover dimension in inches
+  /// HTML template of the template mode, relative to the project folder.
+  /// Supported placeholders: `{title}`, `{subtitle}` and `{authors}`
+  #[serde(default, deserialize_with = "deserialize_optional_string")]
+  pub template: Option<String>,
+
+  /// Subtitle displayed by the template mode
+  #[serde(default, deserialize_with = "deserialize_optional_string")]
+  pub subtitle: Option<String>,
+
+  /// Back cover image filename of `assets/images`, added after the last page
+  #[serde(default, deserialize_with = "deserialize_optional_string")]
+  pub back: Option<String>,
+
+  /// Cover dimension in mm
+  #[serde(default, rename = "dimensions")]
   pub dimension: Dimensions,
 
-  /// Position of the cover
+  /// Offsets of the cover from the page edges
+  #[serde(default)]
   pub position: PositionValues,
 }
 
-#[derive(Default, PartialEq, Debug, Clone)]
+#[derive(Default, PartialEq, Debug, Clone, Deserialize)]
+#[serde(default)]
 pub struct DocumentConfig {
-  /// Document dimensions
+  /// Paper size. When not defined, `dimensions` is used if present, A4 otherwise
+  pub size: Option<PaperSize>,
+  /// Page orientation
+  pub orientation: Orientation,
+  /// Document dimensions, used by the `custom` paper size
   pub dimensions: Dimensions,
   /// Document margins
   pub margins: PositionValues,
 }
+
+impl DocumentConfig {
+  /// Paper size, falling back to `dimensions` or A4 when not defined
+  pub fn get_size(&self) -> PaperSize {
+    match &self.size {
+      Some(size) => size.clone(),
+      None if self.dimensions != Dimensions::default() => PaperSize::Custom,
+      None => PaperSize::A4,
+    }
+  }
+
+  /// Paper width and height in mm, ignoring the orientation
+  pub fn get_paper_dimensions(&self) -> Dimensions {
+    self
+      .get_size()
+      .get_dimensions()
+      .unwrap_or_else(|| self.dimensions.clone())
+  }
+
+  /// Page width and height in mm, following the orientation
+  pub fn get_page_dimensions(&self) -> Dimensions {
+    let (width, height) = self.get_paper_dimensions().get_values();
+    match self.orientation {
+      Orientation::Portrait => Dimensions(width, height),
+      Orientation::Landscape => Dimensions(height, width),
+    }
+  }
+}
+
+#[derive(PartialEq, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TocConfig {
+  /// Add a table of contents page after the cover.
+  /// Ignored when the content places it manually with `@toc`
+  pub enabled: bool,
+  /// Title displayed on top of the table of contents
+  pub title: String,
+  /// Deepest heading level included (1 for h1, 2 for h2...)
+  pub depth: u8,
+}
+
+impl Default for TocConfig {
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      title: "Table of contents".to_string(),
+      depth: 3,
+    }
+  }
+}
+
+#[derive(PartialEq, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct OutlineConfig {
+  /// Add bookmarks to the generated PDF
+  pub enabled: bool,
+  /// Deepest heading level included (1 for h1, 2 for h2...)
+  pub depth: u8,
+}
+
+impl Default for OutlineConfig {
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      depth: 3,
+    }
+  }
+}
+
+impl From<bool> for OutlineConfig {
+  fn from(enabled: bool) -> Self {
+    Self {
+      enabled,
+      ..Self::default()
+    }
+  }
+}
+
+#[derive(PartialEq, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NumberingConfig {
+  /// Number the chapters and sections, e.g.: `3.2.1`
+  pub enabled: bool,
+  /// Deepest heading level numbered (1 for h1, 2 for h2...)
+  pub depth: u8,
+  /// Number displayed in chapter headings (h1), `{n}` is replaced by the number,
+  /// e.g.: `Chapter {n}`
+  pub chapter_label: String,
+  /// Chapters not numbered, relative to the content folder, e.g.: `preface.md`
+  pub exclude: Vec<String>,
+}
+
+impl Default for NumberingConfig {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      depth: 3,
+      chapter_label: "{n}".to_string(),
+      exclude: Vec::new(),
+    }
+  }
+}
+
+impl From<bool> for NumberingConfig {
+  fn from(enabled: bool) -> Self {
+    Self {
+      enabled,
+      ..Self::default()
+    }
+  }
+}
+
+#[derive(PartialEq, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CaptionsConfig {
+  /// Number the captions of figures (image titles) and tables (`Table: ...` paragraphs)
+  pub enabled: bool,
+  /// Number displayed in figure captions, `{n}` is replaced by the number
+  pub figure_label: String,
+  /// Number displayed in table captions, `{n}` is replaced by the number
+  pub table_label: String,
+  /// Title of a list of figures page, added after the table of contents
+  #[serde(deserialize_with = "deserialize_optional_string")]
+  pub list_of_figures: Option<String>,
+  /// Title of a list of tables page, added after the table of contents
+  #[serde(deserialize_with = "deserialize_optional_string")]
+  pub list_of_tables: Option<String>,
+}
+
+impl Default for CaptionsConfig {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      figure_label: "Figure {n}".to_string(),
+      table_label: "Table {n}".to_string(),
+      list_of_figures: None,
+      list_of_tables: None,
+    }
+  }
+}
+
+impl From<bool> for CaptionsConfig {
+  fn from(enabled: bool) -> Self {
+    Self {
+      enabled,
+      ..Self::default()
+    }
+  }
+}
+
+#[derive(PartialEq, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HighlightConfig {
+  /// Highlight fenced code blocks while building
+  pub enabled: bool,
+  /// Number the lines of code blocks
+  pub line_numbers: bool,
+  /// Color scheme of each theme, a css file of `themes/css` without extension.
+  /// Themes without a scheme keep the stylesheet linked by the theme
+  pub schemes: HashMap<String, String>,
+}
+
+impl Default for HighlightConfig {
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      line_numbers: false,
+      schemes: HashMap::new(),
+    }
+  }
+}
+
+impl From<bool> for HighlightConfig {
+  fn from(enabled: bool) -> Self {
+    Self {
+      enabled,
+      ..Self::default()
+    }
+  }
+}
+
+impl HighlightConfig {
+  /// Color scheme of a theme, if any
+  pub fn get_scheme(&self, theme: &str) -> Option<&str> {
+    self.schemes.get(theme).map(|scheme| scheme.as_str())
+  }
+}
+
+#[derive(PartialEq, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DiagramsConfig {
+  /// Render fenced diagram blocks to SVG
+  pub enabled: bool,
+  /// mermaid.js script rendering ```` ```mermaid ```` blocks, relative to the project folder
+  #[serde(deserialize_with = "deserialize_optional_string")]
+  pub mermaid: Option<String>,
+  /// viz.js (Graphviz) script rendering ```` ```dot ```` blocks, relative to the project folder
+  #[serde(deserialize_with = "deserialize_optional_string")]
+  pub dot: Option<String>,
+}
+
+impl Default for DiagramsConfig {
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      mermaid: None,
+      dot: None,
+    }
+  }
+}
+
+impl From<bool> for DiagramsConfig {
+  fn from(enabled: bool) -> Self {
+    Self {
+      enabled,
+      ..Self::default()
+    }
+  }
+}
+
+impl DiagramsConfig {
+  /// Script rendering a diagram language, if any
+  pub fn get_script(&self, kind: DiagramKind) -> Option<&str> {
+    match kind {
+      DiagramKind::Mermaid => self.mermaid.as_deref(),
+      DiagramKind::Dot => self.dot.as_deref(),
+    }
+  }
+}
+
+#[derive(PartialEq, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HeaderFooterConfig {
+  /// HTML template. Supported placeholders:
+  /// `{title}`, `{chapter}`, `{page}`, `{pages}` and `{date}`
+  pub template: String,
+  /// Space reserved for the header/footer in mm
+  #[serde(deserialize_with = "deserialize_length")]
+  pub height: f64,
+  /// Font size in px
+  pub font_size: f64,
+}
+
+impl Default for HeaderFooterConfig {
+  fn default() -> Self {
+    Self {
+      template: Default::default(),
+      height: 15.0,
+      font_size: 9.0,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_utils::create_files;
+
+  fn parse(source: &str) -> Result<(Config, Vec<ConfigIssue>), WordsmithError> {
+    Config::from_yaml(source, "ws.yaml")
+  }
+
+  fn get_issues(source: &str) -> Vec<ConfigIssue> {
+    match parse(source) {
+      Err(WordsmithError::InvalidConfig { issues, .. }) => issues,
+      result => panic!("Expected invalid config, got {:?}", result),
+    }
+  }
+
+  #[test]
+  fn test_from_yaml_stub() {
+    let (config, warnings) = parse(include_str!("../stubs/ws.yaml")).unwrap();
+
+    assert!(warnings.is_empty());
+    assert_eq!(config.title, "Sample");
+    assert_eq!(config.cover.filename, "cover.jpg");
+    assert_eq!(config.document.get_size(), PaperSize::A4);
+    assert_eq!(
+      config.document.get_page_dimensions().get_values(),
+      (210.0, 297.0)
+    );
+    assert_eq!(
+      config.document.margins.get_values(),
+      (10.0, 16.0, 10.0, 10.0)
+    );
+    assert_eq!(
+      config.keywords,
+      Some(vec!["sample".to_string(), "wordsmith".to_string()])
+    );
+    assert_eq!(config.header.unwrap().template, "{title}");
+  }
+
+  #[test]
+  fn test_from_yaml_short_forms() {
+    let (config, _) = parse(
+      "cover:\n  file: cover.jpg\nauthors: Name\nkeywords: \"a, b\"\noutline: false\nfooter: \"{page}\"\nsubject: \"  \"\n",
+    )
+    .unwrap();
+
+    assert_eq!(config.title, "Default title");
+    assert_eq!(config.authors, Some(vec!["Name".to_string()]));
+    assert_eq!(
+      config.keywords,
+      Some(vec!["a".to_string(), "b".to_string()])
+    );
+    assert!(!config.outline.enabled);
+    assert!(!config.numbering.enabled);
+    assert_eq!(config.footer.unwrap().height, 15.0);
+    assert_eq!(config.subject, None);
+  }
+
+  #[test]
+  fn test_from_yaml_captions() {
+    let (config, _) = parse("cover:\n  file: cover.jpg\n").unwrap();
+    assert!(!config.captions.enabled);
+    let (config, _) = parse("cover:\n  file: cover.jpg\ncaptions: true\n").unwrap();
+    assert!(config.captions.enabled);
+
+    let (config, _) = parse(
+      "cover:\n  file: cover.jpg\ncaptions:\n  enabled: true\n  figure_label: Fig. {n}\n  list_of_figures: Figures\n",
+    )
+    .unwrap();
+    assert!(config.captions.enabled);
+    assert_eq!(config.captions.figure_label, "Fig. {n}");
+    assert_eq!(config.captions.table_label, "Table {n}");
+    assert_eq!(config.captions.list_of_figures.as_deref(), Some("Figures"));
+    assert_eq!(config.captions.list_of_tables, None);
+  }
+
+  #[test]
+  fn test_from_yaml_highlight() {
+    let (config, _) = parse(
+      "cover:\n  file: cover.jpg\nhighlight:\n  line_numbers: true\n  schemes:\n    dark: monokai\n",
+    )
+    .unwrap();
+
+    assert!(config.highlight.enabled);
+    assert!(config.highlight.line_numbers);
+    assert_eq!(config.highlight.get_scheme("dark"), Some("monokai"));
+    assert_eq!(config.highlight.get_scheme("light"), None);
+
+    let (config, _) = parse("cover:\n  file: cover.jpg\nhighlight: false\n").unwrap();
+    assert!(!config.highlight.enabled);
+
+    let issues = get_issues("cover:\n  file: cover.jpg\nhighlight:\n  schemes:\n    dark: ../x\n");
+    assert_eq!(issues[0].path, "highlight.schemes.dark");
+    assert_eq!(issues[0].line, Some(5));
+  }
+
+  #[test]
+  fn test_from_yaml_without_cover() {
+    let (config, _) = parse("title: Sample\noutline: false\n").unwrap();
+
+    assert_eq!(config.cover.mode, CoverMode::None);
+    assert!(!config.outline.enabled);
+    assert_eq!(config.outline.depth, 3);
+  }
+
+  #[test]
+  fn test_from_yaml_cover() {
+    let project = create_files(&[("assets/images/back.jpg", ""), ("themes/cover.html", "")]);
+    let file = project.path().join("ws.yaml").display().to_string();
+    let (config, _) = Config::from_yaml(
+      "cover:\n  mode: template\n  template: themes/cover.html\n  subtitle: A subtitle\n  back: back.jpg\n  position:\n    left: 1cm\n    top: 0\n    right: 0\n    bottom: 0\n",
+      &file,
+    )
+    .unwrap();
+
+    assert_eq!(config.cover.mode, CoverMode::Template);
+    assert_eq!(config.cover.subtitle.as_deref(), Some("A subtitle"));
+    assert_eq!(config.cover.back.as_deref(), Some("back.jpg"));
+    assert_eq!(config.cover.position.get_values(), (10.0, 0.0, 0.0, 0.0));
+
+    let issues = get_issues("cover:\n  mode: image\n");
+    assert_eq!(issues[0].path, "cover.file");
+
+    let issues = get_issues("cover:\n  mode: template\n  template: cover.html\n  back: back.jpg\n");
+    assert_eq!(issues[0].path, "cover.template");
+    assert_eq!(issues[0].message, "cover.html not found");
+    assert_eq!(issues[1].path, "cover.back");
+    assert_eq!(issues[1].message, "assets/images/back.jpg not found");
+  }
+
+  #[test]
+  fn test_from_yaml_diagrams() {
+    let (config, _) =
+      parse("cover:\n  file: cover.jpg\ndiagrams:\n  mermaid: assets/js/mermaid.min.js\n").unwrap();
+
+    assert!(config.diagrams.enabled);
+    assert_eq!(
+      config.diagrams.get_script(DiagramKind::Mermaid),
+      Some("assets/js/mermaid.min.js")
+    );
+    assert_eq!(config.diagrams.get_script(DiagramKind::Dot), None);
+
+    let (config, _) = parse("cover:\n  file: cover.jpg\ndiagrams: false\n").unwrap();
+    assert!(!config.diagrams.enabled);
+  }
+
+  #[test]
+  fn test_from_yaml_chapters() {
+    let (config, warnings) = parse(
+      "cover:\n  file: cover.jpg\nchapters:\n  - intro.md\n  - part: Basics\n    folder: basics\n    chapters:\n      - setup.md\n      - file: notes.md\n        draft: true\n",
+    )
+    .unwrap();
+
+    assert!(warnings.is_empty());
+    let chapters = config.chapters.unwrap();
+    assert_eq!(chapters[0].file.as_deref(), Some("intro.md"));
+    assert_eq!(chapters[1].part.as_deref(), Some("Basics"));
+    assert_eq!(chapters[1].folder.as_deref(), Some("basics"));
+    let part_chapters = chapters[1].chapters.as_ref().unwrap();
+    assert_eq!(part_chapters[0].file.as_deref(), Some("setup.md"));
+    assert!(part_chapters[1].draft);
+
+    let issues = get_issues(
+      "cover:\n  file: cover.jpg\nchapters:\n  - file: a.md\n    folder: a\n  - part: Empty\n",
+    );
+    assert_eq!(issues[0].path, "chapters[0]");
+    assert_eq!(issues[0].line, Some(4));
+    assert_eq!(
+      issues[1].message,
+      "set a file, or the folder or chapters of a part"
+    );
+  }
+
+  #[test]
+  fn test_from_yaml_numbering() {
+    let (config, _) = parse("cover:\n  file: cover.jpg\nnumbering: true\n").unwrap();
+    assert!(config.numbering.enabled);
+    assert_eq!(config.numbering.depth, 3);
+
+    let (config, _) = parse(
+      "cover:\n  file: cover.jpg\nnumbering:\n  enabled: true\n  chapter_label: Chapter {n}\n  exclude: [preface.md]\n",
+    )
+    .unwrap();
+    assert_eq!(config.numbering.chapter_label, "Chapter {n}");
+    assert_eq!(config.numbering.exclude, vec!["preface.md".to_string()]);
+
+    let issues = get_issues("cover:\n  file: cover.jpg\nnumbering:\n  depth: 7\n");
+    assert_eq!(issues[0].path, "numbering.depth");
+  }
+
+  #[test]
+  fn test_from_yaml_components() {
+    let (config, _) =
+      parse("cover:\n  file: cover.jpg\ncomponents:\n  tip: '<aside>{{ content }}</aside>'\n")
+        .unwrap();
+
+    assert_eq!(
+      config.components.get("tip").map(|t| t.as_str()),
+      Some("<aside>{{ content }}</aside>")
+    );
+
+    let issues = get_issues("cover:\n  file: cover.jpg\ncomponents:\n  toc: x\n  Tip: x\n");
+    let paths: Vec<&str> = issues.iter().map(|issue| issue.path.as_str()).collect();
+    assert_eq!(paths, ["components.Tip", "components.toc"]);
+  }
+
+  #[test]
+  fn test_from_yaml_paper_size() {
+    let (config, _) = parse(
+      "cover:\n  file: cover.jpg\ndocument:\n  size: 150x230mm\n  orientation: landscape\n  margins:\n    left: 1cm\n    top: 72pt\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+      config.document.get_paper_dimensions().get_values(),
+      (150.0, 230.0)
+    );
+    assert_eq!(
+      config.document.get_page_dimensions().get_values(),
+      (230.0, 150.0)
+    );
+    assert_eq!(config.document.margins.get_values(), (10.0, 25.4, 0.0, 0.0));
+  }
+
+  #[test]
+  fn test_from_yaml_custom_size() {
+    let (config, _) =
+      parse("cover:\n  file: cover.jpg\ndocument:\n  dimensions: [100, 8in]\n").unwrap();
+    assert_eq!(config.document.get_size(), PaperSize::Custom);
+    assert_eq!(
+      config.document.get_page_dimensions().get_values(),
+      (100.0, 203.2)
+    );
+
+    let issues = get_issues("cover:\n  file: cover.jpg\ndocument:\n  size: custom\n");
+    assert_eq!(issues[0].path, "document.dimensions");
+
+    let issues = get_issues("cover:\n  file: cover.jpg\ndocument:\n  size: A3\n");
+    assert_eq!(issues[0].path, "document.size");
+    assert_eq!(issues[0].line, Some(4));
+
+    let issues = get_issues("cover:\n  file: cover.jpg\ndocument:\n  margins:\n    left: 1ft\n");
+    assert_eq!(issues[0].path, "document.margins.left");
+  }
+
+  #[test]
+  fn test_from_yaml_invalid_value() {
+    let issues = get_issues("cover:\n  file: cover.jpg\n  dimensions: [210.0]\n");
+
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].path, "cover.dimensions");
+    assert_eq!(issues[0].line, Some(3));
+    assert!(issues[0].message.starts_with("invalid length 1"));
+  }
+
+  #[test]
+  fn test_from_yaml_reports_every_problem() {
+    let issues =
+      get_issues("cover:\n  file: \"\"\ndocument:\n  margins:\n    left: -1\ntoc:\n  depth: 9\n");
+
+    let issues: Vec<(&str, Option<usize>)> = issues
+      .iter()
+      .map(|issue| (issue.path.as_str(), issue.line))
+      .collect();
+    assert_eq!(
+      issues,
+      [
+        ("document.margins.left", Some(5)),
+        ("cover.file", Some(2)),
+        ("toc.depth", Some(7)),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_from_yaml_reports_every_invalid_value() {
+    let issues = get_issues(
+      "cover:\n  file: cover.jpg\ndocument:\n  margins:\n    left: 1ft\ntoc:\n  depth: high\nmath: maybe\nnumbering:\n  depth: 9\n",
+    );
+
+    let issues: Vec<(&str, Option<usize>)> = issues
+      .iter()
+      .map(|issue| (issue.path.as_str(), issue.line))
+      .collect();
+    assert_eq!(
+      issues,
+      [
+        ("document.margins.left", Some(5)),
+        ("toc.depth", Some(7)),
+        ("math", Some(8)),
+        ("numbering.depth", Some(10)),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_from_yaml_unknown_keys() {
+    let (_, warnings) =
+      parse("cover:\n  file: cover.jpg\ndocument:\n  margins:\n    lef: 1\ntitel: Book\n").unwrap();
+
+    assert_eq!(
+      warnings,
+      [
+        ConfigIssue {
+          path: "document.margins.lef".to_string(),
+          line: Some(5),
+          message: "unknown key, ignored".to_string(),
+        },
+        ConfigIssue {
+          path: "titel".to_string(),
+          line: Some(6),
+          message: "unknown key, ignored".to_string(),
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_from_yaml_syntax_error() {
+    let error = parse("title: [Book\n").unwrap_err();
+
+    assert!(matches!(error, WordsmithError::ConfigSyntax { .. }));
+  }
+}
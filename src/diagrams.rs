@@ -0,0 +1,325 @@
+use std::{
+  collections::HashMap,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+
+use crate::{
+  config::DiagramsConfig,
+  utils::{escape_html, get_hash},
+};
+
+lazy_static! {
+    /// Match diagram placeholders, alone in a paragraph or inline
+    ///
+    /// Expect the capture naming groups:
+    /// - b: for the index of a diagram alone in a paragraph
+    /// - i: for the index of an inline diagram, e.g.: in a list item
+    static ref REG_DIAGRAM_PLACEHOLDER: Regex = Regex::new(r"<p>\x{FFF9}d(?P<b>\d+)\x{FFFB}</p>|\x{FFF9}d(?P<i>\d+)\x{FFFB}").unwrap();
+}
+
+/// Languages of the fenced blocks rendered as diagrams
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagramKind {
+  /// ```` ```mermaid ````, rendered by mermaid.js
+  Mermaid,
+  /// ```` ```dot ```` or ```` ```graphviz ````, rendered by viz.js
+  Dot,
+}
+
+impl DiagramKind {
+  pub fn from_lang(lang: &str) -> Option<Self> {
+    match lang {
+      "mermaid" => Some(Self::Mermaid),
+      "dot" | "graphviz" => Some(Self::Dot),
+      _ => None,
+    }
+  }
+
+  pub fn get_name(&self) -> &'static str {
+    match self {
+      Self::Mermaid => "mermaid",
+      Self::Dot => "dot",
+    }
+  }
+}
+
+/// A fenced diagram block
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagram {
+  pub kind: DiagramKind,
+  pub source: String,
+}
+
+impl Diagram {
+  /// Cached SVG, or the source in an element rendered by the browser
+  /// before printing (see [get_render_script])
+  pub fn to_html(&self, cache: &DiagramCache) -> String {
+    let key = cache.get_key(self);
+    match cache.get(&key) {
+      Some(svg) => format!(
+        r#"<div class="diagram diagram-{}">{}</div>"#,
+        self.kind.get_name(),
+        svg
+      ),
+      None => format!(
+        r#"<div class="diagram diagram-{name}" data-diagram="{name}" data-diagram-key="{}"><pre><code>{}</code></pre></div>"#,
+        key,
+        escape_html(&self.source),
+        name = self.kind.get_name()
+      ),
+    }
+  }
+}
+
+/// SVG of the diagrams rendered by previous builds, in `.ws-cache/diagrams`
+#[derive(Debug, Clone)]
+pub struct DiagramCache {
+  path: PathBuf,
+  /// Hash of the script rendering each language, empty without script
+  renderers: HashMap<&'static str, String>,
+}
+
+impl DiagramCache {
+  /// Cache of a project folder, diagrams are rendered again when their script changes
+  pub fn new(project_path: &Path, config: &DiagramsConfig) -> Self {
+    let renderers = [DiagramKind::Mermaid, DiagramKind::Dot]
+      .into_iter()
+      .map(|kind| {
+        let hash = config
+          .get_script(kind)
+          .and_then(|script| fs::read(project_path.join(script)).ok())
+          .map(|script| get_hash(&script))
+          .unwrap_or_default();
+        (kind.get_name(), hash)
+      })
+      .collect();
+    Self {
+      path: project_path.join(".ws-cache/diagrams"),
+      renderers,
+    }
+  }
+
+  /// Key of the rendered SVG, a hash of the language, its script and the source
+  pub fn get_key(&self, diagram: &Diagram) -> String {
+    let name = diagram.kind.get_name();
+    let renderer = self.renderers.get(name).map_or("", |hash| hash.as_str());
+    get_hash(format!("{}\n{}\n{}", name, renderer, diagram.source).as_bytes())
+  }
+
+  fn get_file(&self, key: &str) -> PathBuf {
+    self.path.join(format!("{}.svg", key))
+  }
+
+  pub fn get(&self, key: &str) -> Option<String> {
+    fs::read_to_string(self.get_file(key)).ok()
+  }
+
+  pub fn insert(&self, key: &str, svg: &str) -> Result<()> {
+    fs::create_dir_all(&self.path)?;
+    fs::write(self.get_file(key), svg)?;
+    Ok(())
+  }
+}
+
+/// Replace the fenced blocks of diagrams with placeholders, so markdown does not
+/// render them as code. Only languages with a script configured are diagrams
+pub fn extract_diagrams(markdown: &str, config: &DiagramsConfig) -> (String, Vec<Diagram>) {
+  let mut output = String::new();
+  let mut diagrams = Vec::new();
+  // Open fence: indentation, marker, length and the diagram being read
+  let mut fence: Option<(usize, char, usize, Option<Diagram>)> = None;
+
+  for line in markdown.split_inclusive('\n') {
+    let content = line.trim_start_matches(' ');
+    let indentation = line.len() - content.len();
+    let marker = content
+      .chars()
+      .next()
+      .filter(|c| (*c == '`' || *c == '~') && indentation < 4);
+    let length = marker.map_or(0, |m| content.chars().take_while(|c| *c == m).count());
+
+    match (&mut fence, marker) {
+      (Some((_, open, open_length, diagram)), Some(marker))
+        if marker == *open && length >= *open_length && content.trim_end().len() == length =>
+      {
+        match diagram.take() {
+          Some(diagram) => {
+            output.push_str(&format!(
+              "\n{}\u{FFF9}d{}\u{FFFB}\n\n",
+              &line[..indentation],
+              diagrams.len()
+            ));
+            diagrams.push(diagram);
+          }
+          None => output.push_str(line),
+        }
+        fence = None;
+      }
+      (Some((open_indentation, _, _, Some(diagram))), _) => {
+        let skip = line.len() - line.trim_start_matches(' ').len();
+        diagram
+          .source
+          .push_str(&line[skip.min(*open_indentation)..]);
+      }
+      (Some(_), _) => output.push_str(line),
+      (None, Some(marker)) if length >= 3 => {
+        let lang = content[length..].split_whitespace().next().unwrap_or("");
+        let diagram = DiagramKind::from_lang(lang)
+          .filter(|kind| config.enabled && config.get_script(*kind).is_some())
+          .map(|kind| Diagram {
+            kind,
+            source: String::new(),
+          });
+        if diagram.is_none() {
+          output.push_str(line);
+        }
+        fence = Some((indentation, marker, length, diagram));
+      }
+      (None, _) => output.push_str(line),
+    }
+  }
+
+  // A block never closed is kept as code, like markdown does
+  if let Some((_, open, length, Some(diagram))) = fence {
+    output.push_str(&format!(
+      "{}{}\n{}",
+      open.to_string().repeat(length),
+      diagram.kind.get_name(),
+      diagram.source
+    ));
+  }
+
+  (output, diagrams)
+}
+
+/// Replace the diagram placeholders of HTML with their SVG or source element
+pub fn render_diagrams(html: &str, diagrams: &[Diagram], cache: &DiagramCache) -> String {
+  REG_DIAGRAM_PLACEHOLDER
+    .replace_all(html, |m: &Captures| {
+      let index = m.name("b").or(m.name("i")).unwrap().as_str();
+      match index.parse::<usize>().ok().and_then(|i| diagrams.get(i)) {
+        Some(diagram) => diagram.to_html(cache),
+        None => m[0].to_string(),
+      }
+    })
+    .to_string()
+}
+
+/// Script rendering the `data-diagram` elements of a page to SVG, using the
+/// diagram libraries loaded by the page. The elements are replaced by their SVG.
+///
+/// Return a JSON object read by [RenderedDiagrams::from_json]
+pub fn get_render_script() -> &'static str {
+  r#"(async () => {
+  const renderers = {
+    mermaid: async (source, id) => (await mermaid.render(id, source)).svg,
+    dot: async (source) => (await Viz.instance()).renderString(source, { format: 'svg' }),
+  };
+  if (window.mermaid) mermaid.initialize({ startOnLoad: false });
+  const svgs = {};
+  const errors = [];
+  for (const element of document.querySelectorAll('[data-diagram]')) {
+    const { diagram, diagramKey } = element.dataset;
+    const source = element.textContent;
+    try {
+      const svg = svgs[diagramKey] || await renderers[diagram](source, `diagram-${diagramKey}`);
+      element.innerHTML = svg;
+      element.removeAttribute('data-diagram');
+      svgs[diagramKey] = svg;
+    } catch (error) {
+      errors.push(`${diagram} diagram "${source.trim().split('\n')[0]}": ${error.message || error}`);
+    }
+  }
+  return JSON.stringify({ svgs, errors });
+})()"#
+}
+
+/// Result of [get_render_script]
+#[derive(Debug, Default, Deserialize)]
+pub struct RenderedDiagrams {
+  /// SVG of the rendered diagrams, by key
+  pub svgs: HashMap<String, String>,
+  /// Diagrams that could not be rendered, with the library error
+  pub errors: Vec<String>,
+}
+
+impl RenderedDiagrams {
+  pub fn from_json(json: &str) -> Result<Self> {
+    Ok(serde_json::from_str(json)?)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_utils::create_files;
+
+  fn config() -> DiagramsConfig {
+    DiagramsConfig {
+      mermaid: Some("assets/js/mermaid.min.js".to_string()),
+      ..DiagramsConfig::default()
+    }
+  }
+
+  #[test]
+  fn test_extract_diagrams() {
+    let markdown = "Text\n```mermaid\ngraph TD\n  A --> B\n```\n````md\n```mermaid\nkept\n```\n````\n```dot\nkept\n```\n- item\n  ~~~mermaid\n  graph LR\n  ~~~\n";
+
+    let (markdown, diagrams) = extract_diagrams(markdown, &config());
+
+    assert_eq!(
+      markdown,
+      "Text\n\n\u{FFF9}d0\u{FFFB}\n\n````md\n```mermaid\nkept\n```\n````\n```dot\nkept\n```\n- item\n\n  \u{FFF9}d1\u{FFFB}\n\n"
+    );
+    assert_eq!(diagrams[0].source, "graph TD\n  A --> B\n");
+    assert_eq!(diagrams[1].source, "graph LR\n");
+  }
+
+  #[test]
+  fn test_render_diagrams() {
+    let cache = DiagramCache::new(Path::new("/tmp/wordsmith-missing-project"), &config());
+    let diagrams = vec![Diagram {
+      kind: DiagramKind::Dot,
+      source: "a -> b\n".to_string(),
+    }];
+
+    assert_eq!(
+      render_diagrams("<p>\u{FFF9}d0\u{FFFB}</p>", &diagrams, &cache),
+      format!(
+        r#"<div class="diagram diagram-dot" data-diagram="dot" data-diagram-key="{}"><pre><code>a -&gt; b
+</code></pre></div>"#,
+        cache.get_key(&diagrams[0])
+      )
+    );
+    assert_ne!(
+      cache.get_key(&diagrams[0]),
+      cache.get_key(&Diagram {
+        kind: DiagramKind::Mermaid,
+        source: "a -> b\n".to_string(),
+      })
+    );
+  }
+
+  #[test]
+  fn test_get_key_renderer() {
+    let project = create_files(&[("assets/js/mermaid.min.js", "v1")]);
+    let diagram = Diagram {
+      kind: DiagramKind::Mermaid,
+      source: "graph TD\n".to_string(),
+    };
+
+    let key = DiagramCache::new(project.path(), &config()).get_key(&diagram);
+    fs::write(project.path().join("assets/js/mermaid.min.js"), "v2").unwrap();
+
+    assert_ne!(
+      DiagramCache::new(project.path(), &config()).get_key(&diagram),
+      key
+    );
+  }
+}
@@ -1,12 +1,79 @@
+use std::fmt;
+
 use thiserror::Error;
 
+/// A problem found in the configuration file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+  /// Key path, e.g.: `document.margins.left`
+  pub path: String,
+  /// Line of the key in the configuration file
+  pub line: Option<usize>,
+  pub message: String,
+}
+
+impl fmt::Display for ConfigIssue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if !self.path.is_empty() {
+      write!(f, "`{}` ", self.path)?;
+    }
+    if let Some(line) = self.line {
+      write!(f, "(line {}) ", line)?;
+    }
+    write!(f, "{}", self.message)
+  }
+}
+
+/// One issue per line
+fn format_config_issues(issues: &[ConfigIssue]) -> String {
+  issues
+    .iter()
+    .map(|issue| format!("  - {}", issue))
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
+/// One item per line
+fn format_list(items: &[String]) -> String {
+  items
+    .iter()
+    .map(|item| format!("  - {}", item))
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
 #[derive(Error, Debug)]
 pub enum WordsmithError {
   #[error("Invalid tag {0}")]
   InvalidTag(String),
 
-  #[error("Invalid closing tag {0}, {1}")]
-  InvalidComponentClosingTag(String, String),
+  /// Represents an unclosed or mismatched block tag
+  #[error("Invalid tag in {file} at line {line}: {message}")]
+  TagSyntax {
+    file: String,
+    line: usize,
+    message: String,
+  },
+
+  /// Represents a file of `@include` that does not exist
+  #[error("File {path} included in {file} at line {line} not found")]
+  IncludeNotFound {
+    file: String,
+    line: usize,
+    path: String,
+  },
+
+  /// Represents files including themselves
+  #[error("Include cycle in {file} at line {line}: {}", .files.join(" -> "))]
+  IncludeCycle {
+    file: String,
+    line: usize,
+    files: Vec<String>,
+  },
+
+  /// Represents a component registered with a name that can not be used as a tag
+  #[error("Invalid component name {0}")]
+  InvalidComponentName(String),
 
   #[error("Project not found")]
   ProjectNotFound,
@@ -14,15 +81,62 @@ pub enum WordsmithError {
   #[error("Project already exists in {0} folder")]
   ProjectConflict(String),
 
-  #[error("Cover configuration is missing or invalid")]
-  ConfigCoverNotDefined,
+  /// Represents a configuration file that is not valid yaml
+  #[error("Invalid yaml in {file} at line {line}: {message}")]
+  ConfigSyntax {
+    file: String,
+    line: usize,
+    message: String,
+  },
+
+  /// Represents missing or invalid configuration values
+  #[error("Invalid configuration in {file}:\n{}", format_config_issues(.issues))]
+  InvalidConfig {
+    file: String,
+    issues: Vec<ConfigIssue>,
+  },
+
+  /// Represents files or folders of the chapters manifest that do not exist
+  #[error("Chapters listed in {file} not found:\n{}", format_list(.paths))]
+  ChaptersNotFound { file: String, paths: Vec<String> },
+
+  /// Represents parts declared inside another part of the chapters manifest
+  #[error("Nested parts in {file} are not supported:\n{}", format_list(.parts))]
+  NestedParts { file: String, parts: Vec<String> },
+
+  /// Represents chapters generating the same output file,
+  /// e.g.: `basics/setup.md` and `basics-setup.md`
+  #[error("Chapters listed in {file} use the same output file:\n{}", format_list(.paths))]
+  DuplicateChapters { file: String, paths: Vec<String> },
 
-  #[error("Cover configuration file is not defined or empty")]
-  ConfigCoverFileIsInvalid,
+  /// Represents labels defined twice and `@ref` tags to unknown labels
+  #[error("Invalid references:\n{}", format_list(.0))]
+  InvalidReferences(Vec<String>),
 
   #[error("Theme {0} not found")]
   ThemeNotFound(String),
 
+  #[error("Highlight scheme {0} not found")]
+  HighlightSchemeNotFound(String),
+
+  #[error("Cover {0} not found")]
+  CoverNotFound(String),
+
+  #[error("Diagram script {0} not found")]
+  DiagramScriptNotFound(String),
+
+  /// Represents formulas with unknown commands or unbalanced groups
+  #[error("Invalid formula in {file}: ${formula}$, {message}")]
+  InvalidFormula {
+    file: String,
+    formula: String,
+    message: String,
+  },
+
+  /// Represents diagram blocks the browser could not render
+  #[error("Invalid diagrams:\n{}", format_list(.0))]
+  InvalidDiagrams(Vec<String>),
+
   /// Represents all other cases of `std::io::Error`.
   #[error(transparent)]
   IOError(#[from] std::io::Error),
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::Deserialize;
+use serde_yaml::Value;
+
+use crate::{components::ComponentArg, error::WordsmithError};
+
+/// Line opening and closing the front matter
+const DELIMITER: &str = "---";
+
+/// Metadata at the top of a chapter, between `---` lines:
+///
+/// ```md
+/// ---
+/// title: Getting started
+/// class: intro
+/// toc: false
+/// ---
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct FrontMatter {
+  /// Title of the chapter in the table of contents, navigation and headers,
+  /// instead of the first heading
+  pub title: Option<String>,
+
+  /// Exclude the chapter from builds
+  pub draft: bool,
+
+  /// CSS classes added to the chapter
+  pub class: Option<String>,
+
+  /// Include the chapter headings in the table of contents
+  pub toc: bool,
+
+  /// Number the chapter headings, when numbering is enabled
+  pub numbered: bool,
+
+  /// Other values, available to components, e.g.: `{{ author }}`.
+  /// Keys are not checked: any unknown key, even misspelled, is a component argument
+  #[serde(flatten)]
+  pub values: HashMap<String, Value>,
+}
+
+impl Default for FrontMatter {
+  fn default() -> Self {
+    Self {
+      title: None,
+      draft: false,
+      class: None,
+      toc: true,
+      numbered: true,
+      values: HashMap::new(),
+    }
+  }
+}
+
+impl FrontMatter {
+  /// Split the front matter from a markdown source, `file` is used in error messages.
+  ///
+  /// The front matter is replaced by empty lines, so line numbers of the
+  /// content do not change. Sources without front matter are returned as they are.
+  /// Return [WordsmithError::ConfigSyntax] for invalid yaml or values
+  pub fn parse(source: &str, file: &str) -> Result<(Self, String)> {
+    let mut lines = source.split_inclusive('\n');
+    let mut length = match lines.next() {
+      Some(line) if line.trim_end() == DELIMITER => line.len(),
+      _ => return Ok((Self::default(), source.to_string())),
+    };
+
+    let mut yaml = String::new();
+    let mut count = 1;
+    let mut closed = false;
+    for line in lines {
+      length += line.len();
+      count += 1;
+      if line.trim_end() == DELIMITER {
+        closed = true;
+        break;
+      }
+      yaml.push_str(line);
+    }
+    if !closed {
+      return Err(
+        WordsmithError::ConfigSyntax {
+          file: file.to_string(),
+          line: 1,
+          message: format!("front matter is never closed, add {}", DELIMITER),
+        }
+        .into(),
+      );
+    }
+
+    let front_matter = match yaml.trim().is_empty() {
+      true => Self::default(),
+      false => serde_yaml::from_str(&yaml).map_err(|e| WordsmithError::ConfigSyntax {
+        file: file.to_string(),
+        // The first line is the delimiter
+        line: e.location().map(|l| l.line() + 1).unwrap_or(1),
+        message: e
+          .to_string()
+          .split(" at line ")
+          .next()
+          .unwrap_or("")
+          .to_string(),
+      })?,
+    };
+
+    Ok((
+      front_matter,
+      format!("{}{}", "\n".repeat(count), &source[length..]),
+    ))
+  }
+
+  /// Values available to components: `title`, `class` and other keys.
+  /// Maps are ignored
+  pub fn get_args(&self) -> HashMap<String, ComponentArg> {
+    let mut args: HashMap<String, ComponentArg> = self
+      .values
+      .iter()
+      .filter_map(|(key, value)| Some((key.to_string(), get_component_arg(value)?)))
+      .collect();
+    for (key, value) in [("title", &self.title), ("class", &self.class)] {
+      if let Some(value) = value {
+        args.insert(key.to_string(), ComponentArg::String(value.to_string()));
+      }
+    }
+    args
+  }
+}
+
+fn get_component_arg(value: &Value) -> Option<ComponentArg> {
+  match value {
+    Value::Bool(value) => Some(ComponentArg::Bool(*value)),
+    Value::Number(value) => value.as_f64().map(ComponentArg::Number),
+    Value::String(value) => Some(ComponentArg::String(value.to_string())),
+    Value::Sequence(values) => Some(ComponentArg::List(
+      values.iter().filter_map(get_component_arg).collect(),
+    )),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    let (front_matter, content) = FrontMatter::parse(
+      "---\ntitle: Setup\ntoc: false\nauthor: Ana\ntags: [a, 1]\n---\n\n# Heading\n",
+      "content/a.md",
+    )
+    .unwrap();
+
+    assert_eq!(front_matter.title.as_deref(), Some("Setup"));
+    assert!(!front_matter.toc);
+    assert!(front_matter.numbered);
+    assert_eq!(content, "\n\n\n\n\n\n\n# Heading\n");
+    let args = front_matter.get_args();
+    assert_eq!(
+      args.get("author"),
+      Some(&ComponentArg::String("Ana".to_string()))
+    );
+    assert_eq!(args.get("tags").unwrap().to_string(), "a 1");
+    assert_eq!(args.get("title").unwrap().to_string(), "Setup");
+  }
+
+  #[test]
+  fn test_parse_without_front_matter() {
+    let source = "# Heading\n---\n";
+
+    let (front_matter, content) = FrontMatter::parse(source, "content/a.md").unwrap();
+
+    assert_eq!(front_matter, FrontMatter::default());
+    assert_eq!(content, source);
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    assert_eq!(
+      FrontMatter::parse("---\ntitle: a\n\ndraft: maybe\n---\n", "content/a.md")
+        .unwrap_err()
+        .to_string(),
+      "Invalid yaml in content/a.md at line 4: draft: invalid type: string \"maybe\", expected a boolean"
+    );
+    assert_eq!(
+      FrontMatter::parse("---\ntitle: a\n", "content/a.md")
+        .unwrap_err()
+        .to_string(),
+      "Invalid yaml in content/a.md at line 1: front matter is never closed, add ---"
+    );
+  }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use comrak::adapters::SyntaxHighlighterAdapter;
+use lazy_static::lazy_static;
+use syntect::{
+  parsing::{ParseState, Scope, ScopeStack, SyntaxSet},
+  util::LinesWithEndings,
+};
+
+use crate::utils::escape_html;
+
+lazy_static! {
+    /// Syntax definitions bundled with syntect, loaded once
+    static ref SYNTAX_SET: SyntaxSet = SyntaxSet::load_defaults_newlines();
+
+    /// Map syntect (TextMate) scopes to highlight.js classes, used by the theme stylesheets.
+    ///
+    /// More specific scopes must come first, e.g.: `string.regexp` before `string`
+    static ref SCOPE_CLASSES: Vec<(Scope, &'static str)> = [
+      ("comment", "comment"),
+      ("string.regexp", "regexp"),
+      ("string", "string"),
+      ("constant.numeric", "number"),
+      ("constant.character.escape", "subst"),
+      ("constant", "literal"),
+      ("keyword.operator", "operator"),
+      ("keyword", "keyword"),
+      ("storage.type", "type"),
+      ("storage", "keyword"),
+      ("entity.name.tag", "name"),
+      ("entity.name.section", "section"),
+      ("entity.name", "title"),
+      ("entity.other.attribute-name", "attr"),
+      ("entity.other.inherited-class", "title"),
+      ("support.function", "built_in"),
+      ("support.type", "type"),
+      ("support.class", "type"),
+      ("variable.parameter", "params"),
+      ("variable.language", "keyword"),
+      ("meta.preprocessor", "meta"),
+      ("meta.annotation", "meta"),
+      ("markup.heading", "section"),
+      ("markup.bold", "strong"),
+      ("markup.italic", "emphasis"),
+      ("markup.inserted", "addition"),
+      ("markup.deleted", "deletion"),
+      ("markup.underline.link", "link"),
+      ("markup.quote", "quote"),
+    ]
+    .iter()
+    .map(|(scope, class)| (Scope::new(scope).unwrap(), *class))
+    .collect();
+}
+
+/// Highlight fenced code blocks while building the document.
+///
+/// Tokens are wrapped in spans with highlight.js classes (`hljs-keyword`, `hljs-string`...),
+/// so the color scheme comes from the theme stylesheet.
+#[derive(Debug, Clone, Default)]
+pub struct Highlighter {
+  line_numbers: bool,
+}
+
+impl Highlighter {
+  pub fn new(line_numbers: bool) -> Self {
+    Self { line_numbers }
+  }
+
+  /// Class of the innermost scope with a highlight.js equivalent
+  fn get_class(stack: &ScopeStack) -> Option<&'static str> {
+    stack.as_slice().iter().rev().find_map(|scope| {
+      SCOPE_CLASSES
+        .iter()
+        .find(|(prefix, _)| prefix.is_prefix_of(*scope))
+        .map(|(_, class)| *class)
+    })
+  }
+
+  /// Append a piece of text, reusing the open span when the class is the same
+  fn push_text(
+    html: &mut String,
+    open: &mut Option<&'static str>,
+    class: Option<&'static str>,
+    text: &str,
+  ) {
+    if text.is_empty() {
+      return;
+    }
+    if *open != class {
+      if open.is_some() {
+        html.push_str("</span>");
+      }
+      if let Some(class) = class {
+        html.push_str(&format!(r#"<span class="hljs-{}">"#, class));
+      }
+      *open = class;
+    }
+    html.push_str(&escape_html(text));
+  }
+
+  /// Highlight a single line, without its line ending.
+  /// Spans are closed at the end of every line
+  fn highlight_line(state: &mut ParseState, stack: &mut ScopeStack, line: &str) -> Result<String> {
+    let ops = state.parse_line(line, &SYNTAX_SET)?;
+    let content = line.trim_end_matches(['\n', '\r']);
+
+    let mut html = String::new();
+    let mut open = None;
+    let mut start = 0;
+
+    for (index, op) in ops {
+      let end = index.min(content.len());
+      if end > start {
+        Self::push_text(
+          &mut html,
+          &mut open,
+          Self::get_class(stack),
+          &content[start..end],
+        );
+        start = end;
+      }
+      stack.apply(&op)?;
+    }
+    Self::push_text(
+      &mut html,
+      &mut open,
+      Self::get_class(stack),
+      &content[start..],
+    );
+
+    if open.is_some() {
+      html.push_str("</span>");
+    }
+
+    Ok(html)
+  }
+
+  /// Highlight a code block. Unknown languages are displayed as plain text
+  pub fn highlight_code(&self, lang: Option<&str>, code: &str) -> Result<String> {
+    let syntax = lang
+      .filter(|lang| !lang.is_empty())
+      .and_then(|lang| SYNTAX_SET.find_syntax_by_token(lang))
+      .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text());
+
+    let mut state = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+
+    let mut html = String::new();
+    for line in LinesWithEndings::from(code) {
+      let line_html = Self::highlight_line(&mut state, &mut stack, line)?;
+      if self.line_numbers {
+        html.push_str(&format!(r#"<span class="line">{}</span>"#, line_html));
+      } else {
+        html.push_str(&line_html);
+      }
+      if line.ends_with('\n') {
+        html.push('\n');
+      }
+    }
+
+    Ok(html)
+  }
+
+  /// Build an opening tag, attributes are sorted to keep the output stable
+  fn build_tag(tag: &str, attributes: &HashMap<String, String>) -> String {
+    let mut attributes: Vec<_> = attributes.iter().collect();
+    attributes.sort();
+
+    let mut html = format!("<{}", tag);
+    for (name, value) in attributes {
+      html.push_str(&format!(r#" {}="{}""#, name, escape_html(value)));
+    }
+    html.push('>');
+    html
+  }
+}
+
+impl SyntaxHighlighterAdapter for Highlighter {
+  fn highlight(&self, lang: Option<&str>, code: &str) -> String {
+    match self.highlight_code(lang, code) {
+      Ok(html) => html,
+      Err(e) => {
+        log::warn!(
+          "Could not highlight {} code: {}",
+          lang.unwrap_or("plain"),
+          e
+        );
+        escape_html(code)
+      }
+    }
+  }
+
+  fn build_pre_tag(&self, attributes: &HashMap<String, String>) -> String {
+    Self::build_tag("pre", attributes)
+  }
+
+  fn build_code_tag(&self, attributes: &HashMap<String, String>) -> String {
+    let mut attributes = attributes.clone();
+    let class = attributes.entry("class".to_string()).or_default();
+    let classes = match self.line_numbers {
+      true => "hljs line-numbers",
+      false => "hljs",
+    };
+    *class = format!("{} {}", classes, class).trim().to_string();
+
+    Self::build_tag("code", &attributes)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_highlight_code() {
+    let html = Highlighter::default()
+      .highlight_code(Some("rust"), "// note\nreturn \"<b>\";\n")
+      .unwrap();
+
+    assert!(html.starts_with(r#"<span class="hljs-comment">// note</span>"#));
+    assert!(html.contains(r#"<span class="hljs-keyword">return</span>"#));
+    assert!(html.contains(r#"<span class="hljs-string">&quot;&lt;b&gt;&quot;</span>"#));
+    assert!(html.ends_with(";\n"));
+  }
+
+  #[test]
+  fn test_highlight_code_unknown_language() {
+    let html = Highlighter::new(true)
+      .highlight_code(Some("unknown"), "a < b\nc")
+      .unwrap();
+
+    assert_eq!(
+      html,
+      "<span class=\"line\">a &lt; b</span>\n<span class=\"line\">c</span>"
+    );
+  }
+
+  #[test]
+  fn test_build_code_tag() {
+    let attributes = HashMap::from([("class".to_string(), "language-rust".to_string())]);
+
+    assert_eq!(
+      Highlighter::new(true).build_code_tag(&attributes),
+      r#"<code class="hljs line-numbers language-rust">"#
+    );
+    assert_eq!(
+      Highlighter::default().build_code_tag(&HashMap::new()),
+      r#"<code class="hljs">"#
+    );
+  }
+}
@@ -1,15 +1,41 @@
 mod assets;
 mod builders;
+mod captions;
 mod cli;
 mod commands;
 mod components;
 mod config;
+mod diagrams;
 mod error;
+mod front_matter;
+mod highlight;
+mod manifest;
+mod math;
+mod references;
+#[cfg(test)]
+mod test_utils;
+mod toc;
+mod utils;
 
 pub use assets::Asset;
-pub use builders::{HtmlGen, PdfGen};
-pub use cli::{Cli, Commands};
-pub use commands::{Build, Init};
-pub use components::{Component, ComponentArg, Components, BREAK_PAGE_HTML};
-pub use config::{Config, CoverConfig, Dimensions, DocumentConfig, PositionValues};
-pub use error::WordsmithError;
+pub use builders::{Chapter, EpubGen, HtmlGen, PdfGen, SiteGen};
+pub use captions::{Caption, CaptionNumbers};
+pub use cli::{Cli, Commands, Format};
+pub use commands::{Build, Init, Serve, Watch};
+pub use components::{
+  Attributes, Component, ComponentArg, ComponentRegistry, Components, Context, TagKind,
+  BREAK_PAGE_HTML, BUILTIN_PRIORITY, PROJECT_PRIORITY,
+};
+pub use config::{
+  CaptionsConfig, ChapterEntry, Config, CoverConfig, CoverMode, DiagramsConfig, Dimensions,
+  DocumentConfig, HeaderFooterConfig, HighlightConfig, NumberingConfig, Orientation, OutlineConfig,
+  PaperSize, PositionValues, TocConfig,
+};
+pub use diagrams::{Diagram, DiagramCache, DiagramKind};
+pub use error::{ConfigIssue, WordsmithError};
+pub use front_matter::FrontMatter;
+pub use highlight::Highlighter;
+pub use manifest::ContentFile;
+pub use math::Formula;
+pub use references::{Label, LabelKind, Reference, References};
+pub use toc::{HeadingNumbers, Toc, TocEntry};
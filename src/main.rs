@@ -1,7 +1,7 @@
 use anyhow::Result;
 use clap::Parser;
 use log::Level;
-use wordsmith::{Build, Cli, Commands, Init};
+use wordsmith::{Build, Cli, Commands, Init, Serve, Watch};
 
 fn main() -> Result<()> {
   // Parse cli commands
@@ -23,10 +23,22 @@ fn main() -> Result<()> {
       log::debug!("Init command triggered");
       Init::new(current_path, folder).execute()?;
     }
-    Commands::Build { theme, output } => {
+    Commands::Build {
+      theme,
+      output,
+      format,
+    } => {
       log::debug!("Build command triggered");
-      log::debug!("Building args: {:?}, {:?}", theme, output);
-      Build::new(current_path, theme).execute()?;
+      log::debug!("Building args: {:?}, {:?}, {:?}", theme, output, format);
+      Build::new(current_path, theme, format).execute()?;
+    }
+    Commands::Serve { theme, port } => {
+      log::debug!("Serve command triggered");
+      Serve::new(current_path, theme, port).execute()?;
+    }
+    Commands::Watch { theme } => {
+      log::debug!("Watch command triggered");
+      Watch::new(current_path, theme).execute()?;
     }
   };
 
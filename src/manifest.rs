@@ -0,0 +1,408 @@
+use std::{
+  fs::{read_dir, read_to_string},
+  path::Path,
+};
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::{config::ChapterEntry, error::WordsmithError};
+
+lazy_static! {
+    /// Match chapter links of `SUMMARY.md`, e.g.: `- [Introduction](intro.md)`
+    ///
+    /// Expect the capture naming groups:
+    /// - p: for the file path, empty for drafts
+    static ref REG_SUMMARY_LINK: Regex = Regex::new(r"^\s*[-*+]\s+\[[^\]]*\]\((?P<p>[^)]*)\)").unwrap();
+
+    /// Match headings of `SUMMARY.md`, used as part titles, e.g.: `# Part I`
+    ///
+    /// Expect the capture naming groups:
+    /// - l: for the heading marks, e.g.: `##`
+    /// - t: for the heading text
+    static ref REG_SUMMARY_HEADING: Regex = Regex::new(r"^(?P<l>#{1,6})\s+(?P<t>.*?)\s*#*\s*$").unwrap();
+}
+
+/// Name of the manifest file in the content folder
+pub const SUMMARY_FILE: &str = "SUMMARY.md";
+
+/// A markdown file of the content folder, in reading order
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentFile {
+  /// Path relative to the content folder, e.g.: `basics/setup.md`
+  pub file: String,
+  /// Title of the part containing the file
+  pub part: Option<String>,
+}
+
+impl ContentFile {
+  fn new(file: String, part: Option<&str>) -> Self {
+    Self {
+      file,
+      part: part.map(|part| part.to_string()),
+    }
+  }
+}
+
+/// Source file without extension, folders are joined with `-`,
+/// e.g.: `001-introduction`, `basics-setup`
+pub fn get_file_stem(file: &str) -> String {
+  file.strip_suffix(".md").unwrap_or(file).replace('/', "-")
+}
+
+/// Content files in reading order, from the first source found:
+///
+/// 1. `chapters` defined in `ws.yaml`
+/// 2. `content/SUMMARY.md`, a list of links with headings as part titles
+/// 3. markdown files of the content folder, sorted by name (subfolders are ignored)
+///
+/// Drafts are excluded. Return [WordsmithError::ChaptersNotFound] with every
+/// listed file or folder that does not exist, [WordsmithError::NestedParts] with every
+/// part declared inside another one and [WordsmithError::DuplicateChapters] with
+/// the files generating the same output file, see [get_file_stem]
+pub fn get_content_files(
+  content: &Path,
+  chapters: Option<&[ChapterEntry]>,
+) -> Result<Vec<ContentFile>> {
+  let mut files = Vec::new();
+  let mut missing = Vec::new();
+  let mut nested = Vec::new();
+
+  let manifest = match chapters {
+    Some(chapters) => {
+      add_entries(
+        content,
+        chapters,
+        "",
+        None,
+        &mut files,
+        &mut missing,
+        &mut nested,
+      )?;
+      "ws.yaml"
+    }
+    None if content.join(SUMMARY_FILE).is_file() => {
+      let summary = read_to_string(content.join(SUMMARY_FILE))?;
+      let (summary_files, summary_nested) = parse_summary(&summary);
+      for file in summary_files {
+        match content.join(&file.file).is_file() {
+          true => files.push(file),
+          false => missing.push(format!("content/{}", file.file)),
+        }
+      }
+      nested = summary_nested;
+      "content/SUMMARY.md"
+    }
+    None => {
+      let files = get_markdown_files(content, false)?
+        .into_iter()
+        .map(|file| ContentFile::new(file, None))
+        .collect();
+      return Ok(files);
+    }
+  };
+
+  let file = manifest.to_string();
+  if !missing.is_empty() {
+    return Err(
+      WordsmithError::ChaptersNotFound {
+        file,
+        paths: missing,
+      }
+      .into(),
+    );
+  }
+  if !nested.is_empty() {
+    return Err(
+      WordsmithError::NestedParts {
+        file,
+        parts: nested,
+      }
+      .into(),
+    );
+  }
+
+  let duplicates = get_duplicate_files(&files);
+  if !duplicates.is_empty() {
+    return Err(
+      WordsmithError::DuplicateChapters {
+        file,
+        paths: duplicates,
+      }
+      .into(),
+    );
+  }
+
+  Ok(files)
+}
+
+/// Files generating the same output file, e.g.: `basics/setup.md, basics-setup.md`
+fn get_duplicate_files(files: &[ContentFile]) -> Vec<String> {
+  let mut stems: Vec<(String, Vec<&str>)> = Vec::new();
+  for file in files {
+    let stem = get_file_stem(&file.file);
+    match stems.iter_mut().find(|(s, _)| *s == stem) {
+      Some((_, paths)) => paths.push(&file.file),
+      None => stems.push((stem, vec![&file.file])),
+    }
+  }
+
+  stems
+    .into_iter()
+    .filter(|(_, paths)| paths.len() > 1)
+    .map(|(_, paths)| paths.join(", "))
+    .collect()
+}
+
+/// Resolve `chapters` entries, paths are relative to `folder` inside the content folder.
+/// Files that do not exist are added to `missing`, parts inside another part to `nested`
+fn add_entries(
+  content: &Path,
+  entries: &[ChapterEntry],
+  folder: &str,
+  part: Option<&str>,
+  files: &mut Vec<ContentFile>,
+  missing: &mut Vec<String>,
+  nested: &mut Vec<String>,
+) -> Result<()> {
+  for entry in entries.iter().filter(|entry| !entry.draft) {
+    if let Some(file) = &entry.file {
+      let file = join_path(folder, file);
+      match content.join(&file).is_file() {
+        true => files.push(ContentFile::new(file, part)),
+        false => missing.push(format!("content/{}", file)),
+      }
+      continue;
+    }
+
+    if let (Some(inner), Some(outer)) = (&entry.part, part) {
+      nested.push(format!("{} inside {}", inner, outer));
+    }
+    let part = entry.part.as_deref().or(part);
+    let folder = match &entry.folder {
+      Some(path) => join_path(folder, path),
+      None => folder.to_string(),
+    };
+
+    match &entry.chapters {
+      Some(chapters) => add_entries(content, chapters, &folder, part, files, missing, nested)?,
+      None if content.join(&folder).is_dir() => files.extend(
+        get_markdown_files(&content.join(&folder), true)?
+          .into_iter()
+          .map(|file| ContentFile::new(join_path(&folder, &file), part)),
+      ),
+      None => missing.push(format!("content/{}/", folder)),
+    }
+  }
+
+  Ok(())
+}
+
+fn join_path(folder: &str, path: &str) -> String {
+  let path = path.trim().trim_matches('/');
+  match folder.is_empty() {
+    true => path.to_string(),
+    false => format!("{}/{}", folder, path),
+  }
+}
+
+/// Markdown files of a folder sorted by path, relative to the folder.
+/// `SUMMARY.md` is not a chapter
+fn get_markdown_files(folder: &Path, recursive: bool) -> Result<Vec<String>> {
+  let mut entries: Vec<_> = read_dir(folder)?.collect::<Result<_, _>>()?;
+  entries.sort_by_key(|entry| entry.path());
+
+  let mut files = Vec::new();
+  for entry in entries {
+    let path = entry.path();
+    let name = entry.file_name().to_string_lossy().to_string();
+
+    if path.is_dir() {
+      if recursive {
+        files.extend(
+          get_markdown_files(&path, true)?
+            .into_iter()
+            .map(|file| format!("{}/{}", name, file)),
+        );
+      }
+      continue;
+    }
+    if path.extension().and_then(|e| e.to_str()) != Some("md") || name == SUMMARY_FILE {
+      continue;
+    }
+
+    files.push(name);
+  }
+
+  Ok(files)
+}
+
+/// Files linked by a `SUMMARY.md` source, in order, and the nested parts.
+///
+/// The first heading is the title of the summary, next ones start a part.
+/// Headings deeper than the current part would be nested parts, which are not supported.
+/// Links without a path are drafts, e.g.: `- [Next chapter]()`
+fn parse_summary(source: &str) -> (Vec<ContentFile>, Vec<String>) {
+  let mut files = Vec::new();
+  let mut nested = Vec::new();
+  let mut part: Option<(usize, String)> = None;
+  let mut has_title = false;
+
+  for line in source.lines() {
+    if let Some(captures) = REG_SUMMARY_HEADING.captures(line) {
+      let (level, title) = (captures["l"].len(), captures["t"].to_string());
+      match (&part, has_title || !files.is_empty()) {
+        (Some((outer_level, outer)), _) if level > *outer_level => {
+          nested.push(format!("{} inside {}", title, outer))
+        }
+        (_, true) => part = Some((level, title)),
+        (_, false) => has_title = true,
+      }
+      continue;
+    }
+    if let Some(captures) = REG_SUMMARY_LINK.captures(line) {
+      let file = captures["p"].trim();
+      if !file.is_empty() {
+        let part = part.as_ref().map(|(_, title)| title.as_str());
+        files.push(ContentFile::new(join_path("", file), part));
+      }
+    }
+  }
+
+  (files, nested)
+}
+
+#[cfg(test)]
+mod tests {
+  use tempfile::TempDir;
+
+  use super::*;
+  use crate::test_utils::create_files;
+
+  fn get_files(files: Vec<ContentFile>) -> Vec<(String, Option<String>)> {
+    files
+      .into_iter()
+      .map(|file| (file.file, file.part))
+      .collect()
+  }
+
+  fn create_content() -> TempDir {
+    create_files(&[
+      ("b.md", "text"),
+      ("a.md", "text"),
+      ("image.png", "text"),
+      ("part/2.md", "text"),
+      ("part/1.md", "text"),
+      ("part/sub/3.md", "text"),
+    ])
+  }
+
+  #[test]
+  fn test_get_content_files_sorted() {
+    let content = create_content();
+
+    let files = get_content_files(content.path(), None).unwrap();
+
+    assert_eq!(
+      get_files(files),
+      vec![("a.md".to_string(), None), ("b.md".to_string(), None)]
+    );
+  }
+
+  #[test]
+  fn test_get_content_files_chapters() {
+    let content = create_content();
+    let (config, _) = crate::Config::from_yaml(
+      "cover:\n  file: c.jpg\nchapters:\n  - b.md\n  - file: a.md\n    draft: true\n  - part: Part\n    folder: part\n  - folder: part\n    chapters: [missing.md, sub/3.md]\n  - folder: none\n",
+      "ws.yaml",
+    )
+    .unwrap();
+
+    let result = get_content_files(content.path(), config.chapters.as_deref());
+    let entries = &config.chapters.unwrap()[..3];
+    let files = get_content_files(content.path(), Some(entries)).unwrap();
+
+    assert_eq!(
+      result.unwrap_err().to_string(),
+      "Chapters listed in ws.yaml not found:\n  - content/part/missing.md\n  - content/none/"
+    );
+    let part = Some("Part".to_string());
+    assert_eq!(
+      get_files(files),
+      vec![
+        ("b.md".to_string(), None),
+        ("part/1.md".to_string(), part.clone()),
+        ("part/2.md".to_string(), part.clone()),
+        ("part/sub/3.md".to_string(), part),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_get_content_files_duplicates() {
+    let content = create_files(&[
+      ("basics/setup.md", "text"),
+      ("basics-setup.md", "text"),
+      ("intro.md", "text"),
+    ]);
+    let (config, _) = crate::Config::from_yaml(
+      "cover:\n  file: c.jpg\nchapters:\n  - intro.md\n  - basics/setup.md\n  - basics-setup.md\n",
+      "ws.yaml",
+    )
+    .unwrap();
+
+    let error = get_content_files(content.path(), config.chapters.as_deref()).unwrap_err();
+
+    assert_eq!(
+      error.to_string(),
+      "Chapters listed in ws.yaml use the same output file:\n  - basics/setup.md, basics-setup.md"
+    );
+  }
+
+  #[test]
+  fn test_get_content_files_nested_parts() {
+    let content = create_content();
+    let (config, _) = crate::Config::from_yaml(
+      "cover:\n  file: c.jpg\nchapters:\n  - part: Part\n    folder: part\n    chapters:\n      - part: Sub\n        folder: sub\n",
+      "ws.yaml",
+    )
+    .unwrap();
+
+    let error = get_content_files(content.path(), config.chapters.as_deref()).unwrap_err();
+
+    assert_eq!(
+      error.to_string(),
+      "Nested parts in ws.yaml are not supported:\n  - Sub inside Part"
+    );
+  }
+
+  #[test]
+  fn test_parse_summary_nested_parts() {
+    let (files, nested) =
+      parse_summary("# Summary\n\n# Part I\n\n## Basics\n\n- [Setup](setup.md)\n\n# Part II\n");
+
+    assert_eq!(
+      get_files(files),
+      vec![("setup.md".to_string(), Some("Part I".to_string()))]
+    );
+    assert_eq!(nested, ["Basics inside Part I"]);
+  }
+
+  #[test]
+  fn test_parse_summary() {
+    let (files, nested) = parse_summary(
+      "# Summary\n\n- [Intro](intro.md)\n  - [Setup](intro/setup.md)\n- [Draft]()\n\n# Part II\n\n* [End](end.md)\n",
+    );
+
+    assert_eq!(
+      get_files(files),
+      vec![
+        ("intro.md".to_string(), None),
+        ("intro/setup.md".to_string(), None),
+        ("end.md".to_string(), Some("Part II".to_string())),
+      ]
+    );
+    assert!(nested.is_empty());
+  }
+}
@@ -0,0 +1,1100 @@
+use anyhow::Result;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::{components::IndentedCode, error::WordsmithError, utils::escape_html};
+
+lazy_static! {
+    /// Match formula placeholders, alone in a paragraph (display formulas) or inline
+    ///
+    /// Expect the capture naming groups:
+    /// - b: for the index of a formula alone in a paragraph
+    /// - i: for the index of an inline formula
+    static ref REG_MATH_PLACEHOLDER: Regex = Regex::new(r"<p>\x{FFF9}(?P<b>\d+)\x{FFFB}</p>|\x{FFF9}(?P<i>\d+)\x{FFFB}").unwrap();
+}
+
+/// Characters around the index of a formula, kept as they are by markdown
+const PLACEHOLDER_START: char = '\u{FFF9}';
+const PLACEHOLDER_END: char = '\u{FFFB}';
+
+/// A LaTeX formula, `$...$` (inline) or `$$...$$` (display)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+  pub tex: String,
+  pub display: bool,
+}
+
+impl Formula {
+  /// Typeset the formula as MathML, printed by browsers without scripts or fonts to download.
+  /// The LaTeX source is kept as an annotation.
+  ///
+  /// Return the message of the first unknown command or unbalanced group as error
+  pub fn to_mathml(&self) -> Result<String, String> {
+    let mut parser = TexParser::new(&self.tex);
+    let items = parser.parse();
+    if let Some(error) = parser.error {
+      return Err(error);
+    }
+    Ok(format!(
+      r#"<math xmlns="http://www.w3.org/1998/Math/MathML" class="math" display="{}"><semantics>{}<annotation encoding="application/x-tex">{}</annotation></semantics></math>"#,
+      match self.display {
+        true => "block",
+        false => "inline",
+      },
+      mrow(items),
+      escape_html(self.tex.trim())
+    ))
+  }
+}
+
+/// Replace the formulas of a markdown source with placeholders, so markdown does not
+/// change them. Formulas inside code (fenced and indented code blocks, code spans)
+/// are kept, `\$` is a dollar sign.
+///
+/// An inline formula starts with `$` followed by a non-space character, and ends with
+/// `$` after a non-space character and not followed by a digit, so prices like
+/// `$5 and $10` are not formulas
+pub fn extract_math(markdown: &str) -> (String, Vec<Formula>) {
+  let mut output = String::new();
+  let mut formulas = Vec::new();
+  let mut text = String::new();
+  let mut fence: Option<(char, usize)> = None;
+  let mut indented_code = IndentedCode::new();
+
+  for line in markdown.split_inclusive('\n') {
+    let trimmed = line.trim_start();
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+    let length = marker.map_or(0, |m| trimmed.chars().take_while(|c| *c == m).count());
+
+    match (fence, marker) {
+      (Some((open, open_length)), Some(marker))
+        if marker == open && length >= open_length && trimmed.trim_end().len() == length =>
+      {
+        indented_code.is_code(line, true);
+        fence = None;
+        output.push_str(line);
+      }
+      (Some(_), _) => {
+        indented_code.is_code(line, true);
+        output.push_str(line);
+      }
+      (None, Some(marker)) if length >= 3 => {
+        indented_code.is_code(line, true);
+        output.push_str(&replace_formulas(&text, &mut formulas));
+        text.clear();
+        fence = Some((marker, length));
+        output.push_str(line);
+      }
+      (None, _) if indented_code.is_code(line, false) => {
+        output.push_str(&replace_formulas(&text, &mut formulas));
+        text.clear();
+        output.push_str(line);
+      }
+      (None, _) => text.push_str(line),
+    }
+  }
+  output.push_str(&replace_formulas(&text, &mut formulas));
+
+  (output, formulas)
+}
+
+/// Replace the formula placeholders of HTML with MathML.
+/// Display formulas alone in a paragraph replace the paragraph.
+///
+/// Return [WordsmithError::InvalidFormula] with the file of the first formula that can not be typeset
+pub fn render_math(html: &str, formulas: &[Formula], file: &str) -> Result<String> {
+  let mut output = String::new();
+  let mut last = 0;
+  for m in REG_MATH_PLACEHOLDER.captures_iter(html) {
+    let all = m.get(0).unwrap();
+    output.push_str(&html[last..all.start()]);
+    last = all.end();
+
+    let index = m.name("b").or(m.name("i")).unwrap().as_str();
+    match index.parse::<usize>().ok().and_then(|i| formulas.get(i)) {
+      Some(formula) => {
+        let mathml = formula
+          .to_mathml()
+          .map_err(|message| WordsmithError::InvalidFormula {
+            file: file.to_string(),
+            formula: formula.tex.trim().to_string(),
+            message,
+          })?;
+        output.push_str(&mathml);
+      }
+      None => output.push_str(all.as_str()),
+    }
+  }
+  output.push_str(&html[last..]);
+  Ok(output)
+}
+
+/// Replace the formulas of text without code blocks, code spans are skipped
+fn replace_formulas(text: &str, formulas: &mut Vec<Formula>) -> String {
+  let bytes = text.as_bytes();
+  let mut output = String::new();
+  let mut last = 0;
+  let mut index = 0;
+
+  let mut push_formula = |output: &mut String, tex: &str, display: bool| {
+    output.push_str(&format!(
+      "{}{}{}",
+      PLACEHOLDER_START,
+      formulas.len(),
+      PLACEHOLDER_END
+    ));
+    formulas.push(Formula {
+      tex: tex.to_string(),
+      display,
+    });
+  };
+
+  while index < bytes.len() {
+    match bytes[index] {
+      b'\\' => index += 2,
+      b'`' => {
+        let run = bytes[index..].iter().take_while(|b| **b == b'`').count();
+        let closing = "`".repeat(run);
+        index = match text[index + run..].find(&closing) {
+          Some(end) => index + run + end + run,
+          None => index + run,
+        };
+      }
+      b'$' if bytes.get(index + 1) == Some(&b'$') => match find_unescaped(text, index + 2, "$$") {
+        Some(end) => {
+          output.push_str(&text[last..index]);
+          push_formula(&mut output, &text[index + 2..end], true);
+          index = end + 2;
+          last = index;
+        }
+        None => index += 2,
+      },
+      b'$' => match find_inline_end(text, index + 1) {
+        Some(end) => {
+          output.push_str(&text[last..index]);
+          push_formula(&mut output, &text[index + 1..end], false);
+          index = end + 1;
+          last = index;
+        }
+        None => index += 1,
+      },
+      _ => index += 1,
+    }
+  }
+
+  output.push_str(&text[last.min(text.len())..]);
+  output
+}
+
+/// Position of `pattern` after `start`, skipping escaped characters
+fn find_unescaped(text: &str, start: usize, pattern: &str) -> Option<usize> {
+  let bytes = text.as_bytes();
+  let mut index = start;
+  while index < bytes.len() {
+    if bytes[index] == b'\\' {
+      index += 2;
+    } else if bytes[index..].starts_with(pattern.as_bytes()) {
+      return Some(index);
+    } else {
+      index += 1;
+    }
+  }
+  None
+}
+
+/// Position of the `$` closing an inline formula, in the same paragraph and before any code span
+fn find_inline_end(text: &str, start: usize) -> Option<usize> {
+  let bytes = text.as_bytes();
+  if bytes.get(start).is_none_or(|b| b.is_ascii_whitespace()) {
+    return None;
+  }
+
+  let mut index = start;
+  while index < bytes.len() {
+    match bytes[index] {
+      b'\\' => index += 2,
+      b'`' => return None,
+      b'\n'
+        if text[index + 1..]
+          .trim_start_matches([' ', '\t'])
+          .starts_with('\n') =>
+      {
+        return None
+      }
+      b'$' => {
+        let closes = !bytes[index - 1].is_ascii_whitespace()
+          && !bytes.get(index + 1).is_some_and(|b| b.is_ascii_digit());
+        return closes.then_some(index);
+      }
+      _ => index += 1,
+    }
+  }
+  None
+}
+
+/// Wrap elements in an `<mrow>`, unless there is a single element
+fn mrow(items: Vec<String>) -> String {
+  match items.len() {
+    1 => items.into_iter().next().unwrap_or_default(),
+    _ => format!("<mrow>{}</mrow>", items.concat()),
+  }
+}
+
+fn mo(value: &str) -> String {
+  format!("<mo>{}</mo>", escape_html(value))
+}
+
+/// Element of a formula, `limits` places scripts under and over, e.g.: `\sum`
+struct Atom {
+  html: String,
+  limits: bool,
+}
+
+impl Atom {
+  fn new(html: String) -> Self {
+    Self {
+      html,
+      limits: false,
+    }
+  }
+}
+
+/// Convert LaTeX math to MathML elements.
+///
+/// Supports the common commands: fractions, roots, scripts, greek letters, symbols,
+/// functions, accents, fonts (`\mathbb`, `\mathbf`), `\text`, `\left ... \right`
+/// and matrix environments. Parsing stops at the first unknown command or
+/// unbalanced group, see [TexParser::error]
+struct TexParser {
+  chars: Vec<char>,
+  position: usize,
+  /// First error of the formula, e.g.: `unknown command \foo`
+  error: Option<String>,
+}
+
+impl TexParser {
+  fn new(tex: &str) -> Self {
+    Self {
+      chars: tex.chars().collect(),
+      position: 0,
+      error: None,
+    }
+  }
+
+  /// Keep the first error, the next elements are not parsed
+  fn fail(&mut self, message: String) {
+    if self.error.is_none() {
+      self.error = Some(message);
+    }
+  }
+
+  /// Element at the current position for error messages, e.g.: `\right` or `}`
+  fn describe_position(&self) -> String {
+    match (self.peek_command(), self.peek()) {
+      (Some(name), _) => format!("\\{}", name),
+      (None, Some(c)) => c.to_string(),
+      (None, None) => "the end of the formula".to_string(),
+    }
+  }
+
+  fn peek(&self) -> Option<char> {
+    self.chars.get(self.position).copied()
+  }
+
+  fn skip_whitespace(&mut self) {
+    while self.peek().is_some_and(|c| c.is_whitespace()) {
+      self.position += 1;
+    }
+  }
+
+  /// Name of the command at the current position, without consuming it
+  fn peek_command(&self) -> Option<String> {
+    if self.peek() != Some('\\') {
+      return None;
+    }
+    let name: String = self.chars[self.position + 1..]
+      .iter()
+      .take_while(|c| c.is_ascii_alphabetic())
+      .collect();
+    match name.is_empty() {
+      true => self.chars.get(self.position + 1).map(|c| c.to_string()),
+      false => Some(name),
+    }
+  }
+
+  fn read_command(&mut self) -> String {
+    let name = self.peek_command().unwrap_or_default();
+    self.position += 1 + name.chars().count();
+    name
+  }
+
+  /// Text of a `{...}` group, or the next character
+  fn read_group_text(&mut self) -> String {
+    self.skip_whitespace();
+    if self.peek() != Some('{') {
+      return self.next_char().map(|c| c.to_string()).unwrap_or_default();
+    }
+    self.position += 1;
+    let mut depth = 0;
+    let mut text = String::new();
+    while let Some(c) = self.next_char() {
+      match c {
+        '{' => depth += 1,
+        '}' if depth == 0 => break,
+        '}' => depth -= 1,
+        _ => {}
+      }
+      text.push(c);
+    }
+    text
+  }
+
+  fn next_char(&mut self) -> Option<char> {
+    let c = self.peek();
+    self.position += 1;
+    c
+  }
+
+  /// Parse the whole formula, stray closing braces, separators and `\right` are errors
+  fn parse(&mut self) -> Vec<String> {
+    let items = self.parse_row();
+    if self.error.is_none() && self.peek().is_some() {
+      self.fail(format!("unexpected {}", self.describe_position()));
+    }
+    items
+  }
+
+  /// Parse elements until the end of a group, cell, row or `\right`
+  fn parse_row(&mut self) -> Vec<String> {
+    let mut items = Vec::new();
+    loop {
+      self.skip_whitespace();
+      match self.peek() {
+        _ if self.error.is_some() => return items,
+        None | Some('}') | Some('&') => return items,
+        Some('\\')
+          if matches!(
+            self.peek_command().as_deref(),
+            Some("\\" | "right" | "end" | "cr")
+          ) =>
+        {
+          return items
+        }
+        _ => {}
+      }
+      if let Some(atom) = self.parse_atom() {
+        items.push(self.parse_scripts(atom));
+      }
+    }
+  }
+
+  /// Argument of a command or script: a group, or a single element
+  fn parse_argument(&mut self) -> String {
+    self.skip_whitespace();
+    match self.peek() {
+      Some(c) if c.is_ascii_digit() => {
+        self.position += 1;
+        format!("<mn>{}</mn>", c)
+      }
+      Some(_) => self
+        .parse_atom()
+        .map(|atom| atom.html)
+        .unwrap_or_else(|| "<mrow></mrow>".to_string()),
+      None => "<mrow></mrow>".to_string(),
+    }
+  }
+
+  /// Superscripts, subscripts and primes following an element
+  fn parse_scripts(&mut self, atom: Atom) -> String {
+    let (mut sub, mut sup): (Option<String>, Vec<String>) = (None, Vec::new());
+    loop {
+      self.skip_whitespace();
+      match self.peek() {
+        Some('^') => {
+          self.position += 1;
+          sup.push(self.parse_argument());
+        }
+        Some('_') => {
+          self.position += 1;
+          sub = Some(self.parse_argument());
+        }
+        Some('\'') => {
+          self.position += 1;
+          sup.push(mo("′"));
+        }
+        _ => break,
+      }
+    }
+
+    let (under, over) = match atom.limits {
+      true => ("munder", "mover"),
+      false => ("msub", "msup"),
+    };
+    let sup = (!sup.is_empty()).then(|| mrow(sup));
+    match (sub, sup) {
+      (None, None) => atom.html,
+      (Some(sub), None) => format!("<{u}>{}{}</{u}>", atom.html, sub, u = under),
+      (None, Some(sup)) => format!("<{o}>{}{}</{o}>", atom.html, sup, o = over),
+      (Some(sub), Some(sup)) => {
+        let tag = match atom.limits {
+          true => "munderover",
+          false => "msubsup",
+        };
+        format!("<{t}>{}{}{}</{t}>", atom.html, sub, sup, t = tag)
+      }
+    }
+  }
+
+  fn parse_atom(&mut self) -> Option<Atom> {
+    let c = self.peek()?;
+    match c {
+      '{' => {
+        self.position += 1;
+        let items = self.parse_row();
+        match self.peek() {
+          Some('}') => self.position += 1,
+          _ => self.fail(format!("missing }} before {}", self.describe_position())),
+        }
+        Some(Atom::new(format!("<mrow>{}</mrow>", items.concat())))
+      }
+      '\\' => {
+        let name = self.read_command();
+        self.parse_command(&name)
+      }
+      '^' | '_' => Some(Atom::new("<mrow></mrow>".to_string())),
+      c if c.is_ascii_digit() || c == '.' => {
+        let number: String = self.chars[self.position..]
+          .iter()
+          .take_while(|c| c.is_ascii_digit() || **c == '.')
+          .collect();
+        self.position += number.chars().count();
+        Some(Atom::new(match number.as_str() {
+          "." => mo("."),
+          _ => format!("<mn>{}</mn>", number),
+        }))
+      }
+      c if c.is_alphabetic() => {
+        self.position += 1;
+        Some(Atom::new(format!("<mi>{}</mi>", c)))
+      }
+      '~' => {
+        self.position += 1;
+        Some(Atom::new(r#"<mspace width="0.25em"/>"#.to_string()))
+      }
+      _ => {
+        self.position += 1;
+        Some(Atom::new(match c {
+          '-' => mo("−"),
+          '*' => mo("∗"),
+          c => mo(&c.to_string()),
+        }))
+      }
+    }
+  }
+
+  fn parse_command(&mut self, name: &str) -> Option<Atom> {
+    if let Some(symbol) = get_symbol(name) {
+      return Some(Atom::new(symbol));
+    }
+    if let Some(letter) = get_greek_letter(name) {
+      let variant = match letter.chars().next().is_some_and(|c| c.is_uppercase()) {
+        true => r#" mathvariant="normal""#,
+        false => "",
+      };
+      return Some(Atom::new(format!("<mi{}>{}</mi>", variant, letter)));
+    }
+    if let Some(operator) = get_large_operator(name) {
+      return Some(Atom {
+        html: mo(operator),
+        limits: !matches!(name, "int" | "iint" | "iiint" | "oint"),
+      });
+    }
+    if FUNCTIONS.contains(&name) {
+      return Some(Atom::new(format!("<mi>{}</mi>", name)));
+    }
+    if LIMIT_FUNCTIONS.contains(&name) {
+      return Some(Atom {
+        html: format!(r#"<mo movablelimits="true" form="prefix">{}</mo>"#, name),
+        limits: true,
+      });
+    }
+    if let Some(accent) = get_accent(name) {
+      let base = self.parse_argument();
+      return Some(Atom::new(match name {
+        "underline" => format!(
+          r#"<munder accentunder="true">{}{}</munder>"#,
+          base,
+          mo(accent)
+        ),
+        _ => format!(r#"<mover accent="true">{}{}</mover>"#, base, mo(accent)),
+      }));
+    }
+    if let Some(width) = get_space(name) {
+      return Some(Atom::new(format!(r#"<mspace width="{}"/>"#, width)));
+    }
+
+    let atom = match name {
+      "frac" | "dfrac" | "tfrac" | "cfrac" => {
+        let (numerator, denominator) = (self.parse_argument(), self.parse_argument());
+        format!("<mfrac>{}{}</mfrac>", numerator, denominator)
+      }
+      "binom" => {
+        let (top, bottom) = (self.parse_argument(), self.parse_argument());
+        format!(
+          r#"<mrow>{}<mfrac linethickness="0">{}{}</mfrac>{}</mrow>"#,
+          mo("("),
+          top,
+          bottom,
+          mo(")")
+        )
+      }
+      "sqrt" => {
+        self.skip_whitespace();
+        let index = match self.peek() {
+          Some('[') => {
+            self.position += 1;
+            let mut index = String::new();
+            while let Some(c) = self.next_char().filter(|c| *c != ']') {
+              index.push(c);
+            }
+            let mut parser = TexParser::new(&index);
+            let items = parser.parse();
+            if let Some(error) = parser.error {
+              self.fail(error);
+            }
+            Some(mrow(items))
+          }
+          _ => None,
+        };
+        let base = self.parse_argument();
+        match index {
+          Some(index) => format!("<mroot>{}{}</mroot>", base, index),
+          None => format!("<msqrt>{}</msqrt>", base),
+        }
+      }
+      "text" | "textrm" | "textit" | "textbf" | "mbox" => {
+        format!("<mtext>{}</mtext>", escape_html(&self.read_group_text()))
+      }
+      "operatorname" => format!("<mi>{}</mi>", escape_html(&self.read_group_text())),
+      "mathrm" => format!(
+        r#"<mi mathvariant="normal">{}</mi>"#,
+        escape_html(&self.read_group_text())
+      ),
+      "mathbb" | "mathbf" | "boldsymbol" | "mathcal" => {
+        let text: String = self
+          .read_group_text()
+          .chars()
+          .filter(|c| !c.is_whitespace())
+          .map(|c| get_styled_letter(name, c))
+          .collect();
+        format!(r#"<mi mathvariant="normal">{}</mi>"#, escape_html(&text))
+      }
+      "left" => {
+        let open = self.read_delimiter();
+        let items = self.parse_row();
+        let close = match self.peek_command().as_deref() {
+          Some("right") => {
+            self.read_command();
+            self.read_delimiter()
+          }
+          _ => {
+            self.fail(format!(
+              "missing \\right before {}",
+              self.describe_position()
+            ));
+            String::new()
+          }
+        };
+        format!(
+          "<mrow>{}{}{}</mrow>",
+          get_fence(&open),
+          items.concat(),
+          get_fence(&close)
+        )
+      }
+      "begin" => self.parse_environment(),
+      "pmod" => format!(
+        "<mrow>{}<mo>mod</mo>{}{}</mrow>",
+        mo("("),
+        self.parse_argument(),
+        mo(")")
+      ),
+      "mod" | "bmod" => "<mo>mod</mo>".to_string(),
+      "displaystyle" | "textstyle" | "limits" | "nolimits" | "nonumber" => return None,
+      "{" | "}" | "%" | "$" | "&" | "#" | "_" | "|" => mo(match name {
+        "|" => "‖",
+        name => name,
+      }),
+      _ => {
+        self.fail(format!("unknown command \\{}", name));
+        return None;
+      }
+    };
+    Some(Atom::new(atom))
+  }
+
+  /// Delimiter of `\left` and `\right`, e.g.: `(`, `\{`, `\langle` or `.` (none)
+  fn read_delimiter(&mut self) -> String {
+    self.skip_whitespace();
+    match self.peek_command() {
+      Some(name) => {
+        self.read_command();
+        match name.as_str() {
+          "{" | "}" => name,
+          "|" | "Vert" => "‖".to_string(),
+          name => get_symbol(name)
+            .and_then(|symbol| {
+              let start = symbol.find('>')? + 1;
+              let end = symbol.rfind("</")?;
+              Some(symbol[start..end].to_string())
+            })
+            .unwrap_or_default(),
+        }
+      }
+      None => self
+        .next_char()
+        .filter(|c| *c != '.')
+        .map(|c| c.to_string())
+        .unwrap_or_default(),
+    }
+  }
+
+  /// Rows (`\\`) and cells (`&`) of `\begin{name} ... \end{name}`
+  fn parse_environment(&mut self) -> String {
+    let name = self.read_group_text();
+    if name == "array" {
+      self.read_group_text();
+    }
+
+    let mut rows: Vec<String> = Vec::new();
+    loop {
+      let mut cells: Vec<String> = Vec::new();
+      loop {
+        let cell = mrow(self.parse_row());
+        let align = match (name.as_str(), cells.len() % 2) {
+          ("cases", _) => r#" style="text-align: left""#,
+          ("aligned" | "align" | "align*" | "split", 0) => r#" style="text-align: right""#,
+          ("aligned" | "align" | "align*" | "split", _) => r#" style="text-align: left""#,
+          _ => "",
+        };
+        cells.push(format!("<mtd{}>{}</mtd>", align, cell));
+        match self.peek() {
+          Some('&') => self.position += 1,
+          _ => break,
+        }
+      }
+      rows.push(format!("<mtr>{}</mtr>", cells.concat()));
+
+      // Every iteration reads a row separator or stops, e.g.: at a stray `\right`
+      match self.peek_command().as_deref() {
+        _ if self.error.is_some() => break,
+        Some("\\" | "cr") => {
+          self.read_command();
+        }
+        Some("end") => {
+          self.read_command();
+          let end = self.read_group_text();
+          if end != name {
+            self.fail(format!(
+              "\\end{{{}}} does not close \\begin{{{}}}",
+              end, name
+            ));
+          }
+          break;
+        }
+        _ => {
+          self.fail(format!(
+            "missing \\end{{{}}} before {}",
+            name,
+            self.describe_position()
+          ));
+          break;
+        }
+      }
+    }
+
+    let table = format!("<mtable>{}</mtable>", rows.concat());
+    let (open, close) = match name.as_str() {
+      "pmatrix" => ("(", ")"),
+      "bmatrix" => ("[", "]"),
+      "Bmatrix" => ("{", "}"),
+      "vmatrix" => ("|", "|"),
+      "Vmatrix" => ("‖", "‖"),
+      "cases" => ("{", ""),
+      _ => return table,
+    };
+    format!(
+      "<mrow>{}{}{}</mrow>",
+      get_fence(open),
+      table,
+      get_fence(close)
+    )
+  }
+}
+
+/// Stretchy delimiter, empty for `.`
+fn get_fence(delimiter: &str) -> String {
+  match delimiter {
+    "" => String::new(),
+    delimiter => format!(
+      r#"<mo fence="true" stretchy="true">{}</mo>"#,
+      escape_html(delimiter)
+    ),
+  }
+}
+
+/// Functions written upright, e.g.: `\sin x`
+const FUNCTIONS: [&str; 24] = [
+  "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh",
+  "coth", "log", "ln", "lg", "exp", "det", "dim", "ker", "gcd", "arg", "deg", "hom",
+];
+
+/// Functions with limits under them in display formulas, e.g.: `\lim_{x \to 0}`
+const LIMIT_FUNCTIONS: [&str; 7] = ["lim", "max", "min", "sup", "inf", "limsup", "liminf"];
+
+fn get_greek_letter(name: &str) -> Option<&'static str> {
+  Some(match name {
+    "alpha" => "α",
+    "beta" => "β",
+    "gamma" => "γ",
+    "delta" => "δ",
+    "epsilon" => "ϵ",
+    "varepsilon" => "ε",
+    "zeta" => "ζ",
+    "eta" => "η",
+    "theta" => "θ",
+    "vartheta" => "ϑ",
+    "iota" => "ι",
+    "kappa" => "κ",
+    "lambda" => "λ",
+    "mu" => "μ",
+    "nu" => "ν",
+    "xi" => "ξ",
+    "pi" => "π",
+    "varpi" => "ϖ",
+    "rho" => "ρ",
+    "varrho" => "ϱ",
+    "sigma" => "σ",
+    "varsigma" => "ς",
+    "tau" => "τ",
+    "upsilon" => "υ",
+    "phi" => "ϕ",
+    "varphi" => "φ",
+    "chi" => "χ",
+    "psi" => "ψ",
+    "omega" => "ω",
+    "Gamma" => "Γ",
+    "Delta" => "Δ",
+    "Theta" => "Θ",
+    "Lambda" => "Λ",
+    "Xi" => "Ξ",
+    "Pi" => "Π",
+    "Sigma" => "Σ",
+    "Upsilon" => "Υ",
+    "Phi" => "Φ",
+    "Psi" => "Ψ",
+    "Omega" => "Ω",
+    _ => return None,
+  })
+}
+
+/// Operators and identifiers written with a command, e.g.: `\le`, `\infty`
+fn get_symbol(name: &str) -> Option<String> {
+  let identifier = match name {
+    "infty" => Some("∞"),
+    "partial" => Some("∂"),
+    "nabla" => Some("∇"),
+    "hbar" => Some("ℏ"),
+    "ell" => Some("ℓ"),
+    "emptyset" | "varnothing" => Some("∅"),
+    "aleph" => Some("ℵ"),
+    _ => None,
+  };
+  if let Some(identifier) = identifier {
+    return Some(format!("<mi>{}</mi>", identifier));
+  }
+
+  let operator = match name {
+    "pm" => "±",
+    "mp" => "∓",
+    "times" => "×",
+    "div" => "÷",
+    "cdot" => "⋅",
+    "cdots" => "⋯",
+    "ldots" | "dots" => "…",
+    "vdots" => "⋮",
+    "ddots" => "⋱",
+    "ast" => "∗",
+    "star" => "⋆",
+    "circ" => "∘",
+    "bullet" => "∙",
+    "le" | "leq" => "≤",
+    "ge" | "geq" => "≥",
+    "ne" | "neq" => "≠",
+    "approx" => "≈",
+    "equiv" => "≡",
+    "sim" => "∼",
+    "simeq" => "≃",
+    "cong" => "≅",
+    "propto" => "∝",
+    "ll" => "≪",
+    "gg" => "≫",
+    "in" => "∈",
+    "notin" => "∉",
+    "ni" => "∋",
+    "subset" => "⊂",
+    "subseteq" => "⊆",
+    "supset" => "⊃",
+    "supseteq" => "⊇",
+    "cup" => "∪",
+    "cap" => "∩",
+    "setminus" => "∖",
+    "forall" => "∀",
+    "exists" => "∃",
+    "neg" | "lnot" => "¬",
+    "land" | "wedge" => "∧",
+    "lor" | "vee" => "∨",
+    "oplus" => "⊕",
+    "otimes" => "⊗",
+    "to" | "rightarrow" => "→",
+    "leftarrow" | "gets" => "←",
+    "leftrightarrow" => "↔",
+    "Rightarrow" => "⇒",
+    "Leftarrow" => "⇐",
+    "Leftrightarrow" => "⇔",
+    "implies" => "⟹",
+    "iff" => "⟺",
+    "mapsto" => "↦",
+    "uparrow" => "↑",
+    "downarrow" => "↓",
+    "prime" => "′",
+    "angle" => "∠",
+    "perp" => "⊥",
+    "parallel" => "∥",
+    "mid" => "∣",
+    "langle" => "⟨",
+    "rangle" => "⟩",
+    "lfloor" => "⌊",
+    "rfloor" => "⌋",
+    "lceil" => "⌈",
+    "rceil" => "⌉",
+    "lvert" | "rvert" | "vert" => "|",
+    "Vert" | "lVert" | "rVert" => "‖",
+    "colon" => ":",
+    "triangle" => "△",
+    _ => return None,
+  };
+  Some(mo(operator))
+}
+
+/// Operators with limits, e.g.: `\sum_{i=0}^{n}`
+fn get_large_operator(name: &str) -> Option<&'static str> {
+  Some(match name {
+    "sum" => "∑",
+    "prod" => "∏",
+    "coprod" => "∐",
+    "bigcup" => "⋃",
+    "bigcap" => "⋂",
+    "bigoplus" => "⨁",
+    "bigotimes" => "⨂",
+    "int" => "∫",
+    "iint" => "∬",
+    "iiint" => "∭",
+    "oint" => "∮",
+    _ => return None,
+  })
+}
+
+fn get_accent(name: &str) -> Option<&'static str> {
+  Some(match name {
+    "hat" | "widehat" => "^",
+    "bar" | "overline" => "‾",
+    "underline" => "_",
+    "vec" => "→",
+    "dot" => "˙",
+    "ddot" => "¨",
+    "tilde" | "widetilde" => "~",
+    _ => return None,
+  })
+}
+
+fn get_space(name: &str) -> Option<&'static str> {
+  Some(match name {
+    "," => "0.1667em",
+    ":" | ">" => "0.2222em",
+    ";" => "0.2778em",
+    " " => "0.25em",
+    "quad" => "1em",
+    "qquad" => "2em",
+    "!" => "-0.1667em",
+    _ => return None,
+  })
+}
+
+/// Letter of the Unicode mathematical alphabets, e.g.: `ℝ` for `\mathbb{R}`
+fn get_styled_letter(style: &str, c: char) -> char {
+  let exception = match (style, c) {
+    ("mathbb", 'C') => Some('ℂ'),
+    ("mathbb", 'H') => Some('ℍ'),
+    ("mathbb", 'N') => Some('ℕ'),
+    ("mathbb", 'P') => Some('ℙ'),
+    ("mathbb", 'Q') => Some('ℚ'),
+    ("mathbb", 'R') => Some('ℝ'),
+    ("mathbb", 'Z') => Some('ℤ'),
+    ("mathcal", 'B') => Some('ℬ'),
+    ("mathcal", 'E') => Some('ℰ'),
+    ("mathcal", 'F') => Some('ℱ'),
+    ("mathcal", 'H') => Some('ℋ'),
+    ("mathcal", 'I') => Some('ℐ'),
+    ("mathcal", 'L') => Some('ℒ'),
+    ("mathcal", 'M') => Some('ℳ'),
+    ("mathcal", 'R') => Some('ℛ'),
+    _ => None,
+  };
+  if let Some(exception) = exception {
+    return exception;
+  }
+
+  // First capital letter, small letter and digit of each alphabet
+  let (upper, lower, digit) = match style {
+    "mathbb" => (0x1D538, 0x1D552, Some(0x1D7D8)),
+    "mathcal" => (0x1D49C, 0x1D4B6, None),
+    _ => (0x1D400, 0x1D41A, Some(0x1D7CE)),
+  };
+  let code = match c {
+    'A'..='Z' => upper + (c as u32 - 'A' as u32),
+    'a'..='z' if style != "mathcal" => lower + (c as u32 - 'a' as u32),
+    '0'..='9' => match digit {
+      Some(digit) => digit + (c as u32 - '0' as u32),
+      None => return c,
+    },
+    _ => return c,
+  };
+  char::from_u32(code).unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn mathml(tex: &str) -> String {
+    mrow(TexParser::new(tex).parse())
+  }
+
+  fn error(tex: &str) -> Option<String> {
+    let mut parser = TexParser::new(tex);
+    parser.parse();
+    parser.error
+  }
+
+  #[test]
+  fn test_extract_math() {
+    let markdown = "Area $\\pi r^2$ costs $5 and $10, `$x$`\n\n$$\nx_1\n$$\n```\n$y$\n```\n\\$z$\n\n    $code$\n- item\n\n    $w$\n";
+
+    let (markdown, formulas) = extract_math(markdown);
+
+    assert_eq!(
+      markdown,
+      "Area \u{FFF9}0\u{FFFB} costs $5 and $10, `$x$`\n\n\u{FFF9}1\u{FFFB}\n```\n$y$\n```\n\\$z$\n\n    $code$\n- item\n\n    \u{FFF9}2\u{FFFB}\n"
+    );
+    assert_eq!(
+      formulas,
+      vec![
+        Formula {
+          tex: "\\pi r^2".to_string(),
+          display: false
+        },
+        Formula {
+          tex: "\nx_1\n".to_string(),
+          display: true
+        },
+        Formula {
+          tex: "w".to_string(),
+          display: false
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_render_math() {
+    let formulas = vec![Formula {
+      tex: "x < 1".to_string(),
+      display: true,
+    }];
+
+    assert_eq!(
+      render_math("<p>\u{FFF9}0\u{FFFB}</p>\n", &formulas, "content/a.md").unwrap(),
+      "<math xmlns=\"http://www.w3.org/1998/Math/MathML\" class=\"math\" display=\"block\"><semantics><mrow><mi>x</mi><mo>&lt;</mo><mn>1</mn></mrow><annotation encoding=\"application/x-tex\">x &lt; 1</annotation></semantics></math>\n"
+    );
+  }
+
+  #[test]
+  fn test_to_mathml() {
+    assert_eq!(
+      mathml("\\frac{a}{b^2} - \\sqrt[3]{x}"),
+      "<mrow><mfrac><mrow><mi>a</mi></mrow><mrow><msup><mi>b</mi><mn>2</mn></msup></mrow></mfrac><mo>−</mo><mroot><mrow><mi>x</mi></mrow><mn>3</mn></mroot></mrow>"
+    );
+    assert_eq!(
+      mathml("\\sum_{i=1}^n x_i'"),
+      "<mrow><munderover><mo>∑</mo><mrow><mi>i</mi><mo>=</mo><mn>1</mn></mrow><mi>n</mi></munderover><msubsup><mi>x</mi><mi>i</mi><mo>′</mo></msubsup></mrow>"
+    );
+    assert_eq!(
+      mathml("\\left( \\alpha \\in \\mathbb{R} \\right)"),
+      "<mrow><mo fence=\"true\" stretchy=\"true\">(</mo><mi>α</mi><mo>∈</mo><mi mathvariant=\"normal\">ℝ</mi><mo fence=\"true\" stretchy=\"true\">)</mo></mrow>"
+    );
+    assert_eq!(
+      mathml("\\begin{pmatrix} 1 & 0 \\\\ 0 & 1 \\end{pmatrix}"),
+      "<mrow><mo fence=\"true\" stretchy=\"true\">(</mo><mtable><mtr><mtd><mn>1</mn></mtd><mtd><mn>0</mn></mtd></mtr><mtr><mtd><mn>0</mn></mtd><mtd><mn>1</mn></mtd></mtr></mtable><mo fence=\"true\" stretchy=\"true\">)</mo></mrow>"
+    );
+    assert_eq!(
+      mathml("\\text{if } x"),
+      "<mrow><mtext>if </mtext><mi>x</mi></mrow>"
+    );
+  }
+
+  #[test]
+  fn test_to_mathml_errors() {
+    assert_eq!(
+      error("\\begin{matrix} a \\right) \\end{matrix}"),
+      Some("missing \\end{matrix} before \\right".to_string())
+    );
+    assert_eq!(
+      error("\\begin{matrix} a \\end{pmatrix}"),
+      Some("\\end{pmatrix} does not close \\begin{matrix}".to_string())
+    );
+    assert_eq!(
+      error("\\left( x"),
+      Some("missing \\right before the end of the formula".to_string())
+    );
+    assert_eq!(error("x \\right)"), Some("unexpected \\right".to_string()));
+    assert_eq!(
+      error("{x"),
+      Some("missing } before the end of the formula".to_string())
+    );
+    assert_eq!(
+      error("x \\unknown"),
+      Some("unknown command \\unknown".to_string())
+    );
+    assert_eq!(
+      error("\\sqrt[\\foo]{x}"),
+      Some("unknown command \\foo".to_string())
+    );
+    assert_eq!(error("\\begin{cases} x & 1 \\\\ y & 2 \\end{cases}"), None);
+  }
+
+  #[test]
+  fn test_render_math_errors() {
+    let formulas = vec![Formula {
+      tex: " \\begin{matrix} a \\right) \\end{matrix} ".to_string(),
+      display: false,
+    }];
+
+    assert_eq!(
+      render_math("<p>\u{FFF9}0\u{FFFB}</p>", &formulas, "content/a.md")
+        .unwrap_err()
+        .to_string(),
+      "Invalid formula in content/a.md: $\\begin{matrix} a \\right) \\end{matrix}$, missing \\end{matrix} before \\right"
+    );
+  }
+}
@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+use crate::{captions::Caption, toc::Toc};
+
+lazy_static! {
+    /// Match headings ending with a label, e.g.: `## Setup {#setup}`
+    ///
+    /// Expect the capture naming groups:
+    /// - l: for the heading level
+    /// - t: for the heading content, without the label
+    /// - id: for the label
+    static ref REG_HEADING_LABEL: Regex = Regex::new(r#"<h(?P<l>[1-6])><a href="[^"]*" aria-hidden="true" class="anchor" id="[^"]*"></a>(?P<t>[^\n]*?)\s*\{#(?P<id>[A-Za-z][A-Za-z0-9_:.-]*)\}</h[1-6]>"#).unwrap();
+
+    /// Match images followed by a label, e.g.: `![Diagram](diagram.png){#fig-diagram}`
+    ///
+    /// Expect the capture naming groups:
+    /// - a: for the image attributes
+    /// - id: for the label
+    static ref REG_IMAGE_LABEL: Regex = Regex::new(r#"<img (?P<a>[^>]*?)\s*/?>\{#(?P<id>[A-Za-z][A-Za-z0-9_:.-]*)\}"#).unwrap();
+
+    /// Match the alt text of image attributes
+    static ref REG_IMAGE_ALT: Regex = Regex::new(r#"alt="(?P<alt>[^"]*)""#).unwrap();
+
+    /// Match a label paragraph written after a table, alone or after
+    /// its caption, e.g.: `{#tab-prices}`, `Table: Prices {#tab-prices}`
+    ///
+    /// Expect the capture naming groups:
+    /// - c: for the caption paragraph text
+    /// - id: for the label
+    static ref REG_TABLE_LABEL: Regex = Regex::new(r#"</table>\s*<p>(?P<c>Table:[^\n]*?)?\s*\{#(?P<id>[A-Za-z][A-Za-z0-9_:.-]*)\}</p>"#).unwrap();
+}
+
+/// Element identified by a label
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelKind {
+  Heading,
+  Figure,
+  Table,
+}
+
+/// A `{#label}` written after a heading, image or table
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+  /// Id of the element, e.g.: `setup`
+  pub id: String,
+  pub kind: LabelKind,
+  /// Heading title, image alt text or caption
+  pub title: String,
+  /// Number of the heading or caption, e.g.: `3.2`, `Figure 2`
+  pub number: Option<String>,
+}
+
+impl Label {
+  fn new(id: &str, kind: LabelKind, title: &str) -> Self {
+    Self {
+      id: id.to_string(),
+      kind,
+      title: title.to_string(),
+      number: None,
+    }
+  }
+
+  /// Text of the references, e.g.: `3.2 Setup` or `Figure 2`.
+  /// The label is used without number and title
+  pub fn get_text(&self) -> String {
+    match (&self.number, self.title.is_empty()) {
+      (Some(number), _) if self.kind != LabelKind::Heading => number.clone(),
+      (_, true) => self.id.clone(),
+      (Some(number), false) => format!("{} {}", number, self.title),
+      (None, false) => self.title.clone(),
+    }
+  }
+
+  /// Replace the `{#label}` of headings, images and tables with element ids.
+  ///
+  /// Return the HTML and the labels found. Heading titles are read
+  /// later with [Label::set_headings], once headings are numbered
+  pub fn parse_html(html: &str) -> (String, Vec<Self>) {
+    let mut labels = Vec::new();
+
+    let html = REG_HEADING_LABEL.replace_all(html, |m: &Captures| {
+      labels.push(Self::new(&m["id"], LabelKind::Heading, ""));
+      format!(
+        r##"<h{l}><a href="#{id}" aria-hidden="true" class="anchor" id="{id}"></a>{t}</h{l}>"##,
+        l = &m["l"],
+        id = &m["id"],
+        t = &m["t"]
+      )
+    });
+
+    let html = REG_IMAGE_LABEL.replace_all(&html, |m: &Captures| {
+      let alt = REG_IMAGE_ALT
+        .captures(&m["a"])
+        .map(|alt| alt["alt"].to_string())
+        .unwrap_or_default();
+      labels.push(Self::new(&m["id"], LabelKind::Figure, &alt));
+      format!(r#"<img id="{}" {} />"#, &m["id"], &m["a"])
+    });
+
+    // Tables are found backwards from their label, markdown tables can not be nested
+    let mut output = String::new();
+    let mut last = 0;
+    for m in REG_TABLE_LABEL.captures_iter(&html) {
+      let end = m.get(0).unwrap();
+      let start = match html[last..end.start()].rfind("<table>") {
+        Some(start) => last + start,
+        None => continue,
+      };
+      let caption = m.name("c").map(|c| c.as_str()).unwrap_or_default();
+      labels.push(Self::new(
+        &m["id"],
+        LabelKind::Table,
+        caption.trim_start_matches("Table:").trim(),
+      ));
+      output.push_str(&html[last..start]);
+      output.push_str(&format!(r#"<table id="{}">"#, &m["id"]));
+      output.push_str(&html[start + "<table>".len()..end.start()]);
+      output.push_str("</table>");
+      if !caption.is_empty() {
+        output.push_str(&format!("\n<p>{}</p>", caption));
+      }
+      last = end.end();
+    }
+    output.push_str(&html[last..]);
+
+    (output, labels)
+  }
+
+  /// Set the title and number of heading labels from the headings of their chapter
+  pub fn set_headings(labels: &mut [Self], toc: &Toc) {
+    for label in labels.iter_mut().filter(|l| l.kind == LabelKind::Heading) {
+      if let Some(entry) = toc.entries().iter().find(|e| e.id == label.id) {
+        label.title = entry.title.clone();
+        label.number = entry.number.clone();
+      }
+    }
+  }
+
+  /// Set the number and title of figure and table labels from their caption
+  pub fn set_captions(labels: &mut [Self], captions: &[Caption]) {
+    for label in labels.iter_mut() {
+      if let Some(caption) = captions.iter().find(|c| c.id == label.id) {
+        label.title = caption.title.clone();
+        label.number = Some(caption.number.clone());
+      }
+    }
+  }
+}
+
+/// Target of `@ref(label)` tags
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+  /// Link to the element, e.g.: `setup.html#setup`
+  pub href: String,
+  /// Text of the link, e.g.: `3.2 Setup`
+  pub text: String,
+}
+
+/// Labels of a document, by id
+#[derive(Debug, Clone, Default)]
+pub struct References {
+  entries: HashMap<String, Reference>,
+  /// Add the page of the element to references, filled while printing the PDF
+  pages: bool,
+}
+
+impl References {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Add page numbers to references
+  pub fn with_pages(mut self, pages: bool) -> Self {
+    self.pages = pages;
+    self
+  }
+
+  pub fn has_pages(&self) -> bool {
+    self.pages
+  }
+
+  /// Add a label, linked with `href`
+  pub fn insert(&mut self, label: &Label, href: &str) {
+    self.entries.insert(
+      label.id.clone(),
+      Reference {
+        href: href.to_string(),
+        text: label.get_text(),
+      },
+    );
+  }
+
+  pub fn get(&self, id: &str) -> Option<&Reference> {
+    self.entries.get(id)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_html() {
+    let html = concat!(
+      r##"<h2><a href="#header-id-setup-setup" aria-hidden="true" class="anchor" id="header-id-setup-setup"></a>Setup {#setup}</h2>"##,
+      "\n",
+      r#"<p><img src="a.png" alt="A diagram" />{#fig-a}</p>"#,
+      "\n<table>\n<tr><td>1</td></tr>\n</table>\n<table>\n<tr><td>2</td></tr>\n</table>\n<p>{#tab-b}</p>\n",
+      "<table>\n</table>\n<p>Table: Prices {#tab-c}</p>\n"
+    );
+
+    let (html, labels) = Label::parse_html(html);
+
+    assert_eq!(
+      html,
+      concat!(
+        r##"<h2><a href="#setup" aria-hidden="true" class="anchor" id="setup"></a>Setup</h2>"##,
+        "\n",
+        r#"<p><img id="fig-a" src="a.png" alt="A diagram" /></p>"#,
+        "\n<table>\n<tr><td>1</td></tr>\n</table>\n<table id=\"tab-b\">\n<tr><td>2</td></tr>\n</table>\n",
+        "<table id=\"tab-c\">\n</table>\n<p>Table: Prices</p>\n"
+      )
+    );
+    assert_eq!(
+      labels,
+      vec![
+        Label::new("setup", LabelKind::Heading, ""),
+        Label::new("fig-a", LabelKind::Figure, "A diagram"),
+        Label::new("tab-b", LabelKind::Table, ""),
+        Label::new("tab-c", LabelKind::Table, "Prices"),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_set_headings() {
+    let toc = Toc::from_html(
+      r##"<h2><a href="#setup" aria-hidden="true" class="anchor" id="setup"></a><span class="heading-number" data-number="3.2">3.2</span> Setup</h2>"##,
+    );
+    let mut labels = vec![
+      Label::new("setup", LabelKind::Heading, ""),
+      Label::new("tab-b", LabelKind::Table, ""),
+    ];
+
+    Label::set_headings(&mut labels, &toc);
+
+    assert_eq!(labels[0].get_text(), "3.2 Setup");
+    assert_eq!(labels[1].get_text(), "tab-b");
+  }
+}
@@ -0,0 +1,17 @@
+//! Helpers shared by the unit tests
+
+use std::fs::{create_dir_all, write};
+
+use tempfile::TempDir;
+
+/// Create a temporary folder with the given files and contents.
+/// The folder is removed when dropped, even if an assertion fails
+pub fn create_files(files: &[(&str, &str)]) -> TempDir {
+  let dir = tempfile::tempdir().unwrap();
+  for (file, content) in files {
+    let file = dir.path().join(file);
+    create_dir_all(file.parent().unwrap()).unwrap();
+    write(file, content).unwrap();
+  }
+  dir
+}
@@ -0,0 +1,425 @@
+use std::collections::HashSet;
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+use crate::utils::escape_html;
+
+lazy_static! {
+    /// Match headings rendered by comrak with `header_ids` enabled
+    ///
+    /// Expect the capture naming groups:
+    /// - l: for the heading level
+    /// - id: for the anchor id
+    /// - t: for the heading content
+    static ref REG_HEADING: Regex = Regex::new(r#"<h(?P<l>[1-6])>(?P<a><a href="[^"]*" aria-hidden="true" class="anchor" id="(?P<id>[^"]*)"></a>)(?s)(?P<t>.*?)</h[1-6]>"#).unwrap();
+
+    /// Match heading anchors, used to make their ids unique across chapters
+    ///
+    /// Expect the capture naming groups:
+    /// - p: for the opening heading tag
+    /// - id: for the anchor id
+    static ref REG_HEADING_ID: Regex = Regex::new(r#"(?P<p><h[1-6]>)<a href="[^"]*" aria-hidden="true" class="anchor" id="(?P<id>[^"]*)""#).unwrap();
+
+    /// Match the number added to headings by [HeadingNumbers]
+    ///
+    /// Expect the capture naming groups:
+    /// - n: for the number, e.g.: `3.2`
+    static ref REG_HEADING_NUMBER: Regex = Regex::new(r#"^<span class="heading-number" data-number="(?P<n>[^"]*)">[^<]*</span>\s*"#).unwrap();
+
+    /// Match any html tag
+    static ref REG_HTML_TAG: Regex = Regex::new(r"<[^>]*>").unwrap();
+}
+
+/// A single heading found in the generated content
+#[derive(Debug, Clone, PartialEq)]
+pub struct TocEntry {
+  /// Heading level (1 for h1, 2 for h2...)
+  pub level: u8,
+  /// Anchor id used to link to the heading
+  pub id: String,
+  /// Heading text, without html tags
+  pub title: String,
+  /// Heading number, e.g.: `3.2`, when numbering is enabled
+  pub number: Option<String>,
+}
+
+impl TocEntry {
+  /// Title with its number, e.g.: `3.2 Setup`
+  pub fn get_numbered_title(&self) -> String {
+    match &self.number {
+      Some(number) => format!("{} {}", number, self.title),
+      None => self.title.clone(),
+    }
+  }
+}
+
+/// Table of contents built from the headings of the generated content
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Toc {
+  entries: Vec<TocEntry>,
+}
+
+impl Toc {
+  /// Collect all headings from an HTML content
+  pub fn from_html(html: &str) -> Self {
+    let entries = REG_HEADING
+      .captures_iter(html)
+      .map(|m| {
+        let number = REG_HEADING_NUMBER.captures(&m["t"]);
+        let title = match &number {
+          Some(number) => &m["t"][number.get(0).unwrap().end()..],
+          None => &m["t"],
+        };
+
+        TocEntry {
+          level: m["l"].parse().unwrap_or(1),
+          id: m["id"].to_string(),
+          title: REG_HTML_TAG.replace_all(title, "").trim().to_string(),
+          number: number.map(|number| number["n"].to_string()),
+        }
+      })
+      .collect();
+
+    Self { entries }
+  }
+
+  /// Make heading ids unique across chapters.
+  ///
+  /// Every chapter is rendered on its own, so two chapters with the same
+  /// heading text end up with the same id. Duplicated ids get a numeric suffix.
+  pub fn unique_heading_ids(chapters: &[String]) -> Vec<String> {
+    let mut ids = HashSet::<String>::new();
+
+    chapters
+      .iter()
+      .map(|html| {
+        REG_HEADING_ID
+          .replace_all(html, |m: &Captures| {
+            let id = &m["id"];
+            let mut unique_id = id.to_string();
+            let mut suffix = 0;
+            while ids.contains(&unique_id) {
+              suffix += 1;
+              unique_id = format!("{}-{}", id, suffix);
+            }
+            ids.insert(unique_id.clone());
+            format!(
+              r##"{p}<a href="#{id}" aria-hidden="true" class="anchor" id="{id}""##,
+              p = &m["p"],
+              id = unique_id
+            )
+          })
+          .to_string()
+      })
+      .collect()
+  }
+
+  /// Replace the title of the first h1 heading, e.g.: with the title of the front matter.
+  /// The title is plain text, escaped like the heading titles
+  pub fn set_chapter_title(&mut self, title: &str) {
+    if let Some(entry) = self.entries.iter_mut().find(|entry| entry.level == 1) {
+      entry.title = escape_html(title);
+    }
+  }
+
+  pub fn entries(&self) -> &[TocEntry] {
+    &self.entries
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Build the table of contents HTML, including headings up to `depth`
+  pub fn to_html(&self, title: &str, depth: u8) -> String {
+    let mut html = String::new();
+
+    html.push_str(r#"<div class="mpdf_toc toc" id="toc">"#);
+    if !title.is_empty() {
+      html.push_str(&format!(
+        r#"<div id="toc-title">{}</div>"#,
+        escape_html(title)
+      ));
+    }
+
+    for entry in self.entries.iter().filter(|e| e.level <= depth) {
+      let level = entry.level - 1;
+      html.push_str(&format!(
+        r##"<div class="mpdf_toc_level_{l}"><a class="mpdf_toc_a" href="#{id}"><span class="mpdf_toc_t_level_{l}">{t}</span></a></div>"##,
+        l = level,
+        id = entry.id,
+        t = entry.get_numbered_title()
+      ));
+    }
+
+    html.push_str("</div>");
+    html
+  }
+
+  /// Add an entry after the current ones, e.g.: a chapter without headings
+  pub fn push(&mut self, entry: TocEntry) {
+    self.entries.push(entry);
+  }
+
+  /// Append the entries of another table of contents
+  pub fn extend(&mut self, other: Toc) {
+    self.entries.extend(other.entries);
+  }
+
+  /// Build nested ordered lists (`<ol>`), including headings up to `depth`.
+  ///
+  /// `href` returns the link of each entry, so entries can point to other files.
+  pub fn to_list_html<F>(&self, depth: u8, href: F) -> String
+  where
+    F: Fn(&TocEntry) -> String,
+  {
+    let mut html = String::new();
+    // Levels of the lists currently open
+    let mut levels: Vec<u8> = Vec::new();
+
+    for entry in self.entries.iter().filter(|e| e.level <= depth) {
+      match levels.last() {
+        Some(level) if entry.level <= *level => {
+          while levels.len() > 1 && entry.level < *levels.last().unwrap() {
+            html.push_str("</li></ol>");
+            levels.pop();
+          }
+          html.push_str("</li>");
+        }
+        _ => {
+          html.push_str("<ol>");
+          levels.push(entry.level);
+        }
+      }
+
+      html.push_str(&format!(
+        r#"<li><a href="{}">{}</a>"#,
+        href(entry),
+        entry.get_numbered_title()
+      ));
+    }
+
+    for _ in levels {
+      html.push_str("</li></ol>");
+    }
+
+    html
+  }
+
+  /// Build hidden links to every heading.
+  ///
+  /// Chrome only writes named destinations (used to find the page of each
+  /// heading in the printed PDF) for elements that are targets of a link.
+  pub fn to_anchors_html(&self) -> String {
+    let links: String = self
+      .entries
+      .iter()
+      .map(|entry| format!(r##"<a href="#{}"></a>"##, entry.id))
+      .collect();
+    format!(
+      r#"<nav class="toc-anchors" style="display:none;">{}</nav>"#,
+      links
+    )
+  }
+}
+
+/// Numbers of chapters and sections, counted across chapters
+#[derive(Debug, Clone)]
+pub struct HeadingNumbers {
+  /// Current number of every heading level
+  counters: [u32; 6],
+  /// Deepest heading level numbered
+  depth: u8,
+  /// Number displayed in h1 headings, e.g.: `Chapter {n}`
+  chapter_label: String,
+}
+
+impl HeadingNumbers {
+  pub fn new(depth: u8, chapter_label: &str) -> Self {
+    Self {
+      counters: [0; 6],
+      depth,
+      chapter_label: chapter_label.to_string(),
+    }
+  }
+
+  /// Number of a heading, `None` when it is deeper than `depth`
+  /// or a parent heading is missing, e.g.: an h3 before any h2
+  fn next(&mut self, level: usize) -> Option<String> {
+    self.counters[level - 1] += 1;
+    self.counters[level..].fill(0);
+
+    let counters = &self.counters[..level];
+    if level > self.depth as usize || counters.contains(&0) {
+      return None;
+    }
+    let numbers: Vec<String> = counters.iter().map(|n| n.to_string()).collect();
+    Some(numbers.join("."))
+  }
+
+  /// Add numbers to the headings of a chapter: h1 display the chapter label,
+  /// e.g.: `Chapter 3`, next levels the section number, e.g.: `3.2.1`
+  pub fn number_html(&mut self, html: &str) -> String {
+    REG_HEADING
+      .replace_all(html, |m: &Captures| {
+        let level: usize = m["l"].parse().unwrap_or(1);
+        let number = match self.next(level) {
+          Some(number) => number,
+          None => return m[0].to_string(),
+        };
+        let label = match level {
+          1 => self.chapter_label.replace("{n}", &number),
+          _ => number.clone(),
+        };
+
+        format!(
+          r#"<h{l}>{a}<span class="heading-number" data-number="{n}">{label}</span> {t}</h{l}>"#,
+          l = level,
+          a = &m["a"],
+          n = number,
+          label = label,
+          t = &m["t"]
+        )
+      })
+      .to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn heading(level: u8, id: &str, title: &str) -> String {
+    format!(
+      r##"<h{l}><a href="#{id}" aria-hidden="true" class="anchor" id="header-id-{id}"></a>{t}</h{l}>"##,
+      l = level,
+      id = id,
+      t = title
+    )
+  }
+
+  #[test]
+  fn test_from_html() {
+    let html = format!(
+      "{}<p>text</p>{}{}",
+      heading(1, "intro", "Intro"),
+      heading(2, "setup", "Set <code>up</code>"),
+      heading(3, "more", "More")
+    );
+
+    let toc = Toc::from_html(&html);
+
+    assert_eq!(
+      toc.entries(),
+      &[
+        TocEntry {
+          level: 1,
+          id: "header-id-intro".to_string(),
+          title: "Intro".to_string(),
+          number: None,
+        },
+        TocEntry {
+          level: 2,
+          id: "header-id-setup".to_string(),
+          title: "Set up".to_string(),
+          number: None,
+        },
+        TocEntry {
+          level: 3,
+          id: "header-id-more".to_string(),
+          title: "More".to_string(),
+          number: None,
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_unique_heading_ids() {
+    let chapters = [heading(1, "intro", "Intro"), heading(1, "intro", "Intro")];
+
+    let html = Toc::unique_heading_ids(&chapters).join("");
+    let toc = Toc::from_html(&html);
+
+    assert_eq!(toc.entries()[0].id, "header-id-intro");
+    assert_eq!(toc.entries()[1].id, "header-id-intro-1");
+    assert!(html.contains(r##"<a href="#header-id-intro-1" aria-hidden="true""##));
+  }
+
+  #[test]
+  fn test_set_chapter_title() {
+    let html = format!("{}{}", heading(2, "a", "A"), heading(1, "b", "B"));
+    let mut toc = Toc::from_html(&html);
+
+    toc.set_chapter_title("Tom & <Jerry>");
+
+    assert_eq!(toc.entries()[0].title, "A");
+    assert_eq!(toc.entries()[1].title, "Tom &amp; &lt;Jerry&gt;");
+  }
+
+  #[test]
+  fn test_heading_numbers() {
+    let mut numbers = HeadingNumbers::new(2, "Chapter {n}");
+    let first = format!(
+      "{}{}{}{}",
+      heading(2, "a", "A"),
+      heading(1, "b", "B"),
+      heading(2, "c", "C"),
+      heading(3, "d", "D")
+    );
+    let second = format!("{}{}", heading(1, "e", "E"), heading(2, "f", "F"));
+
+    let html = format!(
+      "{}{}",
+      numbers.number_html(&first),
+      numbers.number_html(&second)
+    );
+    let toc = Toc::from_html(&html);
+
+    let titles: Vec<String> = toc
+      .entries()
+      .iter()
+      .map(|e| e.get_numbered_title())
+      .collect();
+    assert_eq!(titles, ["A", "1 B", "1.1 C", "D", "2 E", "2.1 F"]);
+    assert!(
+      html.contains(r#"<span class="heading-number" data-number="2">Chapter 2</span> E</h1>"#)
+    );
+    assert_eq!(toc.entries()[4].title, "E");
+  }
+
+  #[test]
+  fn test_to_html_respects_depth() {
+    let html = format!("{}{}", heading(1, "a", "A"), heading(3, "b", "B"));
+    let toc_html = Toc::from_html(&html).to_html("Q&A <Contents>", 2);
+
+    assert!(toc_html.contains(r#"<div id="toc-title">Q&amp;A &lt;Contents&gt;</div>"#));
+    assert!(toc_html.contains(r##"href="#header-id-a""##));
+    assert!(!toc_html.contains(r##"href="#header-id-b""##));
+  }
+
+  #[test]
+  fn test_to_list_html() {
+    let html = format!(
+      "{}{}{}{}",
+      heading(1, "a", "A"),
+      heading(2, "b", "B"),
+      heading(3, "c", "C"),
+      heading(1, "d", "D")
+    );
+
+    let list_html = Toc::from_html(&html).to_list_html(3, |e| format!("page.html#{}", e.id));
+
+    assert_eq!(
+      list_html,
+      concat!(
+        r#"<ol><li><a href="page.html#header-id-a">A</a>"#,
+        r#"<ol><li><a href="page.html#header-id-b">B</a>"#,
+        r#"<ol><li><a href="page.html#header-id-c">C</a></li></ol>"#,
+        r#"</li></ol></li>"#,
+        r#"<li><a href="page.html#header-id-d">D</a></li></ol>"#
+      )
+    );
+  }
+}
@@ -0,0 +1,134 @@
+use std::{
+  fs::{copy, create_dir_all, read_dir},
+  path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+
+/// Escape text before injecting it into HTML/XML documents
+pub fn escape_html(value: &str) -> String {
+  value
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+/// Revert [escape_html], e.g.: to use HTML text in PDF metadata
+pub fn unescape_html(value: &str) -> String {
+  value
+    .replace("&quot;", "\"")
+    .replace("&#39;", "'")
+    .replace("&gt;", ">")
+    .replace("&lt;", "<")
+    .replace("&amp;", "&")
+}
+
+/// FNV-1a hash, stable across builds and Rust versions, e.g.: for cache keys and identifiers
+pub fn get_hash(bytes: &[u8]) -> String {
+  let hash = bytes.iter().fold(0xcbf29ce484222325_u64, |hash, byte| {
+    (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+  });
+  format!("{:016x}", hash)
+}
+
+/// List all files in a folder and its sub folders, sorted by path
+pub fn get_files(dir: &Path) -> Result<Vec<PathBuf>> {
+  let mut files = Vec::new();
+  if !dir.exists() {
+    return Ok(files);
+  }
+
+  let mut entries = read_dir(dir)?
+    .map(|entry| entry.map(|entry| entry.path()))
+    .collect::<Result<Vec<_>, _>>()?;
+  entries.sort();
+
+  for entry in entries {
+    if entry.is_dir() {
+      files.extend(get_files(&entry)?);
+    } else {
+      files.push(entry);
+    }
+  }
+
+  Ok(files)
+}
+
+/// Copy files from a folder to another one, keeping the folder structure.
+/// Files rejected by `filter` are skipped.
+pub fn copy_files<F>(from: &Path, to: &Path, filter: F) -> Result<()>
+where
+  F: Fn(&Path) -> bool,
+{
+  for file in get_files(from)? {
+    if !filter(&file) {
+      continue;
+    }
+
+    let target = to.join(file.strip_prefix(from)?);
+    if let Some(parent) = target.parent() {
+      create_dir_all(parent)?;
+    }
+    copy(&file, &target)?;
+  }
+
+  Ok(())
+}
+
+/// Return the media type of a resource file (stylesheets, scripts, images and fonts),
+/// or `None` if the file type is not supported
+pub fn get_media_type(path: &Path) -> Option<&'static str> {
+  let extension = path.extension()?.to_str()?.to_lowercase();
+  let media_type = match extension.as_str() {
+    "xhtml" => "application/xhtml+xml",
+    "css" => "text/css",
+    "js" => "text/javascript",
+    "jpg" | "jpeg" => "image/jpeg",
+    "png" => "image/png",
+    "gif" => "image/gif",
+    "svg" => "image/svg+xml",
+    "ttf" => "font/ttf",
+    "otf" => "font/otf",
+    "woff" => "font/woff",
+    "woff2" => "font/woff2",
+    _ => return None,
+  };
+  Some(media_type)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_get_media_type() {
+    assert_eq!(
+      get_media_type(Path::new("fonts/Ubuntu-Bold.ttf")),
+      Some("font/ttf")
+    );
+    assert_eq!(
+      get_media_type(Path::new("images/cover.JPG")),
+      Some("image/jpeg")
+    );
+    assert_eq!(
+      get_media_type(Path::new("js/mermaid.min.js")),
+      Some("text/javascript")
+    );
+    assert_eq!(get_media_type(Path::new("fonts/OFL.txt")), None);
+  }
+
+  #[test]
+  fn test_get_hash() {
+    assert_eq!(get_hash(b""), "cbf29ce484222325");
+    assert_eq!(get_hash(b"a"), "af63dc4c8601ec8c");
+  }
+
+  #[test]
+  fn test_escape_html() {
+    assert_eq!(
+      escape_html(r#"<a href="x">Tom & Jerry</a>"#),
+      "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&lt;/a&gt;"
+    );
+  }
+}